@@ -0,0 +1,228 @@
+//! Loads persistent settings from a TOML config file.
+//!
+//! The file holds the same settings as the command line, using the long argument names:
+//!
+//! ```toml
+//! vrc_port = 9000
+//! osc_port = 9002
+//! avatar = "/path/to/avatar.json"
+//! expression_hold = ["JawOpen", "MouthClosed"]
+//!
+//! # The face setup, either as a plain name or with its options.
+//! face = { source = "babble", listen = 9400 }
+//! ```
+//!
+//! The settings are turned into command-line arguments and placed in front of the real ones,
+//! so that flags given on the command line override the file.
+//! Every setting is checked against the arguments first, so that an unknown name or a bad value
+//! is reported with the file and the setting, instead of as a confusing command-line error.
+
+use std::{
+    ffi::{OsStr, OsString},
+    fs,
+};
+
+use clap::{parser::ValueSource, Arg, ArgAction, ArgMatches, Command, CommandFactory};
+use toml::{Table, Value};
+
+use crate::{core::folders::CONFIG_DIR, Args};
+
+/// Returns the command-line arguments with the config file's settings merged in.
+///
+/// The file is taken from `--config <path>`, or `$CONFIG_DIR/oscavmgr.toml` by default.
+/// A missing file is silently ignored, but a file that can't be read or parsed is reported.
+pub fn args_with_config() -> Vec<OsString> {
+    let mut cli: Vec<OsString> = std::env::args_os().collect();
+    if cli.is_empty() {
+        return cli;
+    }
+    let bin = cli.remove(0);
+
+    let path =
+        config_path(&cli).unwrap_or_else(|| format!("{}/oscavmgr.toml", CONFIG_DIR.as_ref()));
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            log::debug!("No config file at {}", path);
+            return [vec![bin], cli].concat();
+        }
+        Err(e) => {
+            log::error!("Could not read config file {}: {:?}", path, e);
+            return [vec![bin], cli].concat();
+        }
+    };
+
+    let table = match content.parse::<Table>() {
+        Ok(table) => table,
+        Err(e) => {
+            log::error!("Could not parse config file {}: {}", path, e);
+            return [vec![bin], cli].concat();
+        }
+    };
+
+    log::info!("Loaded config file {}", path);
+
+    let cmd = Args::command();
+    // The command line on its own, to tell which settings it overrides. Repeated flags like `--vrc-port`
+    // add up instead of replacing each other, so the file's values must be left out rather than overridden.
+    // Only a subcommand clap actually parses counts, not e.g. the value of `--avatar stdin`.
+    let cli_matches = cmd
+        .clone()
+        .subcommand_required(false)
+        .arg_required_else_help(false)
+        .try_get_matches_from(std::iter::once(&bin).chain(cli.iter()))
+        .ok();
+    let cli_has_face = cli_matches
+        .as_ref()
+        .is_some_and(|matches| matches.subcommand_name().is_some());
+
+    let mut args = vec![bin];
+    let mut face = vec![];
+    let mut valid = true;
+
+    for (key, value) in table.iter() {
+        let result = if key == "face" {
+            face_args(&cmd, value).map(|args| face = args)
+        } else {
+            push_arg(&mut args, &cmd, cli_matches.as_ref(), key, value)
+        };
+        if let Err(e) = result {
+            log::error!("Config file {}: {}: {}", path, key, e);
+            valid = false;
+        }
+    }
+    if !valid {
+        std::process::exit(1);
+    }
+
+    // Settings that the command line also sets were left out above, so the command line wins.
+    args.extend(cli);
+
+    // The subcommand has to come last. It is only taken from the file if none was given.
+    if !cli_has_face {
+        args.extend(face);
+    }
+
+    args
+}
+
+/// Finds the value of `--config` on the command line.
+fn config_path(cli: &[OsString]) -> Option<String> {
+    let mut iter = cli.iter().map(|arg| arg.to_string_lossy());
+    while let Some(arg) = iter.next() {
+        if arg == "--config" {
+            return iter.next().map(|path| path.into_owned());
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(path.to_string());
+        }
+    }
+    None
+}
+
+/// Converts a single setting into command-line arguments, after checking it against the argument.
+/// Arrays become a comma-separated list. Bools of plain flags (e.g. `dry_run`) become the flag if `true`
+/// and are left out if `false`, while flags that take a value (e.g. `enable_flight`) get `=true` or `=false`.
+/// Nothing is added if `cli` (the parsed command line) already sets the argument.
+fn push_arg(
+    args: &mut Vec<OsString>,
+    cmd: &Command,
+    cli: Option<&ArgMatches>,
+    key: &str,
+    value: &Value,
+) -> Result<(), String> {
+    let long = key.replace('_', "-");
+    let flag = format!("--{}", long);
+    let Some(arg) = cmd
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(long.as_str()))
+    else {
+        return Err("unknown setting".into());
+    };
+
+    // Still checked, so that a bad value in the file is reported even while it's overridden.
+    let overridden = cli.is_some_and(|cli| {
+        cli.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+    });
+    let mut flags: Vec<OsString> = vec![];
+
+    match (value, arg.get_action()) {
+        (Value::Boolean(true), ArgAction::SetTrue) => flags.push(flag.into()),
+        (Value::Boolean(false), ArgAction::SetTrue) => {}
+        (_, ArgAction::SetTrue) => return Err("must be true or false".into()),
+        (Value::Table(_), _) => return Err("can't be a table".into()),
+        (value, _) => {
+            let delimiter = arg.get_value_delimiter();
+            let value = match value {
+                Value::Array(_) if delimiter.is_none() => {
+                    return Err("takes a single value, not a list".into())
+                }
+                Value::Array(values) => values
+                    .iter()
+                    .map(value_to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                value => value_to_string(value),
+            };
+            // A plain string like `vrc_port = "9000,9001"` is split by clap as well.
+            match delimiter {
+                Some(delimiter) => value
+                    .split(delimiter)
+                    .try_for_each(|value| check_value(cmd, arg, value))?,
+                None => check_value(cmd, arg, &value)?,
+            }
+            flags.push(format!("{}={}", flag, value).into());
+        }
+    }
+    if !overridden {
+        args.extend(flags);
+    }
+    Ok(())
+}
+
+/// Runs the argument's own parser on a value, returning the reason if clap would reject it.
+fn check_value(cmd: &Command, arg: &Arg, value: &str) -> Result<(), String> {
+    arg.get_value_parser()
+        .parse_ref(cmd, Some(arg), OsStr::new(value))
+        .map(|_| ())
+        .map_err(|e| {
+            // Only keep the reason, not the usage that follows it.
+            let message = e.to_string();
+            let reason = message.lines().next().unwrap_or_default();
+            reason.trim_start_matches("error: ").to_string()
+        })
+}
+
+/// Converts the `face` setting into the subcommand and its arguments.
+fn face_args(cmd: &Command, value: &Value) -> Result<Vec<OsString>, String> {
+    let (source, table) = match value {
+        Value::String(source) => (source, None),
+        Value::Table(table) => {
+            let Some(Value::String(source)) = table.get("source") else {
+                return Err("needs a source, e.g. face = { source = \"babble\" }".into());
+            };
+            (source, Some(table))
+        }
+        _ => return Err("must be a name or a table".into()),
+    };
+
+    let Some(sub) = cmd.find_subcommand(source) else {
+        return Err(format!("unknown face source {}", source));
+    };
+    let mut args = vec![source.into()];
+    for (key, value) in table.iter().flat_map(|table| table.iter()) {
+        if key != "source" {
+            push_arg(&mut args, sub, None, key, value).map_err(|e| format!("{}: {}", key, e))?;
+        }
+    }
+    Ok(args)
+}
+
+/// Formats a TOML value the way it would be written on the command line.
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
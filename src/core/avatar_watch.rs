@@ -0,0 +1,76 @@
+use std::{
+    fs,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How often the file's modification time is checked.
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+/// How long the file must stay unchanged before it's reloaded, so that a single save
+/// (which editors often do in several writes) only triggers one reload.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watches the `--avatar` file for changes, so that edits to the parameter layout
+/// are picked up without restarting the application.
+pub struct AvatarWatch {
+    path: String,
+    /// The modification time of the file as last loaded.
+    loaded: Option<SystemTime>,
+    /// The modification time seen on the last check, and when it was first seen.
+    pending: Option<(SystemTime, Instant)>,
+    next_check: Instant,
+}
+
+impl AvatarWatch {
+    pub fn new(path: String) -> Self {
+        Self {
+            loaded: modified(&path),
+            path,
+            pending: None,
+            next_check: Instant::now() + CHECK_INTERVAL,
+        }
+    }
+
+    /// The path of the watched file.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns `true` once the file has changed and then settled for the debounce time.
+    pub fn changed(&mut self) -> bool {
+        let now = Instant::now();
+        if now < self.next_check {
+            return false;
+        }
+        self.next_check = now + CHECK_INTERVAL;
+
+        // A missing file (e.g. while an editor replaces it) is not a change.
+        let Some(current) = modified(&self.path) else {
+            return false;
+        };
+        if Some(current) == self.loaded {
+            self.pending = None;
+            return false;
+        }
+
+        match self.pending {
+            Some((seen, since)) if seen == current => {
+                if since.elapsed() < DEBOUNCE {
+                    return false;
+                }
+                self.loaded = Some(current);
+                self.pending = None;
+                true
+            }
+            _ => {
+                // Still being written to, wait until it settles.
+                self.pending = Some((current, now));
+                false
+            }
+        }
+    }
+}
+
+/// Reads the modification time of a file.
+fn modified(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -2,10 +2,29 @@
 //! It provides a structured way to create and manage OSC (Open Sound Control) bundles
 //! for controlling avatars in applications like VRChat or Resonite.
 
+use std::collections::HashSet;
+
+use once_cell::sync::OnceCell;
 use rosc::{OscBundle, OscMessage, OscPacket, OscType};
 
 use super::{INPUT_PREFIX, PARAM_PREFIX};
 
+/// The address that haptic pulses are sent to, if not set with `set_haptic_address`.
+const DEFAULT_HAPTIC_ADDRESS: &str = "/input/Haptic";
+
+/// The address that haptic pulses are sent to, set once on startup.
+static HAPTIC_ADDRESS: OnceCell<String> = OnceCell::new();
+
+/// Sets the address that `send_haptic` sends to. Only the first call has an effect.
+pub fn set_haptic_address(addr: &str) {
+    let _ = HAPTIC_ADDRESS.set(addr.to_string());
+}
+
+/// The size of an encoded bundle without any content: `#bundle\0` and the time tag.
+const BUNDLE_HEADER_SIZE: usize = 16;
+/// The size prefix in front of each element of an encoded bundle.
+const ELEMENT_HEADER_SIZE: usize = 4;
+
 /// A trait for building OSC (Open Sound Control) bundles to send to applications like Resonite.
 ///
 /// This trait abstracts the creation of OSC messages for various avatar interactions,
@@ -60,10 +79,31 @@ pub trait AvatarBundle {
     /// * `play_sound` - If `true`, a notification sound is played.
     fn send_chatbox_message(&mut self, message: String, open_keyboard: bool, play_sound: bool);
 
+    /// Adds a message to trigger a haptic pulse on the user's controllers.
+    ///
+    /// The message is sent to the address set with `set_haptic_address`.
+    ///
+    /// # Arguments
+    /// * `duration` - The length of the pulse in seconds.
+    /// * `frequency` - The frequency of the vibration in Hz.
+    /// * `amplitude` - The strength of the vibration, from 0.0 to 1.0.
+    fn send_haptic(&mut self, duration: f32, frequency: f32, amplitude: f32);
+
+    /// Removes all but the last message for each avatar parameter address, so that a parameter
+    /// that was set by several extensions in the same frame is only sent once, with the last value.
+    ///
+    /// Input and tracking messages are kept as they are, since their order matters.
+    fn dedup_parameters(&mut self);
+
     /// Serializes the entire bundle into a byte vector for transmission over the network.
     ///
     /// If the bundle contains no messages, this returns `None` to avoid sending empty packets.
     fn serialize(self) -> Option<Vec<u8>>;
+
+    /// Serializes the bundle into as few bundles as possible, each at most `max_size` bytes long.
+    ///
+    /// A single message that is larger than `max_size` on its own is still sent, in a bundle of its own.
+    fn serialize_chunked(self, max_size: usize) -> Vec<Vec<u8>>;
 }
 
 /// Implements the `AvatarBundle` trait for the `rosc::OscBundle` struct.
@@ -120,6 +160,29 @@ impl AvatarBundle for OscBundle {
         }));
     }
 
+    /// Adds an OSC message for a haptic pulse, with the duration, frequency and amplitude as float arguments.
+    fn send_haptic(&mut self, duration: f32, frequency: f32, amplitude: f32) {
+        let addr = HAPTIC_ADDRESS
+            .get()
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_HAPTIC_ADDRESS);
+        log::trace!(
+            "Sending haptic {} = {}s {}Hz {}",
+            addr,
+            duration,
+            frequency,
+            amplitude
+        );
+        self.content.push(OscPacket::Message(OscMessage {
+            addr: addr.to_string(),
+            args: vec![
+                OscType::Float(duration),
+                OscType::Float(frequency),
+                OscType::Float(amplitude),
+            ],
+        }));
+    }
+
     /// Inserts a chatbox message at the beginning of the bundle's message list.
     /// This can give it priority in processing, though OSC message order is not guaranteed.
     fn send_chatbox_message(&mut self, message: String, open_keyboard: bool, play_sound: bool) {
@@ -142,6 +205,21 @@ impl AvatarBundle for OscBundle {
         );
     }
 
+    /// Scans the content backwards, so that the last message of each parameter is the one that's kept.
+    fn dedup_parameters(&mut self) {
+        let mut seen = HashSet::new();
+        let mut keep = vec![true; self.content.len()];
+        for (idx, packet) in self.content.iter().enumerate().rev() {
+            if let OscPacket::Message(msg) = packet {
+                if msg.addr.starts_with(PARAM_PREFIX) && !seen.insert(msg.addr.as_str()) {
+                    keep[idx] = false;
+                }
+            }
+        }
+        let mut keep = keep.into_iter();
+        self.content.retain(|_| keep.next().unwrap_or(true));
+    }
+
     /// Encodes the `OscBundle` into a `Vec<u8>`.
     /// Returns `None` if the bundle is empty to prevent sending unnecessary network traffic.
     fn serialize(self) -> Option<Vec<u8>> {
@@ -151,4 +229,43 @@ impl AvatarBundle for OscBundle {
             None
         }
     }
+
+    /// Packs the messages in order, starting a new bundle whenever the next one wouldn't fit.
+    /// The encoded packet sizes are added up, so no bundle needs to be encoded twice.
+    fn serialize_chunked(self, max_size: usize) -> Vec<Vec<u8>> {
+        let timetag = self.timetag;
+        let mut chunks = Vec::new();
+        let mut chunk = OscBundle::new_bundle();
+        chunk.timetag = timetag;
+        let mut size = BUNDLE_HEADER_SIZE;
+
+        for packet in self.content {
+            let packet_size = match rosc::encoder::encode(&packet) {
+                Ok(buf) => buf.len() + ELEMENT_HEADER_SIZE,
+                Err(e) => {
+                    log::warn!("Could not encode OSC packet: {:?}", e);
+                    continue;
+                }
+            };
+
+            if !chunk.content.is_empty() && size + packet_size > max_size {
+                chunks.extend(std::mem::replace(&mut chunk, OscBundle::new_bundle()).serialize());
+                chunk.timetag = timetag;
+                size = BUNDLE_HEADER_SIZE;
+            }
+            if BUNDLE_HEADER_SIZE + packet_size > max_size {
+                log::debug!(
+                    "OSC packet of {} bytes exceeds the limit of {}",
+                    packet_size,
+                    max_size
+                );
+            }
+
+            chunk.content.push(packet);
+            size += packet_size;
+        }
+
+        chunks.extend(chunk.serialize());
+        chunks
+    }
 }
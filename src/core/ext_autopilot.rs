@@ -6,13 +6,11 @@ use log::info;
 use once_cell::sync::Lazy;
 use rosc::{OscBundle, OscType};
 
-use crate::core::ext_tracking::unified::UnifiedExpressions;
+use crate::{core::ext_tracking::unified::UnifiedExpressions, Args, JumpTarget};
 
 use super::{bundle::AvatarBundle, ext_tracking::ExtTracking, AppState};
 
-// --- Constants for movement thresholds ---
-const MOVE_THRESHOLD_METERS: f32 = 0.1;
-const RUN_THRESHOLD_METERS: f32 = 0.5;
+// --- Constants for rotation thresholds ---
 const ROTATE_THRESHOLD_RAD: f32 = PI / 120.; // 1.5 degrees
 const ROTATE_START_THRESHOLD_RAD: f32 = PI * 2.; // A very high value, effectively disabling rotation start based on this threshold.
 
@@ -25,6 +23,38 @@ static STA_MAN: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "MANUAL".color(Color
 static STA_OFF: Lazy<Arc<str>> =
     Lazy::new(|| format!("{}", "AP-OFF".color(Color::BrightBlack)).into());
 
+/// The distances and expression magnitudes that trigger the autopilot's actions.
+/// The expression values are the sum of the left and right (or all four brow) shapes.
+struct AutoPilotThresholds {
+    /// Follow mode (`Seeker_IsGrabbed`): how far (in meters) the target must be before walking towards it.
+    move_meters: f32,
+    /// Follow mode: the distance at which walking reaches full speed. With `Tracker1_Enable`,
+    /// this is also how far the target must be before moving at all.
+    run_meters: f32,
+    /// Manual mode: the cheek puff (forward) or cheek suck (backward) needed to start moving.
+    cheek_trigger: f32,
+    /// Manual mode: how cheek puff/suck is scaled into movement speed.
+    cheek_scale: f32,
+    /// Manual mode: the raised brows needed to press the `Voice` button.
+    brow_trigger: f32,
+    /// Manual mode: the brows must drop below this before `Voice` can be released again.
+    brow_release: f32,
+    /// Manual mode: how far the eyes must turn to either side to look left/right.
+    look_trigger: f32,
+}
+
+/// The emotes and the flap rhythm of the avatar flight.
+struct FlightCurve {
+    /// The `VRCEmote` values that count as the flight pose.
+    emotes: Range<i32>,
+    /// The highest jump power, i.e. how far (in meters) the hands count above the head.
+    max_power: f32,
+    /// The frames between two jumps at zero power.
+    cooldown_frames: f32,
+    /// How many frames less the cooldown is per meter of power, so that higher hands flap faster.
+    cooldown_per_power: f32,
+}
+
 /// This struct manages the state for the AutoPilot extension.
 /// It allows for controlling the avatar's movement and actions using facial expressions or by following a target.
 pub struct ExtAutoPilot {
@@ -34,10 +64,38 @@ pub struct ExtAutoPilot {
     jump_cd: i32,      // A cooldown timer for the jump action.
     follow_before: bool, // Was the avatar in "Follow" mode in the previous step?
     last_sent: Vec3,   // The last set of movement values sent, to avoid sending redundant OSC messages.
+    jump_target: JumpTarget, // Where the jump action is routed to.
+    jump_param: Arc<str>, // The parameter name used when `jump_target` is `Parameter`.
+    invert: Vec3, // Per-axis sign applied to the sent look/forward/strafe values.
+    flight: bool, // Whether the emote-triggered avatar flight is enabled at all.
+    flight_confirm: Option<Arc<str>>, // A bool parameter that must be set for flight to trigger.
+    flight_smoothing: f32, // How much of the previous hand heights is kept each frame (0 = raw).
+    flight_hands_y: Option<(f32, f32)>, // The smoothed left/right hand heights used by the flight.
+    flight_curve: FlightCurve, // Which emotes start the flight, and how fast it flaps.
+    thresholds: AutoPilotThresholds, // What it takes for the autopilot to move or press buttons.
 }
 
 impl ExtAutoPilot {
-    pub fn new() -> Self {
+    pub fn new(args: &Args) -> Self {
+        if args.flight_emote_min > args.flight_emote_max {
+            log::error!(
+                "--flight-emote-min ({}) is above --flight-emote-max ({}), no emote would start the flight.",
+                args.flight_emote_min,
+                args.flight_emote_max
+            );
+            std::process::exit(1);
+        }
+
+        if args.enable_flight {
+            info!(
+                "Avatar flight is enabled: VRCEmote {}-{} with both hands above the head jumps.",
+                args.flight_emote_min, args.flight_emote_max
+            );
+            info!(
+                "Disable it with --enable-flight false if your avatar uses those emotes otherwise."
+            );
+        }
+
         Self {
             voice: false,
             voice_lock: false,
@@ -45,9 +103,71 @@ impl ExtAutoPilot {
             jump_cd: 0,
             follow_before: false,
             last_sent: Vec3::ZERO,
+            jump_target: args.jump_target,
+            jump_param: args.jump_param.as_str().into(),
+            invert: Vec3::new(
+                if args.invert_look { -1. } else { 1. },
+                if args.invert_forward { -1. } else { 1. },
+                if args.invert_strafe { -1. } else { 1. },
+            ),
+            flight: args.enable_flight,
+            flight_confirm: args.flight_confirm_param.as_deref().map(Arc::from),
+            flight_smoothing: args.flight_smoothing.clamp(0., 0.99),
+            flight_hands_y: None,
+            flight_curve: FlightCurve {
+                emotes: args.flight_emote_min..args.flight_emote_max + 1,
+                max_power: args.flight_max_power.max(0.),
+                cooldown_frames: args.flight_cooldown_frames,
+                cooldown_per_power: args.flight_cooldown_per_power,
+            },
+            thresholds: AutoPilotThresholds {
+                move_meters: args.autopilot_move_threshold,
+                // Used as a divisor for the walking speed.
+                run_meters: args.autopilot_run_threshold.max(0.01),
+                cheek_trigger: args.autopilot_cheek_trigger,
+                cheek_scale: args.autopilot_cheek_scale,
+                brow_trigger: args.autopilot_brow_trigger,
+                brow_release: args.autopilot_brow_release,
+                look_trigger: args.autopilot_look_trigger,
+            },
         }
     }
 
+    /// Sends the jump action to the configured target.
+    /// VRChat uses the `/input/Jump` button, while other targets may only support a parameter, or nothing at all.
+    fn send_jump(&self, bundle: &mut OscBundle, value: bool) {
+        match self.jump_target {
+            JumpTarget::Input => bundle.send_input_button("Jump", value),
+            JumpTarget::Parameter => bundle.send_parameter(&self.jump_param, OscType::Bool(value)),
+            JumpTarget::Disabled => {}
+        }
+    }
+
+    /// Lets go of every input the autopilot may be holding, e.g. before tracking is paused.
+    pub fn release(&mut self, bundle: &mut OscBundle) {
+        if self.last_sent.x != 0. {
+            bundle.send_input_axis("LookHorizontal", 0.);
+        }
+        if self.last_sent.y != 0. {
+            bundle.send_input_axis("Vertical", 0.);
+        }
+        if self.last_sent.z != 0. {
+            bundle.send_input_axis("Horizontal", 0.);
+        }
+        if self.voice {
+            bundle.send_input_button("Voice", false);
+        }
+        if self.jumped {
+            self.send_jump(bundle, false);
+        }
+
+        self.voice = false;
+        self.voice_lock = false;
+        self.jumped = false;
+        self.last_sent = Vec3::ZERO;
+        self.flight_hands_y = None;
+    }
+
     /// The main update loop for the AutoPilot extension, called on every frame.
     /// It decides which control mode to use (Follow, Manual, or Off) and sends the appropriate OSC commands.
     pub fn step(&mut self, state: &mut AppState, tracking: &ExtTracking, bundle: &mut OscBundle) {
@@ -58,7 +178,7 @@ impl ExtAutoPilot {
 
         // --- Determine control mode ---
         let mut follow = false;
-        let mut follow_distance = MOVE_THRESHOLD_METERS;
+        let mut follow_distance = self.thresholds.move_meters;
         let mut allow_rotate = false;
 
         // "Follow" mode is activated by grabbing a "Seeker" object or enabling a tracker.
@@ -67,7 +187,7 @@ impl ExtAutoPilot {
         } else if let Some(OscType::Bool(true)) = state.params.get("Tracker1_Enable") {
             follow = true;
             allow_rotate = true;
-            follow_distance = RUN_THRESHOLD_METERS;
+            follow_distance = self.thresholds.run_meters;
         }
 
         let mut look_horizontal = 0.;
@@ -90,7 +210,7 @@ impl ExtAutoPilot {
 
                 // If the target is beyond the follow distance, move towards it.
                 if dist_horizontal > follow_distance {
-                    let mult = (dist_horizontal / RUN_THRESHOLD_METERS).clamp(0., 1.);
+                    let mult = (dist_horizontal / self.thresholds.run_meters).clamp(0., 1.);
 
                     vertical = tgt.z / dist_horizontal * mult;
                     horizontal = tgt.x / dist_horizontal * mult;
@@ -112,15 +232,15 @@ impl ExtAutoPilot {
 
             // Use eye gaze for looking left/right and jumping.
             if let Some(eye) = tracking.data.eyes[0] {
-                if !(-0.6..=0.5).contains(&eye.z) {
+                if eye.z.abs() > self.thresholds.look_trigger {
                     look_horizontal = -eye.z;
                 }
 
                 if eye.y > 0.4 && !self.jumped {
-                    bundle.send_input_button("Jump", true);
+                    self.send_jump(bundle, true);
                     self.jumped = true;
                 } else if self.jumped {
-                    bundle.send_input_button("Jump", false);
+                    self.send_jump(bundle, false);
                     self.jumped = false;
                 }
             }
@@ -132,10 +252,11 @@ impl ExtAutoPilot {
             let suck = tracking.data.getu(UnifiedExpressions::CheekSuckLeft)
                 + tracking.data.getu(UnifiedExpressions::CheekSuckRight);
 
-            if puff > 0.5 {
-                vertical = (puff * 0.6).min(1.0);
-            } else if suck > 0.5 {
-                vertical = -(suck * 0.6).min(1.0);
+            let t = &self.thresholds;
+            if puff > t.cheek_trigger {
+                vertical = (puff * t.cheek_scale).min(1.0);
+            } else if suck > t.cheek_trigger {
+                vertical = -(suck * t.cheek_scale).min(1.0);
             }
 
             // Use raising eyebrows to toggle the "Voice" button.
@@ -144,11 +265,11 @@ impl ExtAutoPilot {
                 + tracking.data.getu(UnifiedExpressions::BrowOuterUpLeft)
                 + tracking.data.getu(UnifiedExpressions::BrowOuterUpRight);
 
-            if brows < 2.0 {
+            if brows < self.thresholds.brow_release {
                 self.voice_lock = false; // Release the lock when brows are lowered.
             }
 
-            if brows > 3.0 && !self.voice {
+            if brows > self.thresholds.brow_trigger && !self.voice {
                 bundle.send_input_button("Voice", true);
                 self.voice = true;
                 self.voice_lock = true; // Lock to prevent immediate release.
@@ -163,6 +284,11 @@ impl ExtAutoPilot {
         }
 
         // --- Send Movement Commands ---
+        // Correct for coordinate mismatches between the avatar/world and our conventions.
+        let look_horizontal = look_horizontal * self.invert.x;
+        let vertical = vertical * self.invert.y;
+        let horizontal = horizontal * self.invert.z;
+
         // Only send updates if the values have changed significantly to reduce network traffic.
         if (look_horizontal - self.last_sent.x).abs() > 0.01 {
             bundle.send_input_axis("LookHorizontal", look_horizontal);
@@ -183,37 +309,64 @@ impl ExtAutoPilot {
     /// Implements a "flight" or "flap to jump" mechanic.
     /// This is triggered by a specific VRChat emote and raising both hands above the head.
     fn avatar_flight(&mut self, state: &mut AppState, bundle: &mut OscBundle) {
-        const FLIGHT_INTS: Range<i32> = 120..125;
+        if !self.flight {
+            return;
+        }
 
         let Some(OscType::Int(emote)) = state.params.get("VRCEmote") else {
             return;
         };
 
+        // Emotes in the flight range may mean something else on some avatars or worlds,
+        // so an extra parameter can be required to confirm that flight is intended.
+        let confirmed = self.flight_confirm.as_ref().is_none_or(|param| {
+            matches!(state.params.get(param.as_ref()), Some(OscType::Bool(true)))
+        });
+
         let left_pos = state.tracking.left_hand.translation;
         let right_pos = state.tracking.right_hand.translation;
-        let head_pos = state.tracking.head.translation;
+        let head_pos = state.tracking.head_smoothed.translation;
+
+        // Low-pass the hand heights, so that jitter around head height doesn't flicker between jump and no jump.
+        // This only affects the flight, other uses of the hands still see the raw positions.
+        let (left_y, right_y) = match self.flight_hands_y {
+            Some((left_y, right_y)) => {
+                let k = self.flight_smoothing;
+                (
+                    left_y * k + left_pos.y * (1. - k),
+                    right_y * k + right_pos.y * (1. - k),
+                )
+            }
+            None => (left_pos.y, right_pos.y),
+        };
+        self.flight_hands_y = Some((left_y, right_y));
 
         // If the correct emote is active and hands are above the head...
-        if FLIGHT_INTS.contains(emote) && left_pos.y > head_pos.y && right_pos.y > head_pos.y {
+        let curve = &self.flight_curve;
+        let hands_up = left_y > head_pos.y && right_y > head_pos.y;
+        if confirmed && curve.emotes.contains(emote) && hands_up {
             if !self.jumped && self.jump_cd <= 0 {
                 // Calculate jump "power" based on hand height.
-                let diff = (left_pos.y + left_pos.y) * 0.5 + 0.1 - head_pos.y;
-                let diff = diff.clamp(0., 0.3);
+                let diff = (left_y + left_y) * 0.5 + 0.1 - head_pos.y;
+                let diff = diff.clamp(0., curve.max_power);
+                // Set a cooldown for the next jump, creating a "flap" rhythm.
+                let cooldown = (curve.cooldown_frames - curve.cooldown_per_power * diff) as i32;
 
-                bundle.send_input_button("Jump", true);
+                self.send_jump(bundle, true);
+                // A short buzz, so that each flap can be felt.
+                bundle.send_haptic(0.05, 100., 0.3 + diff);
                 info!("Jumping with diff {}", diff);
 
                 self.jumped = true;
-                // Set a cooldown for the next jump, creating a "flap" rhythm.
-                self.jump_cd = (30. - 100. * diff) as i32;
+                self.jump_cd = cooldown;
             } else {
-                bundle.send_input_button("Jump", false);
+                self.send_jump(bundle, false);
                 self.jump_cd -= 1;
                 self.jumped = false;
             }
         } else if self.jumped {
             // Ensure jump is released if conditions are no longer met.
-            bundle.send_input_button("Jump", false);
+            self.send_jump(bundle, false);
             self.jump_cd = 0;
             self.jumped = false;
         }
@@ -240,7 +393,9 @@ const P3: Vec3 = Vec3::new(0., 0., 1.);
 
 /// Calculates the 3D position of a point given its distance from four other known points.
 /// See: https://en.wikipedia.org/wiki/Trilateration
-fn trilaterate(r1: f32, r2: f32, r3: f32, r4: f32) -> Vec3 {
+///
+/// Returns `None` if the distances are too inconsistent to give a finite position.
+fn trilaterate(r1: f32, r2: f32, r3: f32, r4: f32) -> Option<Vec3> {
     let p2_neg_p1 = P2 - P1;
     let p3_neg_p1 = P3 - P1;
 
@@ -258,22 +413,26 @@ fn trilaterate(r1: f32, r2: f32, r3: f32, r4: f32) -> Vec3 {
     let y = ((r1_sq - r3 * r3 + i * i + j * j) / (2. * j)) - (i / j * x);
 
     // There are two possible solutions for the z-coordinate.
-    let z1 = (r1_sq - x * x - y * y).sqrt();
+    // Inconsistent distances (e.g. at the edge of the contact range) make the spheres miss each other,
+    // in which case the closest point on their intersection plane is used.
+    let z1 = (r1_sq - x * x - y * y).max(0.).sqrt();
     let z2 = -1. * z1;
 
     let ans1 = P1 + x * e_x + y * e_y + z1 * e_z;
     let ans2 = P1 + x * e_x + y * e_y + z2 * e_z;
 
     // Use the fourth distance (r4) to disambiguate between the two solutions.
-    if ans1.length() - r4 < ans2.length() - r4 {
+    let ans = if ans1.length() - r4 < ans2.length() - r4 {
         ans1
     } else {
         ans2
-    }
+    };
+    ans.is_finite().then_some(ans)
 }
 
 /// Reads the four contact parameters from OSC, converts them to distances,
 /// and calls the trilateration function to get the final target vector.
+/// Frames with contact values outside of 0-1 are rejected, which stops the movement until they are valid again.
 fn vec3_to_target(parameters: &HashMap<Arc<str>, OscType>) -> Option<Vec3> {
     let par1 = parameters.get("Seeker_P0")?;
     let par2 = parameters.get("Seeker_P1")?;
@@ -282,11 +441,14 @@ fn vec3_to_target(parameters: &HashMap<Arc<str>, OscType>) -> Option<Vec3> {
 
     match (par1, par2, par3, par4) {
         (OscType::Float(c1), OscType::Float(c2), OscType::Float(c3), OscType::Float(c4)) => {
+            if [c1, c2, c3, c4].iter().any(|c| !(0.0..=1.0).contains(*c)) {
+                return None;
+            }
             let r1 = contact_to_dist(c1);
             let r2 = contact_to_dist(c2);
             let r3 = contact_to_dist(c3);
             let r4 = contact_to_dist(c4);
-            Some(trilaterate(r1, r2, r3, r4) * DIST_MULTIPLIER)
+            trilaterate(r1, r2, r3, r4).map(|tgt| tgt * DIST_MULTIPLIER)
         }
         _ => None,
     }
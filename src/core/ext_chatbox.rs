@@ -0,0 +1,78 @@
+use std::time::{Duration, Instant};
+
+use rosc::OscBundle;
+
+use crate::Args;
+
+use super::{bundle::AvatarBundle, status::StatusBar};
+
+/// The longest text the VRChat chatbox shows.
+const MAX_CHATBOX_CHARS: usize = 144;
+/// VRChat drops chatbox messages that arrive faster than this.
+const MIN_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// Shows the tracking state in the VRChat chatbox, e.g. `FT: on | Gaze: off`.
+///
+/// The template's `{name}` placeholders are filled in with the on/off states of the status line,
+/// such as `{face}` or `{gaze}`. Modules that didn't report a state this frame read as `off`.
+/// The text is only sent when it changed, and at most once per interval.
+pub struct ExtChatbox {
+    template: String,
+    interval: Duration,
+    next_check: Instant,
+    last_message: String,
+}
+
+impl ExtChatbox {
+    /// Returns `None` unless `--chatbox-status` is set.
+    pub fn new(args: &Args) -> Option<Self> {
+        if !args.chatbox_status {
+            return None;
+        }
+        Some(Self {
+            template: args.chatbox_template.clone(),
+            interval: Duration::from_secs_f32(args.chatbox_interval.max(0.)).max(MIN_INTERVAL),
+            next_check: Instant::now(),
+            last_message: String::new(),
+        })
+    }
+
+    /// Sends the filled-in template if it changed. Must run before the status line is displayed,
+    /// which clears the states of the current frame.
+    pub fn step(&mut self, status: &StatusBar, bundle: &mut OscBundle) {
+        if self.next_check > Instant::now() {
+            return;
+        }
+        self.next_check = Instant::now() + self.interval;
+
+        let message = self.render(status);
+        if message != self.last_message {
+            bundle.send_chatbox_message(message.clone(), false, false);
+            self.last_message = message;
+        }
+    }
+
+    fn render(&self, status: &StatusBar) -> String {
+        let mut message = self.template.clone();
+        for (name, active) in status.states() {
+            message = message.replace(&format!("{{{}}}", name), if active { "on" } else { "off" });
+        }
+
+        // Whatever is left didn't report this frame.
+        let mut rest = message.as_str();
+        let mut out = String::with_capacity(message.len());
+        while let Some(start) = rest.find('{') {
+            match rest[start..].find('}') {
+                Some(end) => {
+                    out.push_str(&rest[..start]);
+                    out.push_str("off");
+                    rest = &rest[start + end + 1..];
+                }
+                None => break,
+            }
+        }
+        out.push_str(rest);
+
+        out.chars().take(MAX_CHATBOX_CHARS).collect()
+    }
+}
@@ -3,15 +3,36 @@ use mdns_sd::{ServiceDaemon, ServiceEvent};
 use rosc::{OscBundle, OscType};
 use serde::{Deserialize, Serialize};
 use std::{
+    array,
     collections::HashMap,
     fs::File,
     io::{Read, Write},
-    sync::Arc,
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use super::{bundle::AvatarBundle, folders::CONFIG_DIR};
+use crate::Args;
+
+use super::{bundle::AvatarBundle, folders::CONFIG_DIR, oscquery::OscQueryServer};
+
+/// The file name of the local copy of the last avatar json, in `CONFIG_DIR`.
+const LOCAL_COPY_FILE: &str = "oscavmgr-avatar.json";
+
+/// The mDNS service type of OSC JSON (OSCQuery) services.
+const OSCJSON_SERVICE: &str = "_oscjson._tcp.local.";
+/// How often the browse is re-issued, so that services on a new network are found after e.g. a sleep/wake.
+const REBROWSE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// How many times the avatar json is fetched while the service doesn't serve a usable json
+/// for the requested avatar yet.
+const FETCH_ATTEMPTS: u32 = 5;
+/// The wait before the second fetch, if `--oscjson-fetch-delay-ms` is shorter. Doubled for each further fetch.
+const FETCH_RETRY: Duration = Duration::from_millis(250);
 
 /// This extension handles the discovery and interaction with an OSC JSON service,
 /// typically provided by a VR application like VRChat or Resonite. It allows the application
@@ -23,33 +44,88 @@ pub struct ExtOscJson {
     mdns_recv: mdns_sd::Receiver<ServiceEvent>,
     /// The discovered network address (e.g., "http://127.0.0.1:9001/avatar") of the OSC JSON service.
     oscjson_addr: Option<Arc<str>>,
+    /// Whether `oscjson_addr` was given on the command line, in which case mDNS results are ignored.
+    fixed_addr: bool,
     /// A timestamp to throttle how frequently the service discovery is performed.
     next_run: std::time::Instant,
+    /// When the browse is re-issued next.
+    next_rebrowse: Instant,
     /// An HTTP client for making requests to the OSC JSON service.
     client: reqwest::blocking::Client,
+    /// The content and time of the last write of the local avatar json copy.
+    last_write: Option<(String, Instant)>,
+    /// The minimum time between two writes of the local avatar json copy.
+    write_cooldown: Duration,
+    /// How long to wait before fetching the avatar json from the network.
+    fetch_delay: Duration,
+    /// Whether avatar UIDs are looked up in `CONFIG_DIR/avatars/<uid>.json` before the network.
+    local_avatars: bool,
+    /// Our own OSCQuery service, publishing the parameters we send, if enabled.
+    oscquery: Option<OscQueryServer>,
+    /// The avatar json fetched from the network, keyed by the avatar id it reported.
+    avatar_cache: HashMap<String, String>,
+    /// Counts the `avatar` calls, so that only the result of the latest fetch is used.
+    fetch_request: u64,
+    /// Channel for the results of the background fetches.
+    fetch_send: Sender<FetchResult>,
+    fetch_recv: Receiver<FetchResult>,
 }
 
 impl ExtOscJson {
     /// Initializes the OSC JSON extension.
-    pub fn new() -> Self {
+    /// If `--oscjson-url` is given, the avatar JSON is fetched from `<url>/avatar` instead of a discovered service.
+    pub fn new(args: &Args) -> Self {
         // Create a new mDNS daemon to listen for network services.
         let mdns = ServiceDaemon::new().unwrap();
         // Start browsing for services of the type "_oscjson._tcp.local.", which is the standard for OSC JSON.
-        let mdns_recv = mdns.browse("_oscjson._tcp.local.").unwrap();
+        let mdns_recv = mdns.browse(OSCJSON_SERVICE).unwrap();
         let client = reqwest::blocking::Client::new();
 
+        // A user-provided URL takes precedence over anything discovered via mDNS.
+        let oscjson_addr: Option<Arc<str>> = args
+            .oscjson_url
+            .as_ref()
+            .map(|url| format!("{}/avatar", url.trim_end_matches('/')).into());
+
+        if let Some(addr) = oscjson_addr.as_ref() {
+            info!("Using OSCJSON service: {}", addr);
+        }
+
+        let oscquery = args
+            .oscquery_port
+            .and_then(|port| OscQueryServer::new(&mdns, port, args.osc_port));
+        let (fetch_send, fetch_recv) = mpsc::channel();
+
         Self {
             mdns,
             mdns_recv,
-            oscjson_addr: None,
+            fixed_addr: oscjson_addr.is_some(),
+            oscjson_addr,
             next_run: std::time::Instant::now(),
+            next_rebrowse: Instant::now() + REBROWSE_INTERVAL,
             client,
+            last_write: None,
+            write_cooldown: Duration::from_secs(args.avatar_json_cooldown),
+            fetch_delay: Duration::from_millis(args.oscjson_fetch_delay_ms),
+            local_avatars: args.local_avatars,
+            oscquery,
+            avatar_cache: HashMap::new(),
+            fetch_request: 0,
+            fetch_send,
+            fetch_recv,
+        }
+    }
+
+    /// Publishes the parameters we send on our own OSCQuery service, if enabled.
+    pub fn publish(&self, params: &[(Arc<str>, &'static str)]) {
+        if let Some(oscquery) = self.oscquery.as_ref() {
+            oscquery.publish(params);
         }
     }
 
     /// The main update loop for the extension, called periodically.
     /// It checks for new OSC JSON services on the network.
-    /// Returns `true` if a new avatar service was discovered in this step.
+    /// Returns `true` if a new avatar service was discovered in this step, so that the current avatar is loaded.
     pub fn step(&mut self) -> bool {
         let mut notify_avatar = false;
         // Throttle the check to avoid excessive network activity.
@@ -59,10 +135,14 @@ impl ExtOscJson {
         self.next_run = std::time::Instant::now() + std::time::Duration::from_secs(15);
 
         // Process all pending mDNS events.
+        let mut browsing = !self.mdns_recv.is_disconnected();
         for event in self.mdns_recv.try_iter() {
-            if let ServiceEvent::ServiceResolved(info) = event {
-                // We only care about services published by the VRChat client.
-                if !info.get_fullname().starts_with("VRChat-Client-") {
+            if let ServiceEvent::SearchStopped(_) = event {
+                browsing = false;
+            } else if let ServiceEvent::ServiceResolved(info) = event {
+                // We only care about services published by the VRChat client,
+                // and only if the user didn't already tell us where to look.
+                if self.fixed_addr || !info.get_fullname().starts_with("VRChat-Client-") {
                     continue;
                 }
                 let addr = info.get_addresses().iter().next().unwrap();
@@ -84,67 +164,406 @@ impl ExtOscJson {
             }
         }
 
-        // If a new avatar was found, immediately fetch its JSON definition.
-        if self.oscjson_addr.is_some() && notify_avatar {
-            self.avatar(&AvatarIdentifier::Default);
+        // Keep discovery alive across network changes.
+        if !browsing {
+            warn!("mDNS discovery stopped, restarting it.");
+            self.restart_mdns();
+        } else if self.next_rebrowse <= Instant::now() {
+            self.rebrowse();
         }
+
         notify_avatar
     }
 
-    /// Fetches, parses, and saves the avatar's OSC JSON definition.
+    /// Re-issues the browse on the current daemon, recreating the daemon if that fails.
+    fn rebrowse(&mut self) {
+        self.next_rebrowse = Instant::now() + REBROWSE_INTERVAL;
+        let _ = self.mdns.stop_browse(OSCJSON_SERVICE);
+        match self.mdns.browse(OSCJSON_SERVICE) {
+            Ok(mdns_recv) => self.mdns_recv = mdns_recv,
+            Err(e) => {
+                warn!("mDNS browse failed ({}), restarting discovery.", e);
+                self.restart_mdns();
+            }
+        }
+    }
+
+    /// Replaces the mDNS daemon with a new one and browses again.
+    /// If that fails too, the old daemon is kept and the next `step` tries again.
+    fn restart_mdns(&mut self) {
+        self.next_rebrowse = Instant::now() + REBROWSE_INTERVAL;
+        let mdns = match ServiceDaemon::new() {
+            Ok(mdns) => mdns,
+            Err(e) => {
+                log::error!("Could not restart mDNS discovery: {}", e);
+                return;
+            }
+        };
+        let mdns_recv = match mdns.browse(OSCJSON_SERVICE) {
+            Ok(mdns_recv) => mdns_recv,
+            Err(e) => {
+                log::error!("Could not restart mDNS discovery: {}", e);
+                let _ = mdns.shutdown();
+                return;
+            }
+        };
+
+        let _ = self.mdns.shutdown();
+        self.mdns = mdns;
+        self.mdns_recv = mdns_recv;
+        if let Some(oscquery) = self.oscquery.as_ref() {
+            oscquery.advertise(&self.mdns);
+        }
+        info!("mDNS discovery restarted.");
+    }
+
+    /// Loads the avatar's OSC JSON definition.
+    ///
+    /// Local files are read right away. Avatars from the network service are fetched on a background thread,
+    /// since the service may need several attempts; `fetched` returns the result once it's done.
+    /// Loading another avatar supersedes a fetch that is still running.
     ///
     /// # Arguments
     /// * `avatar` - An `AvatarIdentifier` specifying whether to fetch from the network (`Default`) or a local file (`Path`).
-    ///
-    /// # Returns
-    /// An `Option<OscJsonNode>` containing the root of the parsed avatar parameter tree.
-    pub fn avatar(&mut self, avatar: &AvatarIdentifier) -> Option<OscJsonNode> {
-        let mut json = String::new();
+    pub fn avatar(&mut self, avatar: &AvatarIdentifier) -> AvatarLoad {
+        self.fetch_request += 1;
 
-        if let AvatarIdentifier::Path(path) = avatar {
+        // A UID may resolve to a local file, for testing avatars offline.
+        let path = match avatar {
+            AvatarIdentifier::Path(path) => Some(path.clone()),
+            AvatarIdentifier::Uid(uid) => self.local_avatar_path(uid),
+            AvatarIdentifier::Default => None,
+        };
+
+        if let Some(path) = path {
             // Load from a local file if a path is provided.
-            if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut json)) {
+            let mut json = String::new();
+            if let Err(e) = File::open(&path).and_then(|mut f| f.read_to_string(&mut json)) {
                 log::error!("Could not read file: {:?}", e);
-                return None;
+                return AvatarLoad::Loaded(None);
             }
-        } else {
-            // Otherwise, fetch from the discovered network service.
-            let Some(addr) = self.oscjson_addr.as_ref() else {
-                warn!("No avatar oscjson address.");
-                return None;
-            };
+            return AvatarLoad::Loaded(match parse_avatar_json(&json) {
+                Ok(root_node) => {
+                    self.write_local_copy(&json);
+                    Some(root_node)
+                }
+                Err(reason) => {
+                    warn!("Avatar json {} can't be used: {}", path, reason);
+                    None
+                }
+            });
+        }
 
-            // A small delay, possibly to ensure the service is fully ready to respond.
-            thread::sleep(Duration::from_millis(250));
+        let Some(addr) = self.oscjson_addr.clone() else {
+            warn!("No avatar oscjson address.");
+            return AvatarLoad::Loaded(None);
+        };
 
-            let Ok(resp) = self.client.get(addr.as_ref()).send() else {
-                warn!("Failed to send avatar json request.");
-                return None;
-            };
+        let uid = match avatar {
+            AvatarIdentifier::Uid(uid) => Some(uid.clone()),
+            _ => None,
+        };
+        let job = FetchJob {
+            client: self.client.clone(),
+            addr,
+            fetch_delay: self.fetch_delay,
+            cached: uid
+                .as_ref()
+                .and_then(|uid| self.avatar_cache.get(uid).cloned()),
+            seen: vec![],
+        };
+        let request = self.fetch_request;
+        let sender = self.fetch_send.clone();
+        thread::spawn(move || {
+            let (seen, result) = job.run(uid.as_deref());
+            let _ = sender.send(FetchResult {
+                request,
+                seen,
+                result,
+            });
+        });
+        AvatarLoad::Fetching
+    }
 
-            let Ok(text) = resp.text() else {
-                warn!("No payload in avatar json response.");
-                return None;
-            };
+    /// Returns the outcome of the latest background fetch once it's done, `Some(None)` if it failed.
+    /// Results of superseded fetches are only kept for the avatar cache.
+    pub fn fetched(&mut self) -> Option<Option<OscJsonNode>> {
+        let mut latest = None;
+        for fetched in self.fetch_recv.try_iter() {
+            self.avatar_cache.extend(fetched.seen);
+            if fetched.request == self.fetch_request {
+                latest = Some(fetched.result);
+            }
+        }
+
+        latest.map(|result| {
+            result.map(|(json, root_node)| {
+                // Save a local copy of the fetched JSON for debugging or later use.
+                self.write_local_copy(&json);
+                root_node
+            })
+        })
+    }
+
+    /// Whether the avatar json for a UID can be loaded yet: from a local file, or once the service is known.
+    pub fn can_load_uid(&self, uid: &str) -> bool {
+        self.oscjson_addr.is_some()
+            || self
+                .local_avatar_file(uid)
+                .is_some_and(|path| Path::new(&path).is_file())
+    }
+
+    /// Where the local avatar json for a UID would be, if `--local-avatars` is enabled.
+    fn local_avatar_file(&self, uid: &str) -> Option<String> {
+        // UIDs are used as file names, so they must not point outside of the avatars folder.
+        if !self.local_avatars || uid.contains(['/', '\\']) || uid.starts_with('.') {
+            return None;
+        }
+        Some(format!("{}/avatars/{}.json", CONFIG_DIR.as_ref(), uid))
+    }
+
+    /// Finds the local avatar json for a UID, if `--local-avatars` is enabled and the file exists.
+    fn local_avatar_path(&self, uid: &str) -> Option<String> {
+        let path = self.local_avatar_file(uid)?;
+        if Path::new(&path).is_file() {
+            info!("Using local avatar json {}", path);
+            Some(path)
+        } else {
+            log::debug!("No local avatar json at {}, using the network.", path);
+            None
+        }
+    }
+
+    /// Loads the local copy of the last avatar json, as written by `write_local_copy`.
+    /// Used on startup, so that the last avatar's mapping applies until the current avatar is known.
+    /// Returns `None` if there is no copy or it can't be parsed.
+    pub fn cached_avatar(&self) -> Option<OscJsonNode> {
+        let path = format!("{}/{}", CONFIG_DIR.as_ref(), LOCAL_COPY_FILE);
+        let mut json = String::new();
+        if let Err(e) = File::open(&path).and_then(|mut f| f.read_to_string(&mut json)) {
+            log::debug!("No cached avatar json at {}: {:?}", path, e);
+            return None;
+        }
+
+        match parse_avatar_json(&json) {
+            Ok(root_node) => {
+                info!(
+                    "Using the cached avatar json {} until the avatar is known.",
+                    path
+                );
+                Some(root_node)
+            }
+            Err(reason) => {
+                warn!("Cached avatar json {} can't be used: {}", path, reason);
+                None
+            }
+        }
+    }
 
-            json = text;
+    /// Writes the avatar json to `oscavmgr-avatar.json` in the config dir.
+    /// The write is skipped if the content didn't change or the last write was too recent,
+    /// which avoids disk churn when switching avatars rapidly.
+    fn write_local_copy(&mut self, json: &str) {
+        if let Some((last_json, last_time)) = self.last_write.as_ref() {
+            if last_json == json {
+                log::debug!("Avatar json unchanged, not writing.");
+                return;
+            }
+            if last_time.elapsed() < self.write_cooldown {
+                log::debug!("Avatar json written recently, not writing.");
+                return;
+            }
         }
 
-        // Save a local copy of the fetched JSON for debugging or later use.
-        let path = format!("{}/{}", CONFIG_DIR.as_ref(), "oscavmgr-avatar.json");
+        let path = format!("{}/{}", CONFIG_DIR.as_ref(), LOCAL_COPY_FILE);
         if let Err(e) = File::create(path).and_then(|mut f| f.write_all(json.as_bytes())) {
             warn!("Could not write avatar json file: {:?}", e);
+            return;
         }
+        self.last_write = Some((json.to_string(), Instant::now()));
+    }
+}
 
-        // Parse the JSON string into the OscJsonNode structure.
-        match serde_json::from_str(&json) {
-            Ok(root_node) => Some(root_node),
-            Err(e) => {
-                warn!("Failed to deserialize avatar json: {}", e);
-                None
+/// Whether the avatar json could be used right away, or is being fetched.
+pub enum AvatarLoad {
+    /// The avatar json was loaded, or failed to, and the avatar can be applied.
+    Loaded(Option<OscJsonNode>),
+    /// The avatar json is being fetched in the background, see `ExtOscJson::fetched`.
+    Fetching,
+}
+
+/// The outcome of a background fetch.
+struct FetchResult {
+    /// Which `ExtOscJson::avatar` call this answers.
+    request: u64,
+    /// Every avatar json fetched, keyed by the avatar id it reported, for the avatar cache.
+    seen: Vec<(String, String)>,
+    /// The json and its parsed root node, `None` if no usable json was fetched.
+    result: Option<(String, OscJsonNode)>,
+}
+
+/// Fetches the avatar json from the network service, on a background thread.
+struct FetchJob {
+    client: reqwest::blocking::Client,
+    /// The address of the avatar json.
+    addr: Arc<str>,
+    /// How long to wait before fetching the avatar json.
+    fetch_delay: Duration,
+    /// An earlier fetch of the requested UID, if any.
+    cached: Option<String>,
+    /// Every avatar json fetched so far, keyed by the avatar id it reported.
+    seen: Vec<(String, String)>,
+}
+
+impl FetchJob {
+    /// Fetches the avatar json, returning it along with every json seen on the way.
+    fn run(mut self, uid: Option<&str>) -> (Vec<(String, String)>, Option<(String, OscJsonNode)>) {
+        let result = self.fetch_avatar(uid);
+        (self.seen, result)
+    }
+
+    /// Fetches the avatar json, for a UID if one is given.
+    ///
+    /// The request doesn't include the UID, since the service only serves the avatar that is currently worn.
+    /// Instead, the UID is checked against the avatar id that the response reports.
+    ///
+    /// Right after an avatar change the service may not serve the avatar yet, e.g. an error page,
+    /// an avatar without parameters or, since it always describes the avatar that is currently worn,
+    /// still the previous avatar. The json is fetched again until it's usable and the avatar id it reports
+    /// matches the UID. If it never matches, an earlier fetch of the same avatar is used instead.
+    fn fetch_avatar(&mut self, uid: Option<&str>) -> Option<(String, OscJsonNode)> {
+        // A small delay, to give the service some time to catch up with the avatar change.
+        if !self.fetch_delay.is_zero() {
+            thread::sleep(self.fetch_delay);
+        }
+
+        let mut backoff = self.fetch_delay.max(FETCH_RETRY);
+        let mut reason = String::new();
+        // The last usable json, while it's still for another avatar.
+        let mut other = None;
+        for attempt in 0..FETCH_ATTEMPTS {
+            if attempt > 0 {
+                log::debug!(
+                    "Avatar json not ready ({}), retrying in {:?}",
+                    reason,
+                    backoff
+                );
+                thread::sleep(backoff);
+                backoff *= 2;
+            }
+
+            if let Some(uid) = uid {
+                info!("Fetching avatar json, expecting {}", uid);
+            }
+            let Some(json) = self.fetch() else {
+                reason = "request failed".to_string();
+                continue;
+            };
+            let root_node = match parse_avatar_json(&json) {
+                Ok(root_node) => root_node,
+                Err(e) => {
+                    reason = e;
+                    continue;
+                }
+            };
+
+            let reported = reported_avatar_id(&json);
+            if let Some(id) = reported.as_ref() {
+                self.seen.push((id.clone(), json.clone()));
+            }
+            match (uid, reported) {
+                (Some(uid), Some(id)) if id != uid => {
+                    reason = format!("it is still for {}", id);
+                    other = Some((json, root_node));
+                }
+                // Services that don't report the avatar id can't be checked.
+                _ => return Some((json, root_node)),
+            }
+        }
+
+        if let (Some(uid), Some(other)) = (uid, other) {
+            let cached = self
+                .cached
+                .take()
+                .and_then(|json| parse_avatar_json(&json).ok().map(|node| (json, node)));
+            if cached.is_some() {
+                warn!(
+                    "OSCJSON service still serves another avatar, using the cached json for {}.",
+                    uid
+                );
+                return cached;
             }
+            warn!(
+                "OSCJSON service still serves another avatar after {} attempts, its parameters may be wrong for {}.",
+                FETCH_ATTEMPTS, uid
+            );
+            return Some(other);
+        }
+
+        warn!(
+            "Gave up on the avatar json after {} attempts: {}. Its parameters won't apply until the avatar is changed or reset.",
+            FETCH_ATTEMPTS, reason
+        );
+        None
+    }
+
+    /// Fetches the avatar json from the network service.
+    fn fetch(&self) -> Option<String> {
+        let Ok(resp) = self.client.get(self.addr.as_ref()).send() else {
+            warn!("Failed to send avatar json request.");
+            return None;
+        };
+
+        let Ok(text) = resp.text() else {
+            warn!("No payload in avatar json response.");
+            return None;
+        };
+
+        Some(text)
+    }
+}
+
+/// Reads the id of the avatar that an avatar json describes, from the value of its `change` node.
+fn reported_avatar_id(json: &str) -> Option<String> {
+    let root: serde_json::Value = serde_json::from_str(json).ok()?;
+    root.pointer("/CONTENTS/change/VALUE/0")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Parses the avatar json, explaining why it can't be used otherwise.
+///
+/// Besides the avatar node, services may answer with their `HOST_INFO`, an error page
+/// or an avatar that has no parameters yet, all of which would fail to deserialize with a cryptic error.
+/// A root node (`/`) is accepted as well, its `avatar` child is used.
+fn parse_avatar_json(json: &str) -> Result<OscJsonNode, String> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|_| {
+        if json.trim_start().starts_with('<') {
+            "the service answered with a web page instead of json".to_string()
+        } else {
+            "the response is not json".to_string()
+        }
+    })?;
+
+    if value.get("HOST_INFO").is_some() || value.get("OSC_PORT").is_some() {
+        return Err(
+            "the service answered with its host info, make sure the address ends in /avatar"
+                .to_string(),
+        );
+    }
+
+    let value = match value.pointer("/CONTENTS/avatar") {
+        Some(avatar) if value.get("FULL_PATH").and_then(|p| p.as_str()) == Some("/") => {
+            avatar.clone()
         }
+        _ => value,
+    };
+
+    if value.pointer("/CONTENTS/parameters").is_none() {
+        return Err("the avatar has no parameters (yet)".to_string());
     }
+
+    serde_json::from_value(value).map_err(|e| format!("unexpected avatar json format: {}", e))
 }
 
 /// An enum to identify the source of an avatar's OSC JSON definition.
@@ -152,26 +571,35 @@ impl ExtOscJson {
 pub enum AvatarIdentifier {
     /// Use the default, network-discovered service.
     Default,
-    /// Identify by a unique ID (not currently used).
+    /// Identify by a unique ID, fetched from the OSC JSON service once it serves that avatar.
     Uid(String),
     /// Load from a local file path.
     Path(String),
 }
 
 /// Represents a node in the OSC JSON hierarchy, which describes an avatar's OSC parameters.
+/// Serialized with the OSCQuery field names, so that it can be served by `OscQueryServer`.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct OscJsonNode {
     /// The full OSC address path for this node (e.g., "/avatar/parameters/JawOpen").
-    #[serde(alias = "FULL_PATH")]
+    #[serde(rename = "FULL_PATH", alias = "full_path")]
     pub full_path: Arc<str>,
     /// An integer indicating access rights (e.g., 1 for read, 2 for write, 3 for read/write).
-    #[serde(alias = "ACCESS")]
+    #[serde(rename = "ACCESS", alias = "access")]
     pub access: i32,
     /// The expected OSC data type for this parameter (e.g., "Float", "Int", "Bool").
-    #[serde(alias = "TYPE")]
+    #[serde(
+        rename = "TYPE",
+        alias = "data_type",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub data_type: Option<Arc<str>>,
     /// A map of child nodes, representing the nested structure of the OSC address space.
-    #[serde(alias = "CONTENTS")]
+    #[serde(
+        rename = "CONTENTS",
+        alias = "contents",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub contents: Option<HashMap<Arc<str>, OscJsonNode>>,
 }
 
@@ -196,6 +624,12 @@ impl OscJsonNode {
             .and_then(|parameters| parameters.get("VSync"))
             .is_some()
     }
+
+    /// Checks if this node is declared as a boolean parameter.
+    /// OSCQuery uses the `T`/`F` type tags for booleans, but a plain `Bool` is accepted as well.
+    pub fn is_bool(&self) -> bool {
+        matches!(self.data_type.as_deref(), Some("T" | "F" | "Bool"))
+    }
 }
 
 /// This struct represents a complex avatar parameter that is controlled by multiple OSC addresses.
@@ -206,25 +640,78 @@ pub struct MysteryParam {
     pub name: Arc<str>,
     /// The primary address, which usually takes a float value.
     pub main_address: Option<Arc<str>>,
+    /// Whether the primary address is declared as a bool, in which case the value is thresholded at 0.5.
+    pub main_is_bool: bool,
     /// An array of addresses for the individual bits of a high-precision value.
-    pub addresses: [Option<Arc<str>>; 7],
+    /// At most 8 bits (parameter suffixes `1` to `128`) are supported; higher bits are ignored.
+    pub addresses: [Option<Arc<str>>; 8],
     /// An address for a boolean that represents the sign of the value.
     pub neg_address: Option<Arc<str>>,
     /// The number of bits used for the high-precision value.
     pub num_bits: usize,
     /// The last float value sent to the main address, for change detection.
     pub last_value: f32,
+    /// How far the value must move from `last_value` before the main float address is updated.
+    pub deadband: f32,
     /// The last state of the boolean bits sent, for change detection.
     pub last_bits: [bool; 8],
+    /// The last state of the sign bit sent, for change detection.
+    pub last_neg: bool,
+    /// The last quantized value sent over the bits, `None` if nothing was sent yet.
+    pub last_quantized: Option<i32>,
+    /// How far (in quantization steps) the value must move past the edges of `last_quantized`
+    /// before the bits are updated. 0 updates them as soon as the quantized value changes.
+    pub bit_hysteresis: f32,
+    /// How much of the previous value is kept each time a value is sent (0 = no smoothing).
+    pub smoothing: f32,
+    /// The low-passed value, `None` if the next value should be taken as-is.
+    /// `last_value` can't be reused for this, since it only moves past the deadband and would stall the filter.
+    pub smoothed_value: Option<f32>,
 }
 
 impl MysteryParam {
+    /// Creates a new `MysteryParam` with no bit or sign addresses.
+    pub fn new(name: Arc<str>, main_address: Option<Arc<str>>) -> Self {
+        Self {
+            name,
+            main_address,
+            main_is_bool: false,
+            addresses: array::from_fn(|_| None),
+            neg_address: None,
+            num_bits: 0,
+            last_value: 0.,
+            deadband: 0.01,
+            last_bits: [false; 8],
+            last_neg: false,
+            last_quantized: None,
+            bit_hysteresis: 0.,
+            smoothing: 0.,
+            smoothed_value: None,
+        }
+    }
+
     /// Sends the given float value to the appropriate OSC addresses for this parameter.
     /// It handles sending to the main float address as well as updating the individual boolean bits.
     pub fn send(&mut self, value: f32, bundle: &mut OscBundle) {
-        // Send to the main float address if it exists and the value has changed.
+        // Low-pass the value before it's quantized, so that bit-packed params glide instead of popping.
+        let value = match self.smoothed_value {
+            Some(smoothed) if self.smoothing > 0. => {
+                smoothed * self.smoothing + value * (1. - self.smoothing)
+            }
+            _ => value,
+        };
+        self.smoothed_value = Some(value);
+
+        // Send to the main address if it exists and the value has changed.
         if let Some(addr) = self.main_address.as_ref() {
-            if (value - self.last_value).abs() > 0.01 {
+            if self.main_is_bool {
+                // Bool-typed shapes only care about which side of 0.5 the value is on.
+                let send_val = value > 0.5;
+                if send_val != (self.last_value > 0.5) {
+                    bundle.send_parameter(addr, OscType::Bool(send_val));
+                    self.last_value = if send_val { 1. } else { 0. };
+                }
+            } else if (value - self.last_value).abs() > self.deadband {
                 bundle.send_parameter(addr, OscType::Float(value));
                 self.last_value = value;
             }
@@ -234,17 +721,34 @@ impl MysteryParam {
         // Handle the negative sign bit if it exists.
         if let Some(addr) = self.neg_address.as_ref() {
             let send_val = value < 0.;
-            if self.last_bits[7] != send_val {
+            if self.last_neg != send_val {
                 bundle.send_parameter(addr, OscType::Bool(send_val));
-                self.last_bits[7] = send_val;
+                self.last_neg = send_val;
             }
             value = value.abs();
         } else if value < 0. {
             value = 0.; // If there's no negative address, clamp to positive.
         }
 
+        if self.num_bits == 0 {
+            return;
+        }
+
         // Convert the float value (0.0-1.0) to an integer based on the number of bits.
-        let value = (value * ((1 << self.num_bits) - 1) as f32) as i32;
+        // Nothing is sent while the value stays within the quantization step last sent (plus the hysteresis),
+        // so that a value hovering around a step edge doesn't toggle several bits every frame.
+        let max = ((1 << self.num_bits) - 1) as f32;
+        let scaled = value * max;
+        if let Some(last) = self.last_quantized {
+            let last = last as f32;
+            // The top step is only reached at exactly 1.0, so its edge can't be pushed past it.
+            let upper = (last + 1. + self.bit_hysteresis).min(max);
+            if scaled >= last - self.bit_hysteresis && scaled < upper {
+                return;
+            }
+        }
+        let value = scaled as i32;
+        self.last_quantized = Some(value);
 
         // Iterate through the bits and send boolean updates if they have changed.
         self.addresses
@@ -261,4 +765,67 @@ impl MysteryParam {
                 }
             });
     }
+
+    /// Sends 0 (or `false`) to every address of this parameter, regardless of the last sent values.
+    /// Used to clear the parameter before its mapping is dropped.
+    pub fn reset(&mut self, bundle: &mut OscBundle) {
+        self.send_now(0., bundle);
+    }
+
+    /// Sends the value to every address of this parameter, regardless of the last sent values,
+    /// skipping the smoothing, deadband and bit hysteresis. The change detection starts over from it.
+    /// Used to send a neutral face in a single bundle.
+    pub fn send_now(&mut self, value: f32, bundle: &mut OscBundle) {
+        self.smoothed_value = None;
+
+        if let Some(addr) = self.main_address.as_ref() {
+            if self.main_is_bool {
+                bundle.send_parameter(addr, OscType::Bool(value > 0.5));
+                self.last_value = if value > 0.5 { 1. } else { 0. };
+            } else {
+                bundle.send_parameter(addr, OscType::Float(value));
+                self.last_value = value;
+            }
+        }
+
+        let magnitude = if let Some(addr) = self.neg_address.as_ref() {
+            self.last_neg = value < 0.;
+            bundle.send_parameter(addr, OscType::Bool(self.last_neg));
+            value.abs()
+        } else {
+            value.max(0.)
+        };
+
+        if self.num_bits == 0 {
+            return;
+        }
+        let value = (magnitude * ((1 << self.num_bits) - 1) as f32) as i32;
+        self.last_quantized = Some(value);
+        for (idx, param) in self.addresses.iter().enumerate().take(self.num_bits) {
+            self.last_bits[idx] = value & (1 << idx) != 0;
+            if let Some(addr) = param.as_ref() {
+                bundle.send_parameter(addr, OscType::Bool(self.last_bits[idx]));
+            }
+        }
+    }
+
+    /// Sends the last sent values again, regardless of whether they changed.
+    /// Used to refresh receivers that may have lost their state while sending is paused.
+    pub fn resend(&self, bundle: &mut OscBundle) {
+        if let Some(addr) = self.main_address.as_ref() {
+            if self.main_is_bool {
+                bundle.send_parameter(addr, OscType::Bool(self.last_value > 0.5));
+            } else {
+                bundle.send_parameter(addr, OscType::Float(self.last_value));
+            }
+        }
+        if let Some(addr) = self.neg_address.as_ref() {
+            bundle.send_parameter(addr, OscType::Bool(self.last_neg));
+        }
+        for (idx, param) in self.addresses.iter().enumerate().take(self.num_bits) {
+            if let Some(addr) = param.as_ref() {
+                bundle.send_parameter(addr, OscType::Bool(self.last_bits[idx]));
+            }
+        }
+    }
 }
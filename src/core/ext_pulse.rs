@@ -0,0 +1,114 @@
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use rosc::{OscBundle, OscType};
+
+use crate::{
+    core::ext_tracking::unified::{CombinedExpression, UnifiedExpressions},
+    Args,
+};
+
+use super::{bundle::AvatarBundle, ext_tracking::ExtTracking};
+
+/// A single expression → parameter pulse binding.
+struct PulseTrigger {
+    /// The index of the watched shape inside the `UnifiedShapes` array.
+    idx: usize,
+    /// The value the shape must rise above to fire the pulse.
+    threshold: f32,
+    /// The bool avatar parameter that is pulsed.
+    param: String,
+    /// Whether the shape was above the threshold on the last frame, so that a pulse only fires on the rising edge.
+    above: bool,
+    /// When the currently running pulse ends, if any.
+    pulse_end: Option<Instant>,
+}
+
+/// Pulses bool avatar parameters when an expression crosses a threshold.
+///
+/// Some worlds react to specific avatar parameters, e.g. to play a sound or trigger haptics.
+/// When a watched shape rises above its threshold, the bound parameter is set to `true`,
+/// and back to `false` once the pulse duration has passed. The shape must drop below the
+/// threshold again before the next pulse can fire.
+pub struct ExtPulse {
+    triggers: Vec<PulseTrigger>,
+    /// How long each pulse stays `true`.
+    duration: Duration,
+}
+
+impl ExtPulse {
+    /// Creates a new `ExtPulse` from the `--expression-pulse` bindings.
+    /// Each binding has the form `Shape:threshold:Param`, where `Shape` is a unified or combined expression.
+    pub fn new(args: &Args) -> Self {
+        let triggers: Vec<PulseTrigger> = args
+            .expression_pulse
+            .iter()
+            .filter_map(|binding| {
+                let trigger = parse_trigger(binding);
+                if trigger.is_none() {
+                    log::warn!(
+                        "Expression pulse: invalid binding {}, expected Shape:threshold:Param",
+                        binding
+                    );
+                }
+                trigger
+            })
+            .collect();
+
+        if !triggers.is_empty() {
+            log::info!(
+                "Expression pulse: {} binding(s), {}ms",
+                triggers.len(),
+                args.expression_pulse_ms
+            );
+        }
+
+        Self {
+            triggers,
+            duration: Duration::from_millis(args.expression_pulse_ms),
+        }
+    }
+
+    /// Fires and ends pulses based on the current (processed) tracking data.
+    pub fn step(&mut self, tracking: &ExtTracking, bundle: &mut OscBundle) {
+        let now = Instant::now();
+
+        for trigger in self.triggers.iter_mut() {
+            // End the running pulse once its time is up.
+            if trigger.pulse_end.is_some_and(|end| now >= end) {
+                bundle.send_parameter(&trigger.param, OscType::Bool(false));
+                trigger.pulse_end = None;
+            }
+
+            let above = tracking.data.shapes[trigger.idx] > trigger.threshold;
+            if above && !trigger.above && trigger.pulse_end.is_none() {
+                bundle.send_parameter(&trigger.param, OscType::Bool(true));
+                trigger.pulse_end = Some(now + self.duration);
+            }
+            trigger.above = above;
+        }
+    }
+}
+
+/// Parses a `Shape:threshold:Param` binding.
+fn parse_trigger(binding: &str) -> Option<PulseTrigger> {
+    let mut parts = binding.splitn(3, ':');
+    let shape = parts.next()?;
+    let threshold = parts.next()?.parse::<f32>().ok()?;
+    let param = parts.next().filter(|p| !p.is_empty())?;
+
+    let idx = UnifiedExpressions::from_str(shape)
+        .map(|e| e as usize)
+        .or_else(|_| CombinedExpression::from_str(shape).map(|e| e as usize))
+        .ok()?;
+
+    Some(PulseTrigger {
+        idx,
+        threshold,
+        param: param.to_string(),
+        above: false,
+        pulse_end: None,
+    })
+}
@@ -0,0 +1,64 @@
+//! Startup self-checks for the enum layouts that the face mappings rely on.
+//!
+//! The mappings convert between enums with `as usize` casts, and index the shape arrays by discriminant.
+//! Reordering or extending one of the enums can silently send data to the wrong shape, so the
+//! invariants are verified once at startup, and the application refuses to run if any of them break.
+
+use strum::{EnumCount, IntoEnumIterator};
+
+use super::{
+    face2_fb,
+    sranipal::SRanipalExpression,
+    unified::{CombinedExpression, UnifiedExpressions, NUM_SHAPES},
+};
+
+/// Verifies the enum alignment, panicking with a description of the first broken invariant.
+pub fn verify() {
+    // `NUM_SHAPES` must hold exactly the unified shapes followed by the combined shapes.
+    assert_eq!(
+        NUM_SHAPES,
+        UnifiedExpressions::COUNT + CombinedExpression::COUNT,
+        "NUM_SHAPES must equal the unified plus the combined expression count"
+    );
+
+    // The discriminants must match the iteration order, since shape names are resolved with `iter().nth(idx)`.
+    for (idx, e) in UnifiedExpressions::iter().enumerate() {
+        assert_eq!(
+            e as usize, idx,
+            "UnifiedExpressions::{:?} is out of order",
+            e
+        );
+    }
+    for (idx, e) in CombinedExpression::iter().enumerate() {
+        assert_eq!(
+            e as usize,
+            UnifiedExpressions::COUNT + idx,
+            "CombinedExpression::{:?} must follow the unified expressions without gaps",
+            e
+        );
+    }
+
+    // Every SRanipal name must resolve to an existing unified or combined shape.
+    for e in SRanipalExpression::iter() {
+        assert!(
+            (e as usize) < NUM_SHAPES,
+            "SRanipalExpression::{:?} maps to index {}, outside of the {} shapes",
+            e,
+            e as usize,
+            NUM_SHAPES
+        );
+    }
+
+    face2_fb::verify_alignment();
+}
+
+/// Checks that a shape index (e.g. of a default parameter) is inside the shapes array.
+pub fn verify_index(idx: usize, what: &str) {
+    assert!(
+        idx < NUM_SHAPES,
+        "{} has index {}, outside of the {} shapes",
+        what,
+        idx,
+        NUM_SHAPES
+    );
+}
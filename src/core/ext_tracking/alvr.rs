@@ -19,18 +19,26 @@ use strum::EnumCount;
 use sysinfo::ProcessesToUpdate;
 use websocket_lite::{ClientBuilder, Message, Opcode};
 
-use crate::core::{
-    ext_tracking::face2_fb::face2_fb_to_unified, AppState, INSTRUCTIONS_END, INSTRUCTIONS_START,
-    TRACK_ON,
+use crate::{
+    core::{
+        ext_tracking::face2_fb::face2_fb_to_unified, status::StatusPriority, AppState,
+        INSTRUCTIONS_END, INSTRUCTIONS_START, TRACK_ON,
+    },
+    Args,
 };
 
 use super::{
-    unified::{UnifiedExpressions, UnifiedTrackingData, NUM_SHAPES},
+    unified::{UnifiedExpressions, UnifiedShapeAccessors, UnifiedTrackingData, NUM_SHAPES},
     FaceReceiver,
 };
 
 static STA_ON: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "ALVR".color(Color::Green)).into());
 static STA_OFF: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "ALVR".color(Color::Red)).into());
+static STA_GAZE: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "GAZE".color(Color::Green)).into());
+static STA_GAZE_OFF: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "GAZE".color(Color::Red)).into());
+
+/// How long tracking data may arrive without any eye gaze before that is reported.
+const GAZE_MISSING_AFTER: Duration = Duration::from_secs(10);
 
 #[derive(Default)]
 struct AlvrTrackingData {
@@ -46,15 +54,31 @@ pub(super) struct AlvrReceiver {
     sender: SyncSender<Box<AlvrTrackingData>>,
     receiver: Receiver<Box<AlvrTrackingData>>,
     last_received: Instant,
+    /// When eye gaze was last received, if ever.
+    last_gaze: Option<Instant>,
+    /// When the first tracking message was received, to tell a missing gaze from a missing connection.
+    first_received: Option<Instant>,
+    /// Whether the missing gaze was already reported.
+    gaze_warned: bool,
+    /// The eye pitch (in degrees, looking down) at which the eyes start to close, without face data.
+    blink_pitch_offset: f32,
+    /// The eye pitch range (in degrees) over which the eyes go from open to fully closed.
+    blink_pitch_range: f32,
 }
 
 impl AlvrReceiver {
-    pub fn new() -> Self {
+    pub fn new(args: &Args) -> Self {
         let (sender, receiver) = std::sync::mpsc::sync_channel(8);
         Self {
             sender,
             receiver,
             last_received: Instant::now(),
+            last_gaze: None,
+            first_received: None,
+            gaze_warned: false,
+            blink_pitch_offset: args.blink_pitch_offset,
+            // Guard against dividing by zero.
+            blink_pitch_range: args.blink_pitch_range.max(1.),
         }
     }
 }
@@ -85,6 +109,7 @@ impl FaceReceiver for AlvrReceiver {
         log::info!("");
         log::info!("Status bar tickers:");
         log::info!("• {} → face and/or eye data is being received", *STA_ON);
+        log::info!("• {} → eye gaze is being received", *STA_GAZE);
         log::info!(
             "• {} → head & wrist data is being received (for AutoPilot)",
             *TRACK_ON
@@ -99,22 +124,43 @@ impl FaceReceiver for AlvrReceiver {
 
     fn receive(&mut self, data: &mut UnifiedTrackingData, state: &mut AppState) {
         for new_data in self.receiver.try_iter() {
+            self.first_received.get_or_insert_with(Instant::now);
+
             if let Some(new_left) = new_data.eye[0] {
                 data.eyes[0] = Some(new_left);
+                data.last_eyes_received = Some(Instant::now());
+                self.last_gaze = data.last_eyes_received;
             }
             if let Some(new_right) = new_data.eye[1] {
                 data.eyes[1] = Some(new_right);
+                data.last_eyes_received = Some(Instant::now());
+                self.last_gaze = data.last_eyes_received;
+            }
+
+            // Without face data (e.g. with the VRChat Eye OSC preset), nothing else closes the eyes,
+            // so they're closed by looking down, as with OpenXR.
+            let face_live = self.last_received.elapsed() < Duration::from_secs(1);
+            if let Some(eye) = data.eyes[0].filter(|_| new_data.shapes.is_none() && !face_live) {
+                let eye_closed = ((eye.x.to_degrees() + self.blink_pitch_offset)
+                    / -self.blink_pitch_range)
+                    .max(0.0);
+                data.shapes
+                    .setu(UnifiedExpressions::EyeClosedLeft, eye_closed);
+                data.shapes
+                    .setu(UnifiedExpressions::EyeClosedRight, eye_closed);
             }
             if let Some(new_shapes) = new_data.shapes {
                 data.shapes[..=UnifiedExpressions::COUNT]
                     .copy_from_slice(&new_shapes[..=UnifiedExpressions::COUNT]);
                 self.last_received = Instant::now();
+                data.last_face_received = Some(self.last_received);
             }
 
             if let Some(head) = new_data.head {
                 state.tracking.head =
                     glam::Affine3A::from_rotation_translation(head.orientation, head.position);
-                state.tracking.last_received = Instant::now();
+                state.tracking.last_received = Some(Instant::now());
+                state.tracking.received_over_osc = false;
             }
 
             if let Some(left_hand) = new_data.hands[0] {
@@ -133,9 +179,40 @@ impl FaceReceiver for AlvrReceiver {
         }
 
         if self.last_received.elapsed() < Duration::from_secs(1) {
-            state.status.add_item(STA_ON.clone());
+            state
+                .status
+                .add_state(STA_ON.clone(), true, StatusPriority::High);
         } else {
-            state.status.add_item(STA_OFF.clone());
+            state
+                .status
+                .add_state(STA_OFF.clone(), false, StatusPriority::High);
+        }
+
+        if self
+            .last_gaze
+            .is_some_and(|t| t.elapsed() < Duration::from_secs(1))
+        {
+            state
+                .status
+                .add_state(STA_GAZE.clone(), true, StatusPriority::High);
+        } else {
+            state
+                .status
+                .add_state(STA_GAZE_OFF.clone(), false, StatusPriority::High);
+        }
+
+        // Tracking arrives, but never any gaze: the eyes would stay frozen without explanation.
+        if !self.gaze_warned
+            && self.last_gaze.is_none()
+            && self
+                .first_received
+                .is_some_and(|t| t.elapsed() > GAZE_MISSING_AFTER)
+        {
+            log::warn!(
+                "ALVR: no eye gaze is being received. Check the {} preset in ALVR.",
+                "Eye and Face tracking".color(Color::BrightYellow)
+            );
+            self.gaze_warned = true;
         }
     }
 }
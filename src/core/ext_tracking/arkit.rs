@@ -0,0 +1,175 @@
+//! Converts ARKit ("perfect sync") blendshapes, as sent over VMC or reported by the Pico face tracker.
+
+use std::collections::HashMap;
+
+use super::unified::{UnifiedExpressions, UnifiedShapeAccessors, UnifiedShapes, NUM_SHAPES};
+
+/// Converts ARKit blendshapes, keyed by lowercase name, into the application's `UnifiedShapes` format.
+/// Missing blendshapes are treated as 0.
+pub(super) fn arkit_to_unified(values: &HashMap<String, f32>) -> UnifiedShapes {
+    let mut shapes: UnifiedShapes = [0.0; NUM_SHAPES];
+    let geta = |name: &str| values.get(name).copied().unwrap_or(0.0);
+
+    // --- Eye Tracking ---
+    // For the right eye, looking "out" is looking right; for the left eye, looking "in" is.
+    shapes.setu(
+        UnifiedExpressions::EyeRightX,
+        geta("eyelookoutright") - geta("eyelookinright"),
+    );
+    shapes.setu(
+        UnifiedExpressions::EyeLeftX,
+        geta("eyelookinleft") - geta("eyelookoutleft"),
+    );
+    shapes.setu(
+        UnifiedExpressions::EyeY,
+        (geta("eyelookupleft") + geta("eyelookupright")
+            - geta("eyelookdownleft")
+            - geta("eyelookdownright"))
+            * 0.5,
+    );
+
+    shapes.setu(UnifiedExpressions::EyeClosedLeft, geta("eyeblinkleft"));
+    shapes.setu(UnifiedExpressions::EyeClosedRight, geta("eyeblinkright"));
+    shapes.setu(UnifiedExpressions::EyeSquintLeft, geta("eyesquintleft"));
+    shapes.setu(UnifiedExpressions::EyeSquintRight, geta("eyesquintright"));
+    shapes.setu(UnifiedExpressions::EyeWideLeft, geta("eyewideleft"));
+    shapes.setu(UnifiedExpressions::EyeWideRight, geta("eyewideright"));
+
+    // --- Brow Tracking ---
+    shapes.setu(UnifiedExpressions::BrowPinchLeft, geta("browdownleft"));
+    shapes.setu(UnifiedExpressions::BrowPinchRight, geta("browdownright"));
+    shapes.setu(UnifiedExpressions::BrowLowererLeft, geta("browdownleft"));
+    shapes.setu(UnifiedExpressions::BrowLowererRight, geta("browdownright"));
+    // ARKit only has a single inner brow raiser.
+    shapes.setu(UnifiedExpressions::BrowInnerUpLeft, geta("browinnerup"));
+    shapes.setu(UnifiedExpressions::BrowInnerUpRight, geta("browinnerup"));
+    shapes.setu(UnifiedExpressions::BrowOuterUpLeft, geta("browouterupleft"));
+    shapes.setu(
+        UnifiedExpressions::BrowOuterUpRight,
+        geta("browouterupright"),
+    );
+
+    // --- Cheek Tracking ---
+    shapes.setu(UnifiedExpressions::CheekSquintLeft, geta("cheeksquintleft"));
+    shapes.setu(
+        UnifiedExpressions::CheekSquintRight,
+        geta("cheeksquintright"),
+    );
+    // ARKit only has a single cheek puff.
+    shapes.setu(UnifiedExpressions::CheekPuffLeft, geta("cheekpuff"));
+    shapes.setu(UnifiedExpressions::CheekPuffRight, geta("cheekpuff"));
+
+    // --- Jaw and Mouth Tracking ---
+    shapes.setu(UnifiedExpressions::JawOpen, geta("jawopen"));
+    shapes.setu(UnifiedExpressions::JawLeft, geta("jawleft"));
+    shapes.setu(UnifiedExpressions::JawRight, geta("jawright"));
+    shapes.setu(UnifiedExpressions::JawForward, geta("jawforward"));
+    shapes.setu(UnifiedExpressions::MouthClosed, geta("mouthclose"));
+
+    // --- Lip Suck, Funnel and Pucker ---
+    let roll_upper = geta("mouthrollupper");
+    let roll_lower = geta("mouthrolllower");
+    shapes.setu(UnifiedExpressions::LipSuckUpperLeft, roll_upper);
+    shapes.setu(UnifiedExpressions::LipSuckUpperRight, roll_upper);
+    shapes.setu(UnifiedExpressions::LipSuckLowerLeft, roll_lower);
+    shapes.setu(UnifiedExpressions::LipSuckLowerRight, roll_lower);
+
+    let funnel = geta("mouthfunnel");
+    shapes.setu(UnifiedExpressions::LipFunnelUpperLeft, funnel);
+    shapes.setu(UnifiedExpressions::LipFunnelUpperRight, funnel);
+    shapes.setu(UnifiedExpressions::LipFunnelLowerLeft, funnel);
+    shapes.setu(UnifiedExpressions::LipFunnelLowerRight, funnel);
+
+    let pucker = geta("mouthpucker");
+    shapes.setu(UnifiedExpressions::LipPuckerUpperLeft, pucker);
+    shapes.setu(UnifiedExpressions::LipPuckerUpperRight, pucker);
+    shapes.setu(UnifiedExpressions::LipPuckerLowerLeft, pucker);
+    shapes.setu(UnifiedExpressions::LipPuckerLowerRight, pucker);
+
+    shapes.setu(UnifiedExpressions::NoseSneerLeft, geta("nosesneerleft"));
+    shapes.setu(UnifiedExpressions::NoseSneerRight, geta("nosesneerright"));
+
+    // --- Mouth Upper and Lower Lip Movement ---
+    shapes.setu(
+        UnifiedExpressions::MouthUpperUpLeft,
+        geta("mouthupperupleft"),
+    );
+    shapes.setu(
+        UnifiedExpressions::MouthUpperUpRight,
+        geta("mouthupperupright"),
+    );
+    shapes.setu(
+        UnifiedExpressions::MouthUpperDeepenLeft,
+        geta("mouthupperupleft"),
+    );
+    shapes.setu(
+        UnifiedExpressions::MouthUpperDeepenRight,
+        geta("mouthupperupright"),
+    );
+    shapes.setu(
+        UnifiedExpressions::MouthLowerDownLeft,
+        geta("mouthlowerdownleft"),
+    );
+    shapes.setu(
+        UnifiedExpressions::MouthLowerDownRight,
+        geta("mouthlowerdownright"),
+    );
+
+    // --- Mouth Horizontal Movement ---
+    shapes.setu(UnifiedExpressions::MouthUpperLeft, geta("mouthleft"));
+    shapes.setu(UnifiedExpressions::MouthLowerLeft, geta("mouthleft"));
+    shapes.setu(UnifiedExpressions::MouthUpperRight, geta("mouthright"));
+    shapes.setu(UnifiedExpressions::MouthLowerRight, geta("mouthright"));
+
+    // --- Mouth Corner and Slant ---
+    shapes.setu(
+        UnifiedExpressions::MouthCornerPullLeft,
+        geta("mouthsmileleft"),
+    );
+    shapes.setu(
+        UnifiedExpressions::MouthCornerPullRight,
+        geta("mouthsmileright"),
+    );
+    shapes.setu(
+        UnifiedExpressions::MouthCornerSlantLeft,
+        geta("mouthsmileleft"),
+    );
+    shapes.setu(
+        UnifiedExpressions::MouthCornerSlantRight,
+        geta("mouthsmileright"),
+    );
+
+    // --- Mouth Frown, Stretch, Dimple and Press ---
+    shapes.setu(UnifiedExpressions::MouthFrownLeft, geta("mouthfrownleft"));
+    shapes.setu(UnifiedExpressions::MouthFrownRight, geta("mouthfrownright"));
+    shapes.setu(
+        UnifiedExpressions::MouthStretchLeft,
+        geta("mouthstretchleft"),
+    );
+    shapes.setu(
+        UnifiedExpressions::MouthStretchRight,
+        geta("mouthstretchright"),
+    );
+    shapes.setu(UnifiedExpressions::MouthDimpleLeft, geta("mouthdimpleleft"));
+    shapes.setu(
+        UnifiedExpressions::MouthDimpleRight,
+        geta("mouthdimpleright"),
+    );
+    shapes.setu(UnifiedExpressions::MouthPressLeft, geta("mouthpressleft"));
+    shapes.setu(UnifiedExpressions::MouthPressRight, geta("mouthpressright"));
+
+    // --- Mouth Raisers ---
+    shapes.setu(
+        UnifiedExpressions::MouthRaiserUpper,
+        geta("mouthshrugupper"),
+    );
+    shapes.setu(
+        UnifiedExpressions::MouthRaiserLower,
+        geta("mouthshruglower"),
+    );
+
+    // --- Tongue Tracking ---
+    shapes.setu(UnifiedExpressions::TongueOut, geta("tongueout"));
+
+    shapes
+}
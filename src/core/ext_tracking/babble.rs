@@ -2,6 +2,7 @@ use std::{
     collections::HashMap,
     net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
     sync::{
+        atomic::{AtomicBool, Ordering},
         mpsc::{sync_channel, Receiver, SyncSender},
         Arc,
     },
@@ -10,12 +11,14 @@ use std::{
 };
 
 use colored::{Color, Colorize};
+use glam::vec3;
 use once_cell::sync::Lazy;
 use rosc::{OscPacket, OscType};
 
 use crate::core::{
-    ext_tracking::unified::UnifiedExpressions, AppState, INSTRUCTIONS_END, INSTRUCTIONS_START,
-    TRACK_ON,
+    ext_tracking::unified::{UnifiedExpressions, UnifiedShapeAccessors},
+    status::StatusPriority,
+    AppState, INSTRUCTIONS_END, INSTRUCTIONS_START, TRACK_ON,
 };
 
 use super::{unified::UnifiedTrackingData, FaceReceiver};
@@ -24,36 +27,65 @@ static STA_BABL1: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "BABBLE".color(Col
 static STA_BABL0: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "BABBLE".color(Color::Red)).into());
 static STA_ETVR1: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "ETVR".color(Color::Green)).into());
 static STA_ETVR0: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "ETVR".color(Color::Red)).into());
+static STA_NOPORT: Lazy<Arc<str>> =
+    Lazy::new(|| format!("{}", "BABBLE:PORT".color(Color::BrightRed)).into());
 
+/// How far (in degrees) the eyes turn at the ends of ETVR's -1 to 1 gaze range.
+const ETVR_GAZE_RANGE_DEG: f32 = 45.;
+
+/// Receives mouth data from Project Babble and eye data from EyeTrackVR.
+///
+/// Both can share one port, or each can send to its own port:
+/// - The face port expects Babble's `/<blendshape>` addresses, e.g. `/jawOpen` or `/mouthSmileLeft`.
+/// - The eye port expects ETVR's `/avatar/parameters/LeftEyeX` style or `/avatar/parameters/v2/EyeLeftX` style addresses.
 pub(super) struct BabbleEtvrReceiver {
-    listen_port: u16,
+    face_port: u16,
+    eye_port: u16,
     sender: SyncSender<Box<BabbleEtvrEvent>>,
     receiver: Receiver<Box<BabbleEtvrEvent>>,
     last_received_babble: Instant,
     last_received_etvr: Instant,
+    /// Whether the face port is currently bound. False while retrying after a failed bind.
+    listening_face: Arc<AtomicBool>,
+    /// Whether the eye port is currently bound. The same flag as `listening_face` if both share a port.
+    listening_eye: Arc<AtomicBool>,
 }
 
 impl BabbleEtvrReceiver {
-    pub fn new(listen_port: u16) -> Self {
+    pub fn new(face_port: u16, eye_port: u16) -> Self {
         let (sender, receiver) = sync_channel(128);
+        let listening_face = Arc::new(AtomicBool::new(false));
+        let listening_eye = if face_port == eye_port {
+            listening_face.clone()
+        } else {
+            Arc::new(AtomicBool::new(false))
+        };
         Self {
-            listen_port,
+            face_port,
+            eye_port,
             sender,
             receiver,
             last_received_babble: Instant::now(),
             last_received_etvr: Instant::now(),
+            listening_face,
+            listening_eye,
         }
     }
 }
 
 impl FaceReceiver for BabbleEtvrReceiver {
     fn start_loop(&mut self) {
-        let sender = self.sender.clone();
-        let listen_port = self.listen_port;
+        let face_port = self.face_port;
+        let eye_port = self.eye_port;
 
-        let babble_recv_port = listen_port + 10;
+        let babble_recv_port = face_port + 10;
         let babble_http_port = babble_recv_port + 1;
-        let etvr_recv_port = babble_recv_port + 10;
+        // The ETVR ports follow the eye port, unless it's shared with Babble and would clash.
+        let etvr_recv_port = if face_port == eye_port {
+            babble_recv_port + 10
+        } else {
+            eye_port + 10
+        };
         let etvr_http_port = etvr_recv_port + 1;
 
         log::info!("{}", *INSTRUCTIONS_START);
@@ -65,7 +97,7 @@ impl FaceReceiver for BabbleEtvrReceiver {
         log::info!(
             "• Set {} to {}",
             "Port".color(Color::BrightYellow),
-            format!("{}", listen_port).color(Color::Cyan),
+            format!("{}", face_port).color(Color::Cyan),
         );
         log::info!(
             "• Set {} to {}",
@@ -86,7 +118,7 @@ impl FaceReceiver for BabbleEtvrReceiver {
         log::info!(
             "• Set {} to {}",
             "OSC Port".color(Color::BrightYellow),
-            format!("{}", listen_port).color(Color::Cyan),
+            format!("{}", eye_port).color(Color::Cyan),
         );
         log::info!(
             "• Set {} to {}",
@@ -103,6 +135,7 @@ impl FaceReceiver for BabbleEtvrReceiver {
         log::info!("Status bar tickers:");
         log::info!("• {} → mouth data is being received", *STA_BABL1);
         log::info!("• {} → eye data is being received", *STA_ETVR1);
+        log::info!("• {} → a listen port could not be bound", *STA_NOPORT);
         log::info!(
             "• {} → head & wrist data is being received (for AutoPilot)",
             *TRACK_ON
@@ -114,37 +147,116 @@ impl FaceReceiver for BabbleEtvrReceiver {
         log::info!("");
         log::info!("{}", *INSTRUCTIONS_END);
 
-        thread::spawn(move || babble_loop(listen_port, sender));
+        if face_port == eye_port {
+            let sender = self.sender.clone();
+            let listening = self.listening_face.clone();
+            thread::spawn(move || babble_loop(face_port, PortKind::Both, sender, listening));
+        } else {
+            let sender = self.sender.clone();
+            let listening = self.listening_face.clone();
+            thread::spawn(move || babble_loop(face_port, PortKind::Face, sender, listening));
+
+            let sender = self.sender.clone();
+            let listening = self.listening_eye.clone();
+            thread::spawn(move || babble_loop(eye_port, PortKind::Eye, sender, listening));
+        }
     }
 
     fn receive(&mut self, data: &mut UnifiedTrackingData, state: &mut AppState) {
+        let mut eyes_received = false;
         for event in self.receiver.try_iter() {
             data.shapes[event.expression as usize] = event.value;
 
-            if (event.expression as usize) < (UnifiedExpressions::BrowPinchRight as usize) {
+            if is_eye(event.expression) {
                 self.last_received_etvr = Instant::now();
+                eyes_received = true;
             } else {
                 self.last_received_babble = Instant::now();
+                data.last_face_received = Some(self.last_received_babble);
             }
         }
 
+        // Turn the gaze shapes into eye rotations. Positive X looks right and positive Y looks up,
+        // which is the opposite of the internal yaw and the same as the internal pitch.
+        if eyes_received {
+            data.last_eyes_received = Some(self.last_received_etvr);
+            let range = ETVR_GAZE_RANGE_DEG.to_radians();
+            let pitch = data.shapes.getu(UnifiedExpressions::EyeY) * range;
+            data.eyes = [
+                Some(vec3(
+                    pitch,
+                    -data.shapes.getu(UnifiedExpressions::EyeLeftX) * range,
+                    0.,
+                )),
+                Some(vec3(
+                    pitch,
+                    -data.shapes.getu(UnifiedExpressions::EyeRightX) * range,
+                    0.,
+                )),
+            ];
+        }
+
+        if !self.listening_face.load(Ordering::Relaxed)
+            || !self.listening_eye.load(Ordering::Relaxed)
+        {
+            state
+                .status
+                .add_item_priority(STA_NOPORT.clone(), StatusPriority::High);
+        }
+
         if self.last_received_babble.elapsed() < Duration::from_secs(1) {
-            state.status.add_item(STA_BABL1.clone());
+            state
+                .status
+                .add_state(STA_BABL1.clone(), true, StatusPriority::High);
         } else {
-            state.status.add_item(STA_BABL0.clone());
+            state
+                .status
+                .add_state(STA_BABL0.clone(), false, StatusPriority::High);
         }
 
         if self.last_received_etvr.elapsed() < Duration::from_secs(1) {
-            state.status.add_item(STA_ETVR1.clone());
+            state
+                .status
+                .add_state(STA_ETVR1.clone(), true, StatusPriority::High);
         } else {
-            state.status.add_item(STA_ETVR0.clone());
+            state
+                .status
+                .add_state(STA_ETVR0.clone(), false, StatusPriority::High);
         }
     }
 }
 
-fn babble_loop(listen_port: u16, mut sender: SyncSender<Box<BabbleEtvrEvent>>) {
+/// Which data a listen port takes.
+#[derive(Clone, Copy)]
+enum PortKind {
+    Face,
+    Eye,
+    Both,
+}
+
+impl PortKind {
+    fn accepts(self, expression: UnifiedExpressions) -> bool {
+        match self {
+            PortKind::Face => !is_eye(expression),
+            PortKind::Eye => is_eye(expression),
+            PortKind::Both => true,
+        }
+    }
+}
+
+/// Eye shapes come first in `UnifiedExpressions`, up to `BrowPinchRight`.
+fn is_eye(expression: UnifiedExpressions) -> bool {
+    (expression as usize) < (UnifiedExpressions::BrowPinchRight as usize)
+}
+
+fn babble_loop(
+    listen_port: u16,
+    kind: PortKind,
+    mut sender: SyncSender<Box<BabbleEtvrEvent>>,
+    listening: Arc<AtomicBool>,
+) {
     loop {
-        if let Some(()) = receive_babble_osc(listen_port, &mut sender) {
+        if let Some(()) = receive_babble_osc(listen_port, kind, &mut sender, &listening) {
             break;
         } else {
             thread::sleep(Duration::from_millis(5000));
@@ -154,10 +266,25 @@ fn babble_loop(listen_port: u16, mut sender: SyncSender<Box<BabbleEtvrEvent>>) {
 
 fn receive_babble_osc(
     listen_port: u16,
+    kind: PortKind,
     sender: &mut SyncSender<Box<BabbleEtvrEvent>>,
+    listening: &AtomicBool,
 ) -> Option<()> {
     let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
-    let listener = UdpSocket::bind(SocketAddr::new(ip, listen_port)).expect("bind listener socket");
+    // Don't crash if the port is taken; babble_loop will retry in a few seconds.
+    let listener = match UdpSocket::bind(SocketAddr::new(ip, listen_port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!(
+                "Babble port {} is in use — is another tracker running? ({}) Retrying in 5s.",
+                listen_port,
+                e
+            );
+            listening.store(false, Ordering::Relaxed);
+            return None;
+        }
+    };
+    listening.store(true, Ordering::Relaxed);
     let mut buf = [0u8; rosc::decoder::MTU];
     loop {
         if let Ok((size, _addr)) = listener.recv_from(&mut buf) {
@@ -166,7 +293,7 @@ fn receive_babble_osc(
                     log::warn!("Babble/ETVR OSC Message has no args?");
                 } else if let OscType::Float(x) = packet.args[0] {
                     if let Some(expv) = ADDR_TO_UNIFIED.get(packet.addr.as_str()).cloned() {
-                        for exp in expv.iter() {
+                        for exp in expv.iter().filter(|exp| kind.accepts(**exp)) {
                             let event = Box::new(BabbleEtvrEvent::new(*exp, x));
                             if let Err(e) = sender.try_send(event) {
                                 log::warn!("Failed to send Babble/ETVR message: {}", e);
@@ -0,0 +1,49 @@
+use std::time::{Duration, Instant};
+
+/// Limits how often something happens, independent of how often the main loop ticks.
+///
+/// Used to decouple receiving face data from applying it: each side only runs when its own
+/// interval has passed, and works on the latest buffered data in between.
+pub struct Cadence {
+    /// The minimum time between two runs.
+    interval: Duration,
+    /// When this last ran.
+    last: Option<Instant>,
+}
+
+impl Cadence {
+    /// Creates a new `Cadence` running at most `hz` times per second.
+    /// Returns `None` for 0, which means running on every tick.
+    pub fn new(hz: f32) -> Option<Self> {
+        if hz <= 0. {
+            return None;
+        }
+
+        Some(Self {
+            interval: Duration::from_secs_f32(1. / hz),
+            last: None,
+        })
+    }
+
+    /// Returns `true` if the interval has passed since the last run, and records a new run.
+    pub fn due(&mut self) -> bool {
+        let now = Instant::now();
+        match self.last {
+            Some(last) if now.duration_since(last) < self.interval => false,
+            Some(last) => {
+                // Keep the schedule steady, but don't try to catch up after a long stall.
+                let next = last + self.interval;
+                self.last = Some(if now.duration_since(next) < self.interval {
+                    next
+                } else {
+                    now
+                });
+                true
+            }
+            None => {
+                self.last = Some(now);
+                true
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use rosc::OscType;
+use strum::{EnumCount, IntoEnumIterator};
+
+use crate::core::{folders::CONFIG_DIR, AvatarParameters};
+
+use super::unified::{UnifiedExpressions, UnifiedShapes, UnifiedTrackingData};
+
+const FILE_NAME: &str = "oscavmgr-baseline.json";
+/// How long the neutral face is recorded for.
+const RECORD_TIME: Duration = Duration::from_secs(3);
+/// The first shape that is calibrated. The gaze axes before it are signed and have no rest value.
+const FIRST_SHAPE: usize = UnifiedExpressions::EyeClosedRight as usize;
+
+/// A running neutral face recording.
+struct Recording {
+    sum: [f32; UnifiedExpressions::COUNT],
+    frames: u32,
+    /// When the recording ends. Only set once face data is received, so that the time
+    /// spent waiting for the tracker doesn't count.
+    until: Option<Instant>,
+}
+
+/// Removes the rest values that some trackers report for a neutral face, e.g. a slightly open mouth.
+///
+/// A calibration is started with `--calibrate` or by setting the `FTCalibrate` avatar parameter.
+/// The unified shapes are then averaged over a few seconds of neutral face, and that baseline
+/// is subtracted from every frame from then on. The baseline is saved in `CONFIG_DIR`,
+/// and loaded again on the next start.
+pub struct Calibration {
+    path: String,
+    /// The rest value of each unified shape.
+    baseline: Option<[f32; UnifiedExpressions::COUNT]>,
+    recording: Option<Recording>,
+    /// Whether `FTCalibrate` was set on the last frame, so that a calibration only starts on the rising edge.
+    triggered: bool,
+}
+
+impl Calibration {
+    /// Loads the saved baseline, and starts a new calibration right away if `calibrate` is set.
+    pub fn new(calibrate: bool) -> Self {
+        let path = format!("{}/{}", CONFIG_DIR.as_ref(), FILE_NAME);
+
+        let baseline = File::open(&path)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, HashMap<String, f32>>(file).ok())
+            .map(|saved| {
+                log::info!("Loaded face baseline from {}", &path);
+                let mut baseline = [0.; UnifiedExpressions::COUNT];
+                for (name, value) in saved.iter() {
+                    match UnifiedExpressions::from_str(name) {
+                        Ok(e) => baseline[e as usize] = *value,
+                        Err(_) => log::warn!("Face baseline: unknown shape {}, ignoring.", name),
+                    }
+                }
+                baseline
+            });
+
+        let mut me = Self {
+            path,
+            baseline,
+            recording: None,
+            triggered: false,
+        };
+        if calibrate {
+            me.start();
+        }
+        me
+    }
+
+    fn start(&mut self) {
+        log::info!(
+            "Calibrating: keep a neutral face for {}s.",
+            RECORD_TIME.as_secs()
+        );
+        self.recording = Some(Recording {
+            sum: [0.; UnifiedExpressions::COUNT],
+            frames: 0,
+            until: None,
+        });
+    }
+
+    /// Starts a calibration when `FTCalibrate` is set, and records the raw tracking data while calibrating.
+    pub fn update(&mut self, params: &AvatarParameters, raw: &UnifiedTrackingData) {
+        let triggered = matches!(params.get("FTCalibrate"), Some(OscType::Bool(true)));
+        if triggered && !self.triggered && self.recording.is_none() {
+            self.start();
+        }
+        self.triggered = triggered;
+
+        let Some(recording) = self.recording.as_mut() else {
+            return;
+        };
+
+        // Only record frames with live face data.
+        if !raw
+            .last_face_received
+            .is_some_and(|t| t.elapsed() < Duration::from_secs(1))
+        {
+            return;
+        }
+
+        let until = *recording
+            .until
+            .get_or_insert_with(|| Instant::now() + RECORD_TIME);
+        for (sum, value) in recording.sum.iter_mut().zip(raw.shapes.iter()) {
+            *sum += value;
+        }
+        recording.frames += 1;
+
+        if Instant::now() >= until {
+            let frames = recording.frames as f32;
+            let baseline = recording.sum.map(|sum| sum / frames);
+            self.recording = None;
+            self.baseline = Some(baseline);
+            self.save(&baseline);
+        }
+    }
+
+    /// Subtracts the baseline from the unified shapes, clamping at 0.
+    pub fn apply(&self, shapes: &mut UnifiedShapes) {
+        let Some(baseline) = self.baseline.as_ref() else {
+            return;
+        };
+        for (shape, rest) in shapes[FIRST_SHAPE..UnifiedExpressions::COUNT]
+            .iter_mut()
+            .zip(baseline[FIRST_SHAPE..].iter())
+        {
+            *shape = (*shape - rest).max(0.);
+        }
+    }
+
+    fn save(&self, baseline: &[f32; UnifiedExpressions::COUNT]) {
+        let saved: HashMap<&'static str, f32> = UnifiedExpressions::iter()
+            .skip(FIRST_SHAPE)
+            .map(|e| (e.into(), baseline[e as usize]))
+            .collect();
+
+        log::info!("Calibration done, saving face baseline to {}", &self.path);
+        let file = match File::create(&self.path) {
+            Ok(file) => file,
+            Err(e) => {
+                log::warn!("Could not write face baseline: {:?}", e);
+                return;
+            }
+        };
+        if let Err(e) = serde_json::to_writer_pretty(file, &saved) {
+            log::warn!("Could not write face baseline: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::{collections::HashMap, fs::File, io::Read, str::FromStr};
+
+use once_cell::sync::OnceCell;
+
+use super::unified::{
+    CombinedExpression, UnifiedExpressions, UnifiedShapeAccessors, UnifiedShapes,
+};
+
+/// User-provided formulas for combined expressions, replacing the built-in ones in `calc_combined`.
+static COMBINED_WEIGHTS: OnceCell<Vec<CombinedWeights>> = OnceCell::new();
+
+/// A validated formula: the target becomes the weighted sum of the unified source shapes.
+struct CombinedWeights {
+    target: CombinedExpression,
+    sources: Vec<(UnifiedExpressions, f32)>,
+}
+
+/// Loads the combined expression weights from a JSON file, keyed by `CombinedExpression` name,
+/// with the weight of each `UnifiedExpressions` input. For example, a weaker smile:
+///
+/// ```json
+/// {
+///   "MouthSmileLeft": { "MouthCornerPullLeft": 0.5, "MouthCornerSlantLeft": 0.15 },
+///   "MouthSmileRight": { "MouthCornerPullRight": 0.5, "MouthCornerSlantRight": 0.15 }
+/// }
+/// ```
+///
+/// The inputs and built-in weights of each combined expression are listed on `CombinedExpression`.
+/// Overridden values don't feed into other combined expressions, e.g. `SmileSadLeft` keeps using the
+/// built-in smile unless it is overridden as well. `Blush` is driven by parameters, not shapes, and can't be overridden.
+/// Invalid entries are reported and skipped; everything not overridden keeps the built-in formula.
+pub(super) fn load_combined_weights(path: &str) {
+    let mut json = String::new();
+    if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut json)) {
+        log::error!("Could not read combined weights file {}: {:?}", path, e);
+        return;
+    }
+
+    let entries: HashMap<String, HashMap<String, f32>> = match serde_json::from_str(&json) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::error!("Failed to parse combined weights file {}: {}", path, e);
+            return;
+        }
+    };
+
+    let weights: Vec<CombinedWeights> = entries
+        .into_iter()
+        .filter_map(|(target_name, inputs)| {
+            let target = match CombinedExpression::from_str(&target_name) {
+                Ok(CombinedExpression::Blush) => {
+                    log::warn!("Combined weights: Blush can't be overridden");
+                    return None;
+                }
+                Ok(target) => target,
+                Err(_) => {
+                    log::warn!(
+                        "Combined weights: unknown combined expression {}",
+                        target_name
+                    );
+                    return None;
+                }
+            };
+            let sources = inputs
+                .into_iter()
+                .filter_map(|(name, weight)| match UnifiedExpressions::from_str(&name) {
+                    Ok(source) => Some((source, weight)),
+                    Err(_) => {
+                        log::warn!(
+                            "Combined weights: unknown input {} for {}",
+                            name,
+                            target_name
+                        );
+                        None
+                    }
+                })
+                .collect();
+            Some(CombinedWeights { target, sources })
+        })
+        .collect();
+
+    log::info!(
+        "Loaded {} combined expression override(s) from {}",
+        weights.len(),
+        path
+    );
+    let _ = COMBINED_WEIGHTS.set(weights);
+}
+
+/// Replaces the built-in value of each overridden combined expression.
+pub(super) fn apply_combined_weights(shapes: &mut UnifiedShapes) {
+    let Some(weights) = COMBINED_WEIGHTS.get() else {
+        return;
+    };
+
+    for w in weights.iter() {
+        let value = w
+            .sources
+            .iter()
+            .map(|(source, weight)| shapes.getu(*source) * weight)
+            .sum();
+        shapes.setc(w.target, value);
+    }
+}
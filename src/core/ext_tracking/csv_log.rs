@@ -0,0 +1,86 @@
+use std::{
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+    sync::mpsc::{sync_channel, Receiver, SyncSender},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    shape_name,
+    unified::{UnifiedShapes, NUM_SHAPES},
+};
+
+/// How often the writer thread flushes the file.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Appends the processed shapes of every frame to a CSV file, for studying expression ranges in a spreadsheet.
+///
+/// The first column is the Unix time in seconds, followed by one column per unified and combined shape.
+/// The header is only written to a new (or empty) file, so that several sessions can be appended to one file.
+/// Unlike `SessionRecorder`, this logs the data as sent rather than as received, and can't be replayed.
+pub struct CsvLog {
+    sender: SyncSender<(f64, UnifiedShapes)>,
+}
+
+impl CsvLog {
+    /// Opens the file for appending and starts the writer thread. Returns `None` if the file can't be opened.
+    pub fn new(path: &str) -> Option<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| log::error!("Could not open CSV log {}: {:?}", path, e))
+            .ok()?;
+        let is_empty = file.metadata().map(|m| m.len() == 0).unwrap_or(true);
+        let mut writer = BufWriter::new(file);
+
+        if is_empty {
+            let names = (0..NUM_SHAPES).filter_map(shape_name);
+            let header = ["time"].into_iter().chain(names).collect::<Vec<_>>();
+            if let Err(e) = writeln!(writer, "{}", header.join(",")) {
+                log::error!("Could not write CSV log {}: {:?}", path, e);
+                return None;
+            }
+        }
+
+        log::info!("Logging face shapes to {}", path);
+
+        let (sender, receiver) = sync_channel(256);
+        thread::spawn(move || write_loop(writer, receiver));
+
+        Some(Self { sender })
+    }
+
+    /// Queues the shapes of the current frame for writing.
+    /// Never blocks; if the writer falls behind, the row is dropped.
+    pub fn log(&self, shapes: &UnifiedShapes) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_secs_f64())
+            .unwrap_or_default();
+        let _ = self.sender.try_send((time, *shapes));
+    }
+}
+
+fn write_loop(mut writer: BufWriter<impl Write>, receiver: Receiver<(f64, UnifiedShapes)>) {
+    let mut next_flush = Instant::now() + FLUSH_INTERVAL;
+
+    for (time, shapes) in receiver.iter() {
+        let mut row = format!("{:.3}", time);
+        for value in shapes.iter() {
+            row.push_str(&format!(",{:.4}", value));
+        }
+
+        let mut result = writeln!(writer, "{}", row);
+        if result.is_ok() && Instant::now() >= next_flush {
+            result = writer.flush();
+            next_flush = Instant::now() + FLUSH_INTERVAL;
+        }
+        if let Err(e) = result {
+            log::error!("Could not write CSV log: {:?}", e);
+            return;
+        }
+    }
+    let _ = writer.flush();
+}
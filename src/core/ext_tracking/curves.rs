@@ -0,0 +1,121 @@
+use std::{collections::HashMap, fs::File, io::Read, str::FromStr};
+
+use serde::Deserialize;
+
+use super::{
+    sranipal::SRanipalExpression,
+    unified::{CombinedExpression, UnifiedExpressions, NUM_SHAPES},
+};
+
+/// A single entry of the shape curve file, as written by the user.
+#[derive(Deserialize)]
+struct ShapeCurveEntry {
+    #[serde(default = "default_one")]
+    gain: f32,
+    #[serde(default = "default_one")]
+    exponent: f32,
+    min: Option<f32>,
+    max: Option<f32>,
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+/// The output transform of a single shape.
+#[derive(Clone, Copy)]
+struct ShapeCurve {
+    gain: f32,
+    exponent: f32,
+    min: f32,
+    max: f32,
+}
+
+/// Per-shape output transforms, applied right before a shape is sent, so that
+/// each avatar can be tuned to how strongly it reacts to a shape.
+///
+/// A shape becomes `clamp(gain * value ^ exponent, min, max)`. The exponent is applied to the
+/// magnitude, so that signed combined shapes (e.g. `SmileSadLeft`) keep their sign.
+/// Shapes without an entry are sent unchanged.
+pub struct ShapeCurves {
+    curves: [Option<ShapeCurve>; NUM_SHAPES],
+}
+
+impl Default for ShapeCurves {
+    fn default() -> Self {
+        Self {
+            curves: [None; NUM_SHAPES],
+        }
+    }
+}
+
+impl ShapeCurves {
+    /// Loads the shape curves from a JSON file, for example:
+    ///
+    /// ```json
+    /// {
+    ///   "JawOpen": { "max": 0.8 },
+    ///   "SmileSadLeft": { "exponent": 1.5 },
+    ///   "MouthClosed": { "gain": 1.2, "min": 0.0, "max": 1.0 }
+    /// }
+    /// ```
+    ///
+    /// Keys are unified or combined expression names, as in the avatar's parameter names.
+    /// Invalid entries are reported and skipped.
+    pub fn load(path: &str) -> Self {
+        let mut me = Self::default();
+
+        let mut json = String::new();
+        if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut json)) {
+            log::error!("Could not read shape curve file {}: {:?}", path, e);
+            return me;
+        }
+
+        let entries: HashMap<String, ShapeCurveEntry> = match serde_json::from_str(&json) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::error!("Failed to parse shape curve file {}: {}", path, e);
+                return me;
+            }
+        };
+
+        for (name, entry) in entries.iter() {
+            let Ok(idx) = UnifiedExpressions::from_str(name)
+                .map(|e| e as usize)
+                .or_else(|_| CombinedExpression::from_str(name).map(|e| e as usize))
+                .or_else(|_| SRanipalExpression::from_str(name).map(|e| e as usize))
+            else {
+                log::warn!("Shape curves: unknown shape {}", name);
+                continue;
+            };
+            let min = entry.min.unwrap_or(f32::NEG_INFINITY);
+            let max = entry.max.unwrap_or(f32::INFINITY);
+            if min > max {
+                log::warn!("Shape curves: {} has min > max, ignoring.", name);
+                continue;
+            }
+            me.curves[idx] = Some(ShapeCurve {
+                gain: entry.gain,
+                exponent: entry.exponent,
+                min,
+                max,
+            });
+        }
+
+        log::info!(
+            "Loaded {} shape curve(s) from {}",
+            me.curves.iter().flatten().count(),
+            path
+        );
+        me
+    }
+
+    /// Transforms the shape at `idx` for sending.
+    pub fn apply(&self, idx: usize, value: f32) -> f32 {
+        let Some(curve) = self.curves[idx].as_ref() else {
+            return value;
+        };
+        let shaped = value.signum() * value.abs().powf(curve.exponent);
+        (shaped * curve.gain).clamp(curve.min, curve.max)
+    }
+}
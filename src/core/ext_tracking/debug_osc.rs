@@ -0,0 +1,54 @@
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+
+use rosc::{OscBundle, OscType};
+
+use crate::core::bundle::AvatarBundle;
+
+use super::{shape_name, unified::UnifiedShapes};
+
+/// The address prefix of the echoed shapes, followed by the shape name.
+const DEBUG_PREFIX: &str = "/oscavmgr/debug/";
+
+/// Echoes every computed shape to a local port as `/oscavmgr/debug/<ShapeName>`,
+/// regardless of which parameters the avatar has. Meant for checking the tracker output with external tools.
+pub struct DebugOsc {
+    socket: UdpSocket,
+}
+
+impl DebugOsc {
+    /// Returns `None` unless a port is given. Failing to set up the socket is reported, but not fatal.
+    pub fn new(port: Option<u16>) -> Option<Self> {
+        let port = port?;
+        let socket = UdpSocket::bind("0.0.0.0:0")
+            .and_then(|socket| {
+                socket
+                    .connect(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port))
+                    .map(|_| socket)
+            })
+            .map_err(|e| log::error!("Could not set up debug OSC port {}: {:?}", port, e))
+            .ok()?;
+
+        log::info!("Echoing the face shapes to port {}", port);
+        Some(Self { socket })
+    }
+
+    /// Sends the unified and combined shapes, after `calc_combined`.
+    pub fn send(&self, shapes: &UnifiedShapes) {
+        let mut bundle = OscBundle::new_bundle();
+        for (idx, value) in shapes.iter().enumerate() {
+            if let Some(name) = shape_name(idx) {
+                bundle.send_tracking(
+                    &format!("{}{}", DEBUG_PREFIX, name),
+                    vec![OscType::Float(*value)],
+                );
+            }
+        }
+
+        for buf in bundle.serialize_chunked(rosc::decoder::MTU) {
+            if let Err(e) = self.socket.send(&buf) {
+                log::debug!("Failed to send debug OSC: {}", e);
+                break;
+            }
+        }
+    }
+}
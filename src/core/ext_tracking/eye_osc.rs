@@ -0,0 +1,98 @@
+use std::time::{Duration, Instant};
+
+use glam::{vec3, Vec3};
+use rosc::{OscMessage, OscType};
+
+use crate::core::EYE_PREFIX;
+
+use super::unified::{UnifiedExpressions, UnifiedShapeAccessors, UnifiedTrackingData};
+
+/// Takes in eye data that another app sends in VRChat's native `/tracking/eye/*` format,
+/// so that an external eye tracker can be combined with the face data of the selected receiver.
+///
+/// VRChat uses pitch/yaw in degrees, with positive pitch looking down and positive yaw looking right.
+/// Internally, eyes are stored as euler angles in radians with the opposite signs (see `apply_to_bundle`).
+/// Gaze vectors are in Unity's head space: +X right, +Y up, +Z forward.
+#[derive(Default)]
+pub struct EyeOscIngest {
+    /// The last received eye rotations, left then right.
+    eyes: [Option<Vec3>; 2],
+    /// The last received `EyesClosedAmount`, if any.
+    closed: Option<f32>,
+    /// When eye data was last received. Stale data is not applied.
+    last_received: Option<Instant>,
+}
+
+impl EyeOscIngest {
+    /// Parses a `/tracking/eye/*` message. Unknown addresses and malformed arguments are ignored.
+    pub fn handle(&mut self, msg: &OscMessage) {
+        let floats: Vec<f32> = msg
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                OscType::Float(f) => Some(*f),
+                _ => None,
+            })
+            .collect();
+
+        match (&msg.addr[EYE_PREFIX.len()..], floats.as_slice()) {
+            ("EyesClosedAmount", [closed]) => {
+                self.closed = Some(closed.clamp(0., 1.));
+            }
+            ("CenterPitchYaw", [pitch, yaw]) | ("CenterPitchYawDist", [pitch, yaw, _]) => {
+                let eye = from_pitch_yaw(*pitch, *yaw);
+                self.eyes = [Some(eye), Some(eye)];
+            }
+            ("LeftRightPitchYaw", [lp, ly, rp, ry]) => {
+                self.eyes = [
+                    Some(from_pitch_yaw(*lp, *ly)),
+                    Some(from_pitch_yaw(*rp, *ry)),
+                ];
+            }
+            ("CenterVec", [x, y, z]) | ("CenterVecFull", [x, y, z]) => {
+                let eye = from_vec(vec3(*x, *y, *z));
+                self.eyes = [eye, eye];
+            }
+            ("LeftRightVec", [lx, ly, lz, rx, ry, rz]) => {
+                self.eyes = [from_vec(vec3(*lx, *ly, *lz)), from_vec(vec3(*rx, *ry, *rz))];
+            }
+            _ => {
+                log::debug!("Eye OSC: Unsupported message {} {:?}", msg.addr, msg.args);
+                return;
+            }
+        }
+
+        self.last_received = Some(Instant::now());
+    }
+
+    /// Overrides the eyes (and eye closure, if received) in the tracking data,
+    /// as long as eye data has been received recently.
+    pub fn apply(&self, data: &mut UnifiedTrackingData) {
+        if !self
+            .last_received
+            .is_some_and(|t| t.elapsed() < Duration::from_secs(1))
+        {
+            return;
+        }
+
+        if self.eyes[0].is_some() {
+            data.eyes = self.eyes;
+            data.last_eyes_received = self.last_received;
+        }
+        if let Some(closed) = self.closed {
+            data.shapes.setu(UnifiedExpressions::EyeClosedLeft, closed);
+            data.shapes.setu(UnifiedExpressions::EyeClosedRight, closed);
+        }
+    }
+}
+
+/// Converts VRChat's pitch/yaw (in degrees) into the internal euler angles.
+fn from_pitch_yaw(pitch: f32, yaw: f32) -> Vec3 {
+    vec3(-pitch.to_radians(), -yaw.to_radians(), 0.)
+}
+
+/// Converts a gaze direction into the internal euler angles. Returns `None` for a zero vector.
+fn from_vec(dir: Vec3) -> Option<Vec3> {
+    let dir = dir.try_normalize()?;
+    Some(vec3(dir.y.asin(), -dir.x.atan2(dir.z), 0.))
+}
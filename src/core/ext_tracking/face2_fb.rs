@@ -4,13 +4,27 @@
 //! blendshape weights provided by the OpenXR extension to the standardized
 //! shapes used internally by OscAvMgr.
 
+use std::{fs::File, io::Read, str::FromStr};
+
+use glam::Vec3;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use strum::EnumString;
+
 use super::unified::{UnifiedExpressions, UnifiedShapeAccessors, UnifiedShapes, NUM_SHAPES};
 
+/// The number of weights in the `FB_face_tracking2` weight array.
+const NUM_FB_WEIGHTS: usize = 70;
+
+/// User-provided overrides for the FB → unified mapping, applied after the built-in conversion.
+static FB_OVERRIDES: OnceCell<Vec<FbOverride>> = OnceCell::new();
+
 /// Represents the indices of the core face tracking blendshapes provided by the
 /// `FB_face_tracking2` extension. The `repr(usize)` allows casting the enum
 /// variants directly to indices for accessing the raw float array from the API.
 #[allow(non_snake_case, unused)]
 #[repr(usize)]
+#[derive(Debug, Clone, Copy, EnumString)]
 enum FaceFb {
     BrowLowererL,
     BrowLowererR,
@@ -75,6 +89,7 @@ enum FaceFb {
     UpperLidRaiserR,
     UpperLipRaiserL,
     UpperLipRaiserR,
+    #[strum(disabled)]
     Max,
 }
 
@@ -83,6 +98,7 @@ enum FaceFb {
 /// These indices start after the core set.
 #[allow(non_snake_case, unused)]
 #[repr(usize)]
+#[derive(Debug, Clone, Copy, EnumString)]
 enum Face2Fb {
     TongueTipInterdental = 63,
     TongueTipAlveolar,
@@ -91,9 +107,70 @@ enum Face2Fb {
     TongueBackDorsalPalate,
     TongueOut,
     TongueRetreat,
+    #[strum(disabled)]
     Max,
 }
 
+/// How far (in radians) an eye turns at a look weight of 1.
+const EYE_LOOK_RANGE_RAD: f32 = 30. * std::f32::consts::PI / 180.;
+
+/// Calculates how far each eye's yaw deviates from the average of both eyes, from the per-eye look shapes.
+/// The result is `[left, right]` in radians, positive towards the left, or `None` if the data is too short.
+///
+/// The eye gaze pose only gives a single direction for both eyes. Adding these offsets to it
+/// lets each eye turn on its own, e.g. converging when looking at something close.
+pub(crate) fn fb_eye_yaw_offsets(face_fb: &[f32]) -> Option<[f32; 2]> {
+    if face_fb.len() < FaceFb::Max as usize {
+        return None;
+    }
+    let getf = |index: FaceFb| face_fb[index as usize];
+
+    // Looking right turns the eye away from the positive (left) yaw direction.
+    let left = (getf(FaceFb::EyesLookLeftL) - getf(FaceFb::EyesLookRightL)) * EYE_LOOK_RANGE_RAD;
+    let right = (getf(FaceFb::EyesLookLeftR) - getf(FaceFb::EyesLookRightR)) * EYE_LOOK_RANGE_RAD;
+    let mean = (left + right) * 0.5;
+
+    Some([left - mean, right - mean])
+}
+
+/// Calculates each eye's rotation from the per-eye look shapes, as `[left, right]` euler angles
+/// in the same layout as the eye gaze pose (x = pitch, positive up; y = yaw, positive left).
+/// Returns `None` if the data is too short.
+pub(crate) fn fb_eye_gaze(face_fb: &[f32]) -> Option<[Vec3; 2]> {
+    if face_fb.len() < FaceFb::Max as usize {
+        return None;
+    }
+    let getf = |index: FaceFb| face_fb[index as usize];
+
+    let left = Vec3::new(
+        (getf(FaceFb::EyesLookUpL) - getf(FaceFb::EyesLookDownL)) * EYE_LOOK_RANGE_RAD,
+        (getf(FaceFb::EyesLookLeftL) - getf(FaceFb::EyesLookRightL)) * EYE_LOOK_RANGE_RAD,
+        0.,
+    );
+    let right = Vec3::new(
+        (getf(FaceFb::EyesLookUpR) - getf(FaceFb::EyesLookDownR)) * EYE_LOOK_RANGE_RAD,
+        (getf(FaceFb::EyesLookLeftR) - getf(FaceFb::EyesLookRightR)) * EYE_LOOK_RANGE_RAD,
+        0.,
+    );
+
+    Some([left, right])
+}
+
+/// Checks that the FB index enums line up with the weight array layout.
+/// The tongue indices continue right after the core set, and both must fit in the weight array.
+pub(super) fn verify_alignment() {
+    assert_eq!(
+        FaceFb::Max as usize,
+        Face2Fb::TongueTipInterdental as usize,
+        "FB tongue indices must start right after the core face indices"
+    );
+    assert_eq!(
+        Face2Fb::Max as usize,
+        NUM_FB_WEIGHTS,
+        "FB indices must cover the whole weight array"
+    );
+}
+
 /// Converts a slice of f32 values from the `FB_face_tracking2` extension
 /// into the application's `UnifiedShapes` format.
 ///
@@ -145,13 +222,14 @@ pub(crate) fn face2_fb_to_unified(face_fb: &[f32]) -> Option<UnifiedShapes> {
     );
 
     // EyeSquint is derived by subtracting the closed amount from the lid tightener.
+    // The tightener lags behind the closed weight during a blink, so the difference is floored at 0.
     shapes.setu(
         UnifiedExpressions::EyeSquintRight,
-        getf(FaceFb::LidTightenerR) - getf(FaceFb::EyesClosedR),
+        (getf(FaceFb::LidTightenerR) - getf(FaceFb::EyesClosedR)).max(0.),
     );
     shapes.setu(
         UnifiedExpressions::EyeSquintLeft,
-        getf(FaceFb::LidTightenerL) - getf(FaceFb::EyesClosedL),
+        (getf(FaceFb::LidTightenerL) - getf(FaceFb::EyesClosedL)).max(0.),
     );
     shapes.setu(
         UnifiedExpressions::EyeWideRight,
@@ -395,7 +473,164 @@ pub(crate) fn face2_fb_to_unified(face_fb: &[f32]) -> Option<UnifiedShapes> {
             UnifiedExpressions::TongueCurlUp,
             getf2(Face2Fb::TongueTipAlveolar),
         );
+
+        // FB describes where the tongue touches the mouth rather than where it points,
+        // so the directions are approximated from the contact points:
+        // - Pressing the front or middle of the tongue against the palate lifts it: up.
+        // - Resting the tip between the teeth without sticking it out lowers it: down.
+        // - Pressing the back against the palate pushes the sides up around the middle: roll.
+        // There are no sideways weights, so `TongueLeft` and `TongueRight` stay unset.
+        // They can still be fed from another weight with `--fb-mapping`.
+        shapes.setu(
+            UnifiedExpressions::TongueUp,
+            getf2(Face2Fb::TongueFrontDorsalPalate).max(getf2(Face2Fb::TongueMidDorsalPalate)),
+        );
+        shapes.setu(
+            UnifiedExpressions::TongueDown,
+            getf2(Face2Fb::TongueTipInterdental) * (1. - getf2(Face2Fb::TongueOut)),
+        );
+        shapes.setu(
+            UnifiedExpressions::TongueRoll,
+            getf2(Face2Fb::TongueBackDorsalPalate),
+        );
     }
 
+    exclude_eyelids(&mut shapes);
+    apply_fb_overrides(face_fb, &mut shapes);
+
     Some(shapes)
 }
+
+/// Keeps the eyelid shapes from fighting each other on avatars that blend all three.
+///
+/// `EyeWide` and `EyeSquint` are clamped to 0..1, then scaled by how open the eye is,
+/// so that they fade out as `EyeClosed` goes to 1 instead of pulling the lid open mid-blink.
+pub(super) fn exclude_eyelids(shapes: &mut UnifiedShapes) {
+    for (closed, wide, squint) in [
+        (
+            UnifiedExpressions::EyeClosedLeft,
+            UnifiedExpressions::EyeWideLeft,
+            UnifiedExpressions::EyeSquintLeft,
+        ),
+        (
+            UnifiedExpressions::EyeClosedRight,
+            UnifiedExpressions::EyeWideRight,
+            UnifiedExpressions::EyeSquintRight,
+        ),
+    ] {
+        let open = 1. - shapes.getu(closed).clamp(0., 1.);
+        shapes.setu(wide, shapes.getu(wide).clamp(0., 1.) * open);
+        shapes.setu(squint, shapes.getu(squint).clamp(0., 1.) * open);
+    }
+}
+
+/// A single entry of the FB mapping override file, as written by the user.
+#[derive(Deserialize)]
+struct FbOverrideEntry {
+    /// The FB blendshape, either by name (e.g. `LipSuckLB`) or by index into the weight array.
+    source: FbSource,
+    /// The `UnifiedExpressions` name that receives the value.
+    target: String,
+    /// The factor the source weight is multiplied with.
+    #[serde(default = "default_scale")]
+    scale: f32,
+}
+
+/// An FB blendshape reference, either by name or by index.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FbSource {
+    Index(usize),
+    Name(String),
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+/// A validated override: the target shape becomes `weights[source] * scale`.
+struct FbOverride {
+    source: usize,
+    target: UnifiedExpressions,
+    scale: f32,
+}
+
+/// Loads the FB mapping overrides from a JSON file, for example:
+///
+/// ```json
+/// [
+///   { "source": "LipSuckLB", "target": "LipSuckLowerLeft", "scale": 1.2 },
+///   { "source": 45, "target": "LipSuckUpperLeft" }
+/// ]
+/// ```
+///
+/// Every target that appears in the file replaces the built-in mapping for that shape.
+/// If a target appears more than once, the scaled sources are added up.
+/// Invalid entries are reported and skipped; everything not overridden keeps the built-in mapping.
+pub(crate) fn load_fb_overrides(path: &str) {
+    let mut json = String::new();
+    if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut json)) {
+        log::error!("Could not read FB mapping file {}: {:?}", path, e);
+        return;
+    }
+
+    let entries: Vec<FbOverrideEntry> = match serde_json::from_str(&json) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::error!("Failed to parse FB mapping file {}: {}", path, e);
+            return;
+        }
+    };
+
+    let overrides: Vec<FbOverride> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let source = match &entry.source {
+                FbSource::Index(idx) if *idx < NUM_FB_WEIGHTS => Some(*idx),
+                FbSource::Index(_) => None,
+                FbSource::Name(name) => FaceFb::from_str(name)
+                    .map(|e| e as usize)
+                    .or_else(|_| Face2Fb::from_str(name).map(|e| e as usize))
+                    .ok()
+                    .filter(|idx| *idx < NUM_FB_WEIGHTS),
+            };
+            let Some(source) = source else {
+                log::warn!("FB mapping: unknown source for target {}", entry.target);
+                return None;
+            };
+            let Ok(target) = UnifiedExpressions::from_str(&entry.target) else {
+                log::warn!("FB mapping: unknown target {}", entry.target);
+                return None;
+            };
+            Some(FbOverride {
+                source,
+                target,
+                scale: entry.scale,
+            })
+        })
+        .collect();
+
+    log::info!(
+        "Loaded {} FB mapping override(s) from {}",
+        overrides.len(),
+        path
+    );
+    let _ = FB_OVERRIDES.set(overrides);
+}
+
+/// Replaces the built-in mapping for each overridden target shape.
+fn apply_fb_overrides(face_fb: &[f32], shapes: &mut UnifiedShapes) {
+    let Some(overrides) = FB_OVERRIDES.get() else {
+        return;
+    };
+
+    // Clear the targets first, so that multiple sources for the same target add up.
+    for o in overrides.iter() {
+        shapes.setu(o.target, 0.);
+    }
+    for o in overrides.iter() {
+        if let Some(weight) = face_fb.get(o.source) {
+            shapes.setu(o.target, shapes.getu(o.target) + weight * o.scale);
+        }
+    }
+}
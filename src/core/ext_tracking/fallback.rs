@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use strum::EnumCount;
+
+use crate::core::AppState;
+
+use super::{
+    unified::{UnifiedExpressions, UnifiedTrackingData},
+    FaceReceiver,
+};
+
+/// A receiver in the fallback chain, along with the data it last produced.
+struct ChainEntry {
+    name: String,
+    receiver: Box<dyn FaceReceiver>,
+    data: UnifiedTrackingData,
+}
+
+/// Chains several face receivers by priority, for users whose primary source is flaky.
+///
+/// All receivers keep running, each into its own copy of the tracking data, so that sources
+/// that failed to initialize keep retrying in the background. Each frame, the output is taken
+/// from the highest-priority receiver that has produced face data recently. If none has,
+/// the last active receiver is kept, so that the face doesn't jump between stale sources.
+pub struct FallbackReceiver {
+    chain: Vec<ChainEntry>,
+    /// How long a receiver may go without face data before the chain moves past it.
+    timeout: Duration,
+    /// The index of the receiver the output is currently taken from.
+    active: usize,
+}
+
+impl FallbackReceiver {
+    /// Creates a new `FallbackReceiver` from named receivers, ordered from highest to lowest priority.
+    pub fn new(receivers: Vec<(String, Box<dyn FaceReceiver>)>, timeout_ms: u64) -> Self {
+        log::info!(
+            "Face fallback chain: {}",
+            receivers
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(" → ")
+        );
+
+        Self {
+            chain: receivers
+                .into_iter()
+                .map(|(name, receiver)| ChainEntry {
+                    name,
+                    receiver,
+                    data: UnifiedTrackingData::default(),
+                })
+                .collect(),
+            timeout: Duration::from_millis(timeout_ms),
+            active: 0,
+        }
+    }
+}
+
+impl FaceReceiver for FallbackReceiver {
+    fn start_loop(&mut self) {
+        for entry in self.chain.iter_mut() {
+            entry.receiver.start_loop();
+        }
+    }
+
+    fn receive(&mut self, data: &mut UnifiedTrackingData, state: &mut AppState) {
+        for entry in self.chain.iter_mut() {
+            entry.receiver.receive(&mut entry.data, state);
+        }
+
+        let live = self.chain.iter().position(|entry| {
+            entry
+                .data
+                .last_face_received
+                .is_some_and(|t| t.elapsed() < self.timeout)
+        });
+
+        if let Some(live) = live {
+            if live != self.active {
+                log::info!(
+                    "Face source switched from {} to {}",
+                    self.chain[self.active].name,
+                    self.chain[live].name
+                );
+                self.active = live;
+            }
+        }
+
+        let active = &self.chain[self.active].data;
+        data.shapes[..UnifiedExpressions::COUNT]
+            .copy_from_slice(&active.shapes[..UnifiedExpressions::COUNT]);
+        data.eyes = active.eyes;
+        data.last_face_received = active.last_face_received;
+        data.last_eyes_received = active.last_eyes_received;
+    }
+}
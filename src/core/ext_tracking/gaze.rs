@@ -0,0 +1,31 @@
+use glam::Vec3;
+
+/// Keeps the eye rotations sane before anything reads them, so that tracker glitches don't fling the gaze.
+///
+/// Each euler angle (in radians) is clamped to `±range`, then angles within `±deadzone` are snapped to 0,
+/// so that small jitter around the center reads as looking straight ahead.
+pub struct GazeLimits {
+    range: f32,
+    deadzone: f32,
+}
+
+impl GazeLimits {
+    pub fn new(range: f32, deadzone: f32) -> Self {
+        let range = range.abs();
+        Self {
+            range,
+            deadzone: deadzone.abs().min(range),
+        }
+    }
+
+    /// Clamps and deadzones both eyes in place.
+    pub fn apply(&self, eyes: &mut [Option<Vec3>; 2]) {
+        for eye in eyes.iter_mut().flatten() {
+            *eye = eye
+                .clamp(Vec3::splat(-self.range), Vec3::splat(self.range))
+                .to_array()
+                .map(|v| if v.abs() < self.deadzone { 0. } else { v })
+                .into();
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use std::str::FromStr;
+
+use super::unified::{UnifiedExpressions, UnifiedShapes};
+
+/// The per-shape state of the expression hold.
+#[derive(Debug, Default, Clone)]
+struct HeldShape {
+    /// The index of the shape inside the `UnifiedShapes` array.
+    idx: usize,
+    /// The value that was last let through to the output.
+    output: f32,
+    /// How long (in seconds) the input has been on the other side of the threshold.
+    pending: f32,
+}
+
+/// An edge-triggered hysteresis filter for flickery expression shapes.
+///
+/// While the input stays on the same side of the threshold as the output, it passes through untouched.
+/// Once the input crosses the threshold, the output holds its last value until the input has stayed
+/// on the new side for the configured hold time. Rapid toggling around the threshold is therefore
+/// swallowed entirely, while slow, deliberate movements pass through without the lag of a low-pass.
+pub struct ExpressionHold {
+    /// The shapes that the hold is applied to.
+    shapes: Vec<HeldShape>,
+    /// The value that separates the "low" and "high" side of a shape.
+    threshold: f32,
+    /// How long (in seconds) the input must stay across the threshold before the output follows.
+    hold_time: f32,
+}
+
+impl ExpressionHold {
+    /// Creates a new `ExpressionHold` for the given `UnifiedExpressions` names.
+    /// Returns `None` if none of the names could be resolved, so that the hold can be skipped entirely.
+    pub fn new(names: &[String], threshold: f32, hold_ms: u64) -> Option<Self> {
+        let shapes: Vec<HeldShape> = names
+            .iter()
+            .filter_map(|name| match UnifiedExpressions::from_str(name) {
+                Ok(e) => Some(HeldShape {
+                    idx: e as usize,
+                    ..Default::default()
+                }),
+                Err(_) => {
+                    log::warn!("Expression hold: unknown shape {}", name);
+                    None
+                }
+            })
+            .collect();
+
+        if shapes.is_empty() {
+            return None;
+        }
+
+        log::info!(
+            "Expression hold: {} shape(s), threshold {:.2}, {}ms",
+            shapes.len(),
+            threshold,
+            hold_ms
+        );
+
+        Some(Self {
+            shapes,
+            threshold,
+            hold_time: hold_ms as f32 / 1000.,
+        })
+    }
+
+    /// Applies the hold to the given shapes in place.
+    /// `delta_t` is the time elapsed since the previous call, in seconds.
+    pub fn apply(&mut self, shapes: &mut UnifiedShapes, delta_t: f32) {
+        for held in self.shapes.iter_mut() {
+            let input = shapes[held.idx];
+            let input_high = input >= self.threshold;
+            let output_high = held.output >= self.threshold;
+
+            if input_high == output_high {
+                // Same side as the output: follow the input and forget any pending crossing.
+                held.pending = 0.;
+                held.output = input;
+            } else {
+                // The input crossed the threshold: only follow once it has stayed there long enough.
+                held.pending += delta_t;
+                if held.pending >= self.hold_time {
+                    held.pending = 0.;
+                    held.output = input;
+                }
+            }
+
+            shapes[held.idx] = held.output;
+        }
+    }
+}
+
+/// A hysteresis band for the `JawOpen` / `MouthClosed` pair.
+///
+/// `MouthClosed` closes the lips relative to `JawOpen`, so when both hover around the same value,
+/// tiny opposite movements between frames make the mouth rapidly open and close.
+/// Both outputs are held until either input leaves the band around its held value, and are then
+/// updated together, so the pair never drifts apart. Movements larger than the band pass through as-is.
+pub struct JawHysteresis {
+    /// How far (0-1) an input must move away from its held value before the pair is updated.
+    band: f32,
+    /// The held `JawOpen` and `MouthClosed` outputs.
+    output: Option<[f32; 2]>,
+}
+
+impl JawHysteresis {
+    /// Creates a new `JawHysteresis`. Returns `None` if the band is zero, leaving the pair untouched.
+    pub fn new(band: f32) -> Option<Self> {
+        if band <= 0. {
+            return None;
+        }
+
+        Some(Self { band, output: None })
+    }
+
+    /// Applies the hysteresis to the given shapes in place.
+    pub fn apply(&mut self, shapes: &mut UnifiedShapes) {
+        let jaw = UnifiedExpressions::JawOpen as usize;
+        let closed = UnifiedExpressions::MouthClosed as usize;
+        let input = [shapes[jaw], shapes[closed]];
+
+        let output = match self.output {
+            Some(held)
+                if (input[0] - held[0]).abs() < self.band
+                    && (input[1] - held[1]).abs() < self.band =>
+            {
+                held
+            }
+            _ => input,
+        };
+
+        self.output = Some(output);
+        shapes[jaw] = output[0];
+        shapes[closed] = output[1];
+    }
+}
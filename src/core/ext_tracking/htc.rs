@@ -1,6 +1,23 @@
 use super::unified::{UnifiedExpressions, UnifiedShapeAccessors, UnifiedShapes, NUM_SHAPES};
 use openxr as xr;
 
+/// The weights of one frame from the HTC facial trackers (VIVE Pro Eye, VIVE Facial Tracker).
+///
+/// The runtime fills fixed-size arrays, which are indexed by the raw value of the expression enums, i.e.
+/// `XrEyeExpressionHTC` for the `FACIAL_EXPRESSION_EYE_COUNT_HTC` (14) eye weights:
+///
+/// | Index | Left           | Index | Right           |
+/// |-------|----------------|-------|-----------------|
+/// | 0     | `LEFT_BLINK`   | 2     | `RIGHT_BLINK`   |
+/// | 1     | `LEFT_WIDE`    | 3     | `RIGHT_WIDE`    |
+/// | 4     | `LEFT_SQUEEZE` | 5     | `RIGHT_SQUEEZE` |
+/// | 6     | `LEFT_DOWN`    | 7     | `RIGHT_DOWN`    |
+/// | 8     | `LEFT_OUT`     | 9     | `RIGHT_IN`      |
+/// | 10    | `LEFT_IN`      | 11    | `RIGHT_OUT`     |
+/// | 12    | `LEFT_UP`      | 13    | `RIGHT_UP`      |
+///
+/// And `XrLipExpressionHTC` for the `FACIAL_EXPRESSION_LIP_COUNT_HTC` (37) lip weights.
+/// Either array is `None` while its tracker is missing or inactive.
 pub(crate) struct HtcFacialData {
     pub(super) eye: Option<[f32; xr::sys::FACIAL_EXPRESSION_EYE_COUNT_HTC]>,
     pub(super) lip: Option<[f32; xr::sys::FACIAL_EXPRESSION_LIP_COUNT_HTC]>,
@@ -22,6 +39,8 @@ impl HtcFacialData {
     }
 }
 
+/// Converts the HTC weights into unified shapes. The eye wide and squeeze weights, which SRanipal calls
+/// `EyeLeftWide` and `EyeLeftSqueeze` (see `sranipal.rs`), become `EyeWide*` and `EyeSquint*`.
 pub(crate) fn htc_to_unified(d: &HtcFacialData) -> UnifiedShapes {
     let mut shapes: UnifiedShapes = [0.0; NUM_SHAPES];
 
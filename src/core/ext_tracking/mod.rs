@@ -1,11 +1,17 @@
-use std::{array, str::FromStr, sync::Arc};
+use std::{
+    array,
+    collections::HashSet,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use once_cell::sync::Lazy;
 use regex::Regex;
-use rosc::{OscBundle, OscType};
+use rosc::{OscBundle, OscMessage, OscType};
 use sranipal::SRanipalExpression;
 
-use crate::FaceSetup;
+use crate::{Args, FacePauseBehavior, FaceSetup};
 
 #[cfg(feature = "alvr")]
 use self::alvr::AlvrReceiver;
@@ -14,29 +20,82 @@ use self::alvr::AlvrReceiver;
 use self::babble::BabbleEtvrReceiver;
 
 #[cfg(feature = "openxr")]
-use self::openxr::OpenXrReceiver;
-
-use self::unified::{CombinedExpression, UnifiedExpressions, UnifiedTrackingData, NUM_SHAPES};
+use self::openxr::{OpenXrReceiver, XrConfig};
+
+#[cfg(feature = "vmc")]
+use self::vmc::VmcReceiver;
+
+use self::{
+    cadence::Cadence,
+    calibration::Calibration,
+    csv_log::CsvLog,
+    curves::ShapeCurves,
+    debug_osc::DebugOsc,
+    eye_osc::EyeOscIngest,
+    fallback::FallbackReceiver,
+    gaze::GazeLimits,
+    hold::{ExpressionHold, JawHysteresis},
+    pause_ramp::PauseRamp,
+    relax::Relax,
+    session::{ReplayReceiver, SessionRecorder},
+    smoothing::OneEuroSmoothing,
+    solitude::Solitude,
+    stdin::StdinReceiver,
+    unified::{
+        neutral_shapes, CombinedExpression, ExpressionIntensity, SplitParam, UnifiedExpressions,
+        UnifiedTrackingData, NUM_SHAPES, SPLIT_PARAMS,
+    },
+};
 
 use super::{
+    bundle::AvatarBundle,
     ext_oscjson::{MysteryParam, OscJsonNode},
+    is_truthy,
+    status::StatusItem,
     AppState,
 };
 
 use strum::EnumCount;
 use strum::IntoEnumIterator;
 
+mod alignment;
 #[cfg(feature = "alvr")]
 mod alvr;
+#[cfg(any(feature = "openxr", feature = "vmc"))]
+mod arkit;
 #[cfg(feature = "babble")]
 mod babble;
+mod cadence;
+mod calibration;
+mod combined;
+mod csv_log;
+mod curves;
+mod debug_osc;
+mod eye_osc;
 mod face2_fb;
+mod fallback;
+mod gaze;
+mod hold;
 #[cfg(feature = "openxr")]
 mod htc;
 #[cfg(feature = "openxr")]
 mod openxr;
+mod pause_ramp;
+#[cfg(feature = "openxr")]
+pub(super) use openxr::list_extensions;
+#[cfg(feature = "openxr")]
+mod pico;
+#[cfg(feature = "openxr")]
+mod raw_dump;
+mod relax;
+mod session;
+mod smoothing;
+mod solitude;
 mod sranipal;
+mod stdin;
 pub mod unified;
+#[cfg(feature = "vmc")]
+mod vmc;
 
 /// A trait defining the interface for a face tracking data receiver.
 /// This allows for different tracking sources (OpenXR, ALVR, etc.) to be used interchangeably.
@@ -55,23 +114,269 @@ impl FaceReceiver for DummyReceiver {
     fn receive(&mut self, _data: &mut UnifiedTrackingData, _: &mut AppState) {}
 }
 
+/// The `UnifiedExpressions` or `CombinedExpression` name of a shape index.
+fn shape_name(idx: usize) -> Option<&'static str> {
+    UnifiedExpressions::iter()
+        .nth(idx)
+        .map(|e| e.into())
+        .or_else(|| {
+            CombinedExpression::iter()
+                .nth(idx.checked_sub(UnifiedExpressions::COUNT)?)
+                .map(|e| e.into())
+        })
+}
+
+/// Instantiates the face receiver for the given `FaceSetup`.
+fn create_receiver(
+    setup: &FaceSetup,
+    // Only read by some of the optional receivers.
+    #[cfg_attr(
+        not(any(feature = "alvr", feature = "openxr")),
+        allow(unused_variables)
+    )]
+    args: &Args,
+) -> Box<dyn FaceReceiver> {
+    match setup {
+        FaceSetup::Dummy => Box::new(DummyReceiver {}),
+        #[cfg(feature = "alvr")]
+        FaceSetup::Alvr => Box::new(AlvrReceiver::new(args)),
+        #[cfg(feature = "openxr")]
+        FaceSetup::Openxr => Box::new(OpenXrReceiver::new(XrConfig::new(args))),
+        #[cfg(feature = "babble")]
+        FaceSetup::Babble {
+            listen,
+            face_listen,
+            eye_listen,
+        } => Box::new(BabbleEtvrReceiver::new(
+            face_listen.unwrap_or(*listen),
+            eye_listen.unwrap_or(*listen),
+        )),
+        #[cfg(feature = "vmc")]
+        FaceSetup::Vmc { listen } => Box::new(VmcReceiver::new(*listen)),
+        FaceSetup::Replay { file } => Box::new(ReplayReceiver::new(file)),
+        FaceSetup::Stdin => Box::new(StdinReceiver::new()),
+    }
+}
+
+/// Parses a `--face-fallback` entry: `openxr`, `alvr`, `babble[:port]`, `vmc[:port]` or `stdin`.
+/// Returns `None` for unknown sources and for sources that weren't compiled in.
+fn parse_face_setup(name: &str) -> Option<FaceSetup> {
+    let (kind, port) = match name.split_once(':') {
+        Some((kind, port)) => (kind, Some(port)),
+        None => (name, None),
+    };
+
+    match (kind.to_ascii_lowercase().as_str(), port) {
+        #[cfg(feature = "openxr")]
+        ("openxr", None) => Some(FaceSetup::Openxr),
+        #[cfg(feature = "alvr")]
+        ("alvr", None) => Some(FaceSetup::Alvr),
+        #[cfg(feature = "babble")]
+        ("babble", port) => Some(FaceSetup::Babble {
+            listen: match port {
+                Some(port) => port.parse().ok()?,
+                None => 9400,
+            },
+            face_listen: None,
+            eye_listen: None,
+        }),
+        #[cfg(feature = "vmc")]
+        ("vmc", port) => Some(FaceSetup::Vmc {
+            listen: match port {
+                Some(port) => port.parse().ok()?,
+                None => 39539,
+            },
+        }),
+        ("stdin", None) => Some(FaceSetup::Stdin),
+        _ => None,
+    }
+}
+
 /// The main struct for the tracking extension.
 /// It manages the unified tracking data, the mapping to OSC parameters,
 /// and the active face tracking receiver.
 pub struct ExtTracking {
-    /// The unified tracking data structure that holds all face expression values.
+    /// The processed tracking data that is sent to the avatar and read by other extensions.
     pub data: UnifiedTrackingData,
+    /// The raw tracking data as written by the receiver, before any processing is applied.
+    raw: UnifiedTrackingData,
+    /// Removes the tracker's rest values from a neutral face.
+    calibration: Calibration,
+    /// Records the raw tracking data of every frame, if enabled.
+    recorder: Option<SessionRecorder>,
+    /// Appends the processed shapes of every frame to a CSV file, if enabled.
+    csv_log: Option<CsvLog>,
+    /// Eye data received in VRChat's native format, which overrides the receiver's eyes if enabled.
+    eye_osc: Option<EyeOscIngest>,
+    /// Clamps the eye rotations and snaps them to the center within the deadzone.
+    gaze: GazeLimits,
+    /// The optional one-euro filter that smooths jittery shapes.
+    smoothing: Option<OneEuroSmoothing>,
+    /// The optional expression hold that debounces flickery shapes.
+    hold: Option<ExpressionHold>,
+    /// The optional hysteresis that stops `JawOpen` and `MouthClosed` from chattering against each other.
+    jaw_hysteresis: Option<JawHysteresis>,
+    /// The optional fade towards neutral when face data is lost.
+    relax: Option<Relax>,
+    /// The master intensity applied to expressions as they are sent.
+    intensity: ExpressionIntensity,
+    /// The per-shape output transforms, applied after the intensity.
+    curves: ShapeCurves,
+    /// The fraction of nose sneer that is folded into the upper lip for avatars without nose shapes.
+    nose_sneer_fold: f32,
+    /// Whether to send the `FTActive` parameter to the avatar.
+    emit_face_active: bool,
+    /// The last value sent for `FTActive`, for change detection.
+    face_active: Option<bool>,
+    /// Echoes the computed shapes to a local port for debugging, if enabled.
+    debug_osc: Option<DebugOsc>,
+    /// The optional fade towards neutral while tracking is paused, instead of the hard freeze.
+    pause_ramp: Option<PauseRamp>,
+    /// What to do while `FacePause` is set.
+    facepause_behavior: FacePauseBehavior,
+    /// Whether `FacePause` was set on the last frame, for edge detection.
+    face_paused: bool,
+    /// When the frozen values were last re-sent during a `FacePause`.
+    last_frozen_send: Option<Instant>,
     /// An array that maps each of the possible face shapes to an OSC parameter configuration.
     params: [Option<MysteryParam>; NUM_SHAPES],
+    /// The split halves of signed expressions that the avatar has parameters for, e.g. `SmileLeft`.
+    split_params: Vec<SplitParam>,
+    /// Expression names that are never mapped, neither by default nor from OSC JSON.
+    suppressed: HashSet<Arc<str>>,
+    /// The address prefix of the default parameters, e.g. `FT/v2/`.
+    ft_prefix: Arc<str>,
+    /// The low-pass factor applied by each parameter right before it's sent.
+    param_smoothing: f32,
+    /// How far a float parameter must move before it's re-sent.
+    param_deadband: f32,
+    /// How far (in quantization steps) a bit-packed parameter must move past its last step before it's re-sent.
+    param_bit_hysteresis: f32,
     /// The currently active face tracking receiver, boxed as a trait object.
     receiver: Box<dyn FaceReceiver>,
+    /// Limits how often the receiver is polled. The latest received data is reused in between.
+    receive_cadence: Option<Cadence>,
+    /// The status items added by the receiver when it was last polled, shown again in between.
+    receiver_status: Vec<StatusItem>,
+    /// Limits how often the tracking data is sent to the avatar.
+    apply_cadence: Option<Cadence>,
+    /// Lowers the send rate further while nobody is nearby, if enabled.
+    solitude: Option<Solitude>,
 }
 
 impl ExtTracking {
-    /// Creates a new `ExtTracking` instance based on the selected `FaceSetup`.
-    pub fn new(setup: FaceSetup) -> Self {
-        // A set of default parameters for combined expressions.
-        // These are used as a fallback if an avatar's OSC JSON is not available or doesn't define them.
+    /// Creates a new `ExtTracking` instance based on the selected `FaceSetup` and tracking options.
+    pub fn new(args: &Args) -> Self {
+        // Refuse to run if the expression enums don't line up, rather than silently misrouting data.
+        alignment::verify();
+
+        // Load user overrides for the FB → unified mapping before any receiver starts converting.
+        if let Some(path) = args.fb_mapping.as_ref() {
+            face2_fb::load_fb_overrides(path);
+        }
+        if let Some(path) = args.combined_weights.as_ref() {
+            combined::load_combined_weights(path);
+        }
+
+        // Select and instantiate the appropriate face receiver based on the command-line arguments.
+        let receiver: Box<dyn FaceReceiver> = if args.face_fallback.is_empty() {
+            create_receiver(&args.face, args)
+        } else {
+            // The subcommand is the primary source, followed by the fallbacks in the given order.
+            let mut receivers = vec![(
+                format!("{:?}", args.face),
+                create_receiver(&args.face, args),
+            )];
+            for name in args.face_fallback.iter() {
+                match parse_face_setup(name) {
+                    Some(setup) => receivers.push((name.clone(), create_receiver(&setup, args))),
+                    None => log::warn!("Face fallback: unknown or unavailable source {}", name),
+                }
+            }
+            Box::new(FallbackReceiver::new(
+                receivers,
+                args.face_fallback_after_ms,
+            ))
+        };
+
+        let hold = ExpressionHold::new(
+            &args.expression_hold,
+            args.expression_hold_threshold,
+            args.expression_hold_ms,
+        );
+
+        let relax = Relax::new(
+            args.relax_after_ms,
+            args.relax_rate,
+            args.relax_combined_rate,
+        );
+
+        let mut me = Self {
+            data: UnifiedTrackingData::default(),
+            raw: UnifiedTrackingData::default(),
+            smoothing: OneEuroSmoothing::new(args.smoothing_mincutoff, args.smoothing_beta),
+            eye_osc: args.ingest_eye_osc.then(EyeOscIngest::default),
+            gaze: GazeLimits::new(args.gaze_range, args.gaze_deadzone),
+            calibration: Calibration::new(args.calibrate),
+            recorder: args.record.as_deref().and_then(SessionRecorder::new),
+            csv_log: args.csv_log.as_deref().and_then(CsvLog::new),
+            hold,
+            jaw_hysteresis: JawHysteresis::new(args.jaw_hysteresis),
+            relax,
+            intensity: ExpressionIntensity {
+                multiplier: args.expression_intensity,
+                combined: args.expression_intensity_combined,
+            },
+            curves: args
+                .shape_curves
+                .as_deref()
+                .map(ShapeCurves::load)
+                .unwrap_or_default(),
+            nose_sneer_fold: args.nose_sneer_fold,
+            emit_face_active: args.emit_face_active,
+            face_active: None,
+            pause_ramp: PauseRamp::new(args.pause_ramp_ms),
+            debug_osc: DebugOsc::new(args.debug_osc_port),
+            facepause_behavior: args.facepause_behavior,
+            face_paused: false,
+            last_frozen_send: None,
+            params: array::from_fn(|_| None),
+            split_params: Vec::new(),
+            suppressed: args
+                .suppress_params
+                .iter()
+                .map(|name| name.as_str().into())
+                .collect(),
+            ft_prefix: args.ft_prefix.as_str().into(),
+            param_smoothing: args.param_smoothing.clamp(0., 0.99),
+            param_deadband: args.param_deadband.max(0.),
+            param_bit_hysteresis: args.param_bit_hysteresis.max(0.),
+            receiver,
+            receive_cadence: Cadence::new(args.face_receive_hz),
+            receiver_status: vec![],
+            apply_cadence: Cadence::new(args.face_apply_hz),
+            solitude: Solitude::new(
+                args.solitude_param.as_deref(),
+                args.solitude_threshold,
+                args.solitude_hz,
+            ),
+        };
+
+        if !args.no_default_params {
+            me.set_default_params();
+            log::info!("--- Default params ---");
+            me.print_params();
+        }
+
+        // Start the receiver's loop.
+        me.receiver.start_loop();
+
+        me
+    }
+
+    /// Configures a set of default parameters under `ft_prefix`.
+    /// These are used as a fallback if an avatar's OSC JSON is not available or doesn't define them.
+    fn set_default_params(&mut self) {
         let default_combined = vec![
             CombinedExpression::BrowExpressionLeft,
             CombinedExpression::BrowExpressionRight,
@@ -98,98 +403,303 @@ impl ExtTracking {
             UnifiedExpressions::MouthClosed,
         ];
 
-        let mut params = array::from_fn(|_| None);
-
         // Initialize the params array with default configurations for combined expressions.
         for e in default_combined.into_iter() {
+            alignment::verify_index(e as usize, &format!("CombinedExpression::{:?}", e));
             let name: &str = e.into();
-            let new = MysteryParam {
-                name: name.into(),
-                main_address: Some(format!("FT/v2/{}", name).into()),
-                addresses: array::from_fn(|_| None),
-                neg_address: None,
-                num_bits: 0,
-                last_value: 0.,
-                last_bits: [false; 8],
-            };
-            params[e as usize] = Some(new);
+            if self.suppressed.contains(name) {
+                continue;
+            }
+            let addr = format!("{}{}", self.ft_prefix, name);
+            self.params[e as usize] = Some(self.new_param(name.into(), Some(addr.into())));
         }
 
         // Initialize the params array with default configurations for unified expressions.
         for e in default_unified.into_iter() {
+            alignment::verify_index(e as usize, &format!("UnifiedExpressions::{:?}", e));
             let name: &str = e.into();
-            let new = MysteryParam {
-                name: name.into(),
-                main_address: Some(format!("FT/v2/{}", name).into()),
-                addresses: array::from_fn(|_| None),
-                neg_address: None,
-                num_bits: 0,
-                last_value: 0.,
-                last_bits: [false; 8],
-            };
-            params[e as usize] = Some(new);
+            if self.suppressed.contains(name) {
+                continue;
+            }
+            let addr = format!("{}{}", self.ft_prefix, name);
+            self.params[e as usize] = Some(self.new_param(name.into(), Some(addr.into())));
         }
-
-        // Select and instantiate the appropriate face receiver based on the command-line arguments.
-        let receiver: Box<dyn FaceReceiver> = match setup {
-            FaceSetup::Dummy => Box::new(DummyReceiver {}),
-            #[cfg(feature = "alvr")]
-            FaceSetup::Alvr => Box::new(AlvrReceiver::new()),
-            #[cfg(feature = "openxr")]
-            FaceSetup::Openxr => Box::new(OpenXrReceiver::new()),
-            #[cfg(feature = "babble")]
-            FaceSetup::Babble { listen } => Box::new(BabbleEtvrReceiver::new(listen)),
-        };
-
-        let mut me = Self {
-            data: UnifiedTrackingData::default(),
-            params,
-            receiver,
-        };
-
-        log::info!("--- Default params ---");
-        me.print_params();
-
-        // Start the receiver's loop.
-        me.receiver.start_loop();
-
-        me
     }
 
     /// This method is called on each application tick to process tracking data.
     pub fn step(&mut self, state: &mut AppState, bundle: &mut OscBundle) {
         // Check for various state flags that might inhibit face tracking.
-        let motion = matches!(state.params.get("Motion"), Some(OscType::Int(1)));
-        let face_override = matches!(state.params.get("FaceFreeze"), Some(OscType::Bool(true)));
-        let afk = matches!(state.params.get("AFK"), Some(OscType::Bool(true)))
-            || matches!(state.params.get("IsAfk"), Some(OscType::Bool(true)));
+        let is_set = |name: &str| state.params.get(name).is_some_and(is_truthy);
+        let motion = is_set("Motion");
+        let face_override = is_set("FaceFreeze");
+        let afk = is_set("AFK") || is_set("IsAfk");
+
+        let paused = afk || motion ^ face_override;
 
         if afk {
             log::debug!("AFK: tracking paused");
+            self.reset_smoothing();
         } else if motion ^ face_override {
             // `motion` is an old parameter for freezing the avatar, `FaceFreeze` is the new one.
             // The XOR handles either one being active.
             log::debug!("Freeze: tracking paused");
+            self.reset_smoothing();
         } else {
             // If not paused, receive new data and calculate combined expressions.
-            self.receiver.receive(&mut self.data, state);
+            // Between receives, the latest received data is processed again.
+            if self.receive_cadence.as_mut().is_none_or(Cadence::due) {
+                let start = state.status.item_count();
+                self.receiver.receive(&mut self.raw, state);
+                self.receiver_status = state.status.items_since(start);
+            } else {
+                // Keep showing e.g. FACE and GAZE, which the receiver only adds when it's polled.
+                state.status.add_items(&self.receiver_status);
+            }
+
+            if let Some(recorder) = self.recorder.as_ref() {
+                recorder.record(&self.raw, &state.tracking);
+            }
+
+            // Start from the raw unified shapes and eyes. Combined shapes are left alone,
+            // since some of them (e.g. Blush) carry state over from the previous frame.
+            self.data.shapes[..UnifiedExpressions::COUNT]
+                .copy_from_slice(&self.raw.shapes[..UnifiedExpressions::COUNT]);
+            self.data.eyes = self.raw.eyes;
+            self.data.last_eyes_received = self.raw.last_eyes_received;
+
+            self.calibration.update(&state.params, &self.raw);
+            self.calibration.apply(&mut self.data.shapes);
+
+            if let Some(eye_osc) = self.eye_osc.as_ref() {
+                eye_osc.apply(&mut self.data);
+            }
+
+            self.gaze.apply(&mut self.data.eyes);
+
+            if let Some(smoothing) = self.smoothing.as_mut() {
+                smoothing.apply(&mut self.data.shapes, state.delta_t);
+            }
+
+            if let Some(relax) = self.relax.as_mut() {
+                relax.update(self.raw.last_face_received, state.delta_t);
+                relax.apply_base(&mut self.data.shapes);
+            }
+
+            if let Some(hold) = self.hold.as_mut() {
+                hold.apply(&mut self.data.shapes, state.delta_t);
+            }
+
+            if let Some(jaw_hysteresis) = self.jaw_hysteresis.as_mut() {
+                jaw_hysteresis.apply(&mut self.data.shapes);
+            }
+
+            if self.nose_sneer_fold > 0. {
+                self.fold_nose_sneer();
+            }
+
             self.data.calc_combined(state);
+
+            if let Some(debug_osc) = self.debug_osc.as_ref() {
+                debug_osc.send(&self.data.shapes);
+            }
+
+            if let Some(relax) = self.relax.as_ref() {
+                relax.apply_combined(&mut self.data.shapes);
+            }
+
+            if self.emit_face_active {
+                self.send_face_active(bundle);
+            }
+        }
+
+        if let Some(csv_log) = self.csv_log.as_ref() {
+            csv_log.log(&self.data.shapes);
         }
 
-        // Another pause mechanism.
-        if matches!(state.params.get("FacePause"), Some(OscType::Bool(true))) {
+        // Another pause mechanism. Holding the last frame is replaced by the pause ramp, if enabled.
+        let face_pause = state.params.get("FacePause").is_some_and(is_truthy);
+        let ramp_face_pause =
+            self.pause_ramp.is_some() && self.facepause_behavior == FacePauseBehavior::Hold;
+        if face_pause && !ramp_face_pause {
             log::debug!("FacePause: tracking paused");
+            let just_paused = !self.face_paused;
+            self.face_paused = true;
+            self.face_pause(just_paused, bundle);
             return;
         }
+        self.face_paused = face_pause;
+
+        if let Some(ramp) = self.pause_ramp.as_mut() {
+            ramp.update(paused || face_pause, state.delta_t);
+        }
 
         // Apply the final tracking data to the OSC bundle to be sent.
-        self.data.apply_to_bundle(&mut self.params, bundle);
+        // While alone, the solitude rate replaces the normal one.
+        let due = match self.solitude.as_mut().and_then(|s| s.due(&state.params)) {
+            Some(due) => due,
+            None => self.apply_cadence.as_mut().is_none_or(Cadence::due),
+        };
+        if due {
+            // While ramping, a faded copy is sent, so that the tracking data read by other extensions is left alone.
+            let mut faded = self
+                .pause_ramp
+                .as_ref()
+                .filter(|ramp| ramp.active())
+                .map(|ramp| ramp.apply(&self.data));
+            faded.as_mut().unwrap_or(&mut self.data).apply_to_bundle(
+                &mut self.params,
+                &mut self.split_params,
+                bundle,
+                &self.intensity,
+                &self.curves,
+            );
+        }
+    }
+
+    /// Takes in a `/tracking/eye/*` message from another app. Ignored unless `--ingest-eye-osc` is set.
+    pub fn ingest_eye_osc(&mut self, msg: &OscMessage) {
+        if let Some(eye_osc) = self.eye_osc.as_mut() {
+            eye_osc.handle(msg);
+        }
+    }
+
+    /// Resets the smoothing filter while tracking is paused.
+    fn reset_smoothing(&mut self) {
+        if let Some(smoothing) = self.smoothing.as_mut() {
+            smoothing.reset();
+        }
+    }
+
+    /// Sends the shapes of a neutral face (see `neutral_shapes`) and the eyes as centered, leaving the avatar neutral.
+    pub fn send_neutral(&mut self, bundle: &mut OscBundle) {
+        // Send the parameters directly, so that neither the smoothing nor the deadband holds back
+        // the neutral face. The tracking data read by other extensions is left alone.
+        let neutral = neutral_shapes();
+        for (idx, param) in self.params.iter_mut().enumerate() {
+            if let Some(param) = param {
+                param.send_now(
+                    self.curves
+                        .apply(idx, self.intensity.apply(idx, neutral[idx])),
+                    bundle,
+                );
+            }
+        }
+        for split in self.split_params.iter_mut() {
+            let idx = split.source as usize;
+            let signed = self
+                .curves
+                .apply(idx, self.intensity.apply(idx, neutral[idx]));
+            split.param.send_now(split.value(signed), bundle);
+        }
+        bundle.send_tracking(
+            "/tracking/eye/LeftRightPitchYaw",
+            vec![OscType::Float(0.); 4],
+        );
+    }
+
+    /// Handles a frame during `FacePause`, according to the configured `FacePauseBehavior`.
+    fn face_pause(&mut self, just_paused: bool, bundle: &mut OscBundle) {
+        match self.facepause_behavior {
+            FacePauseBehavior::Hold => {}
+            FacePauseBehavior::Neutral => {
+                if just_paused {
+                    self.send_neutral(bundle);
+                }
+            }
+            FacePauseBehavior::Frozen => {
+                let recently_sent = matches!(
+                    self.last_frozen_send,
+                    Some(t) if t.elapsed() < Duration::from_secs(1)
+                );
+                if just_paused || !recently_sent {
+                    for param in self.all_params() {
+                        param.resend(bundle);
+                    }
+                    self.last_frozen_send = Some(Instant::now());
+                }
+            }
+        }
+    }
+
+    /// Folds a fraction of the nose sneer into the upper lip, so that the expression still partially reads
+    /// on avatars without nose shapes. Does nothing if the avatar has any nose sneer parameter.
+    fn fold_nose_sneer(&mut self) {
+        let has_nose = [
+            CombinedExpression::NoseSneer as usize,
+            UnifiedExpressions::NoseSneerLeft as usize,
+            UnifiedExpressions::NoseSneerRight as usize,
+        ]
+        .iter()
+        .any(|idx| self.params[*idx].is_some());
+
+        if has_nose {
+            return;
+        }
+
+        for (sneer, upper) in [
+            (
+                UnifiedExpressions::NoseSneerLeft,
+                UnifiedExpressions::MouthUpperUpLeft,
+            ),
+            (
+                UnifiedExpressions::NoseSneerRight,
+                UnifiedExpressions::MouthUpperUpRight,
+            ),
+        ] {
+            let folded = self.data.getu(upper) + self.data.getu(sneer) * self.nose_sneer_fold;
+            self.data.setu(upper, folded.clamp(0., 1.));
+        }
+    }
+
+    /// The name and current value of every shape, and whether the avatar has a parameter for it.
+    pub fn shape_values(&self) -> impl Iterator<Item = (&'static str, f32, bool)> + '_ {
+        (0..NUM_SHAPES).filter_map(|idx| {
+            let mapped = self.params[idx].is_some()
+                || self.split_params.iter().any(|s| s.source as usize == idx);
+            shape_name(idx).map(|name| (name, self.data.shapes[idx], mapped))
+        })
+    }
+
+    /// Whether face data is currently being received.
+    pub fn face_live(&self) -> bool {
+        self.raw
+            .last_face_received
+            .is_some_and(|t| t.elapsed() < Duration::from_secs(1))
+    }
+
+    /// Whether eye data is currently being received, from the receiver or over OSC.
+    pub fn gaze_live(&self) -> bool {
+        self.data
+            .last_eyes_received
+            .is_some_and(|t| t.elapsed() < Duration::from_secs(1))
+    }
+
+    /// Lets the avatar know whether face data is currently being received, so that it can
+    /// switch between its face tracking and fallback animations. Only sent on change.
+    fn send_face_active(&mut self, bundle: &mut OscBundle) {
+        let active = self.face_live();
+
+        if self.face_active != Some(active) {
+            bundle.send_parameter("FTActive", OscType::Bool(active));
+            self.face_active = Some(active);
+        }
+    }
+
+    /// Zeroes every currently mapped parameter, so that stale values don't linger
+    /// on addresses that the next avatar happens to share.
+    pub fn reset_params(&mut self, bundle: &mut OscBundle) {
+        for param in self.all_params_mut() {
+            param.reset(bundle);
+        }
     }
 
     /// Called when a new avatar is loaded to parse its OSC JSON configuration.
     pub fn osc_json(&mut self, avatar_node: &OscJsonNode) {
         // Reset all existing parameter mappings.
         self.params.iter_mut().for_each(|p| *p = None);
+        self.split_params.clear();
+        // Make sure the new avatar gets told about the face tracking state.
+        self.face_active = None;
 
         let Some(parameters) = avatar_node.get("parameters") else {
             log::warn!("oscjson: Could not read /avatar/parameters");
@@ -221,6 +731,33 @@ impl ExtTracking {
             let main: Arc<str> = m[1].into();
 
             log::debug!("Param: {}", name);
+            if self.suppressed.contains(&main) {
+                log::debug!("Suppressed: {}", main);
+                return None;
+            }
+
+            // Halves of signed expressions get parameters of their own, next to the regular shapes.
+            if let Some((_, source, negative)) = SPLIT_PARAMS.iter().find(|(n, ..)| **n == *main) {
+                let pos = match self.split_params.iter().position(|s| s.param.name == main) {
+                    Some(pos) => pos,
+                    None => {
+                        let param = self.new_param(main.clone(), None);
+                        self.split_params.push(SplitParam {
+                            source: *source,
+                            negative: *negative,
+                            param,
+                        });
+                        self.split_params.len() - 1
+                    }
+                };
+                Self::map_address(
+                    &mut self.split_params[pos].param,
+                    m.get(2).map(|s| s.as_str()),
+                    node,
+                );
+                return None;
+            }
+
             // Try to map the parameter name to a known expression enum.
             let idx = UnifiedExpressions::from_str(&main)
                 .map(|e| e as usize)
@@ -228,6 +765,12 @@ impl ExtTracking {
                 .or_else(|_| SRanipalExpression::from_str(&main).map(|e| e as usize))
                 .ok()?;
 
+            // An alias (e.g. an SRanipal name) of a suppressed expression is suppressed too.
+            if shape_name(idx).is_some_and(|name| self.suppressed.contains(name)) {
+                log::debug!("Suppressed: {}", main);
+                return None;
+            }
+
             log::debug!(
                 "Match: {}",
                 UnifiedExpressions::iter()
@@ -242,49 +785,116 @@ impl ExtTracking {
 
             let create = self.params[idx].is_none();
 
+            // Names can alias the same shape, e.g. an SRanipal name and its unified counterpart.
+            // Their addresses end up in the same slot, so they would fight over the value.
+            if let Some(existing) = self.params[idx].as_ref().filter(|p| p.name != main) {
+                log::warn!(
+                    "{} ({}) maps to the same shape as {}, their addresses will overwrite each other.",
+                    &node.full_path.as_ref()[super::PARAM_PREFIX.len()..],
+                    main,
+                    existing.name
+                );
+            }
+
             if create {
-                let new = MysteryParam {
-                    name: main.clone(),
-                    main_address: None,
-                    addresses: array::from_fn(|_| None),
-                    neg_address: None,
-                    num_bits: 0,
-                    last_value: 0.,
-                    last_bits: [false; 8],
-                };
-                self.params[idx] = Some(new);
+                self.params[idx] = Some(self.new_param(main.clone(), None));
             };
 
-            // Update the parameter configuration based on whether it's a negative, binary, or float parameter.
-            let stored = self.params[idx].as_mut().unwrap();
-            match m.get(2).map(|s| s.as_str()) {
-                Some("Negative") => {
-                    let addr = &node.full_path.as_ref()[super::PARAM_PREFIX.len()..];
-                    stored.neg_address = Some(addr.into());
-                }
-                Some(digit) => {
-                    let digit = digit.parse::<f32>().unwrap();
-                    let idx = digit.log2() as usize;
-                    let addr = &node.full_path.as_ref()[super::PARAM_PREFIX.len()..];
-                    stored.num_bits = stored.num_bits.max(idx + 1);
-                    stored.addresses[idx] = Some(addr.into());
-                }
-                None => {
-                    let addr = &node.full_path.as_ref()[super::PARAM_PREFIX.len()..];
-                    stored.main_address = Some(addr.into());
+            Self::map_address(
+                self.params[idx].as_mut().unwrap(),
+                m.get(2).map(|s| s.as_str()),
+                node,
+            );
+        }
+        None
+    }
+
+    /// Creates a parameter with the configured smoothing and change thresholds.
+    fn new_param(&self, name: Arc<str>, main_address: Option<Arc<str>>) -> MysteryParam {
+        let mut param = MysteryParam::new(name, main_address);
+        param.smoothing = self.param_smoothing;
+        param.deadband = self.param_deadband;
+        param.bit_hysteresis = self.param_bit_hysteresis;
+        param
+    }
+
+    /// Updates the parameter configuration based on whether the address is a negative, binary, or float parameter.
+    /// `suffix` is the `Negative` or bit value suffix of the parameter name, if any.
+    fn map_address(stored: &mut MysteryParam, suffix: Option<&str>, node: &OscJsonNode) {
+        let addr = &node.full_path.as_ref()[super::PARAM_PREFIX.len()..];
+        match suffix {
+            Some("Negative") => {
+                stored.neg_address = Some(addr.into());
+            }
+            Some(digit) => {
+                let digit = digit.parse::<f32>().unwrap();
+                let idx = digit.log2() as usize;
+                // Only 8 bits (suffixes 1 to 128) are supported.
+                if idx >= stored.addresses.len() {
+                    log::warn!(
+                        "{}: bit {} exceeds the supported precision of {} bits, ignoring.",
+                        addr,
+                        idx,
+                        stored.addresses.len()
+                    );
+                    return;
                 }
+                stored.num_bits = stored.num_bits.max(idx + 1);
+                stored.addresses[idx] = Some(addr.into());
+            }
+            None => {
+                stored.main_address = Some(addr.into());
+                // Some avatars use a bool threshold parameter for a shape instead of a float.
+                stored.main_is_bool = node.is_bool();
             }
         }
-        None
+    }
+
+    /// All mapped parameters, including the split halves of signed expressions.
+    fn all_params(&self) -> impl Iterator<Item = &MysteryParam> {
+        self.params
+            .iter()
+            .flatten()
+            .chain(self.split_params.iter().map(|s| &s.param))
+    }
+
+    fn all_params_mut(&mut self) -> impl Iterator<Item = &mut MysteryParam> {
+        self.params
+            .iter_mut()
+            .flatten()
+            .chain(self.split_params.iter_mut().map(|s| &mut s.param))
+    }
+
+    /// Lists the addresses (relative to `/avatar/parameters/`) that face data is sent to,
+    /// along with their OSC type tags.
+    pub fn param_addresses(&self) -> Vec<(Arc<str>, &'static str)> {
+        let mut addresses = vec![];
+        if self.emit_face_active {
+            addresses.push(("FTActive".into(), "T"));
+        }
+        for param in self.all_params() {
+            if let Some(addr) = param.main_address.as_ref() {
+                addresses.push((addr.clone(), if param.main_is_bool { "T" } else { "f" }));
+            }
+            for addr in param
+                .addresses
+                .iter()
+                .flatten()
+                .chain(param.neg_address.iter())
+            {
+                addresses.push((addr.clone(), "T"));
+            }
+        }
+        addresses
     }
 
     /// Prints the currently configured parameters to the log for debugging.
     fn print_params(&self) {
-        for v in self.params.iter().filter_map(|p| p.as_ref()) {
+        for v in self.all_params() {
             let mut elems = vec![];
 
             if v.main_address.is_some() {
-                elems.push("float".into())
+                elems.push(if v.main_is_bool { "bool" } else { "float" }.into())
             }
             if v.num_bits > 0 {
                 elems.push(if v.num_bits > 1 {
@@ -11,11 +11,16 @@ use once_cell::sync::Lazy;
 use openxr as xr;
 use strum::EnumCount;
 
-use crate::core::{AppState, INSTRUCTIONS_END, INSTRUCTIONS_START, TRACK_ON};
+use crate::{
+    core::{status::StatusPriority, AppState, INSTRUCTIONS_END, INSTRUCTIONS_START, TRACK_ON},
+    Args, LowConfidenceBehavior, ReferenceSpace,
+};
 
 use super::{
     htc::{htc_to_unified, HtcFacialData},
-    unified::{UnifiedExpressions, UnifiedShapeAccessors, UnifiedTrackingData},
+    pico::{pico_to_unified, PicoFace},
+    raw_dump::RawFaceDump,
+    unified::{UnifiedExpressions, UnifiedShapeAccessors, UnifiedShapes, UnifiedTrackingData},
     FaceReceiver,
 };
 
@@ -26,27 +31,82 @@ static STA_GAZE_OFF: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "GAZE".color(Co
 static STA_FACE: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "FACE".color(Color::Green)).into());
 static STA_FACE_OFF: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "FACE".color(Color::Red)).into());
 
+/// How fast (per second) the face fades towards neutral while below `--face-confidence-floor`.
+const LOW_CONFIDENCE_FADE_RATE: f32 = 4.;
+
+/// Settings for the OpenXR receiver, taken from the command line.
+#[derive(Debug, Clone)]
+pub struct XrConfig {
+    /// If set, shapes of a face region are zeroed while FB reports a confidence below this value for it.
+    pub region_confidence_gate: Option<f32>,
+    /// If set, FB face data is treated as lost while both confidences are below this value.
+    pub face_confidence_floor: Option<f32>,
+    /// What happens to the face while its confidence is below the floor.
+    pub face_confidence_behavior: LowConfidenceBehavior,
+    /// If set, the raw FB/HTC weight arrays are written here every frame.
+    pub raw_dump: Option<RawFaceDump>,
+    /// The preferred reference space for head and hand tracking.
+    pub reference_space: ReferenceSpace,
+    /// The eye pitch (in degrees, looking down) at which the eyes start to close.
+    pub blink_pitch_offset: f32,
+    /// The eye pitch range (in degrees) over which the eyes go from open to fully closed.
+    pub blink_pitch_range: f32,
+    /// The eye rotation (in degrees per frame) above which a blink is detected.
+    pub blink_velocity_deg: f32,
+    /// How many frames the eyes are forced closed after a detected blink.
+    pub blink_hold_frames: u32,
+    /// How much (0-1) of the FB look shapes is blended into the eye gaze pose. 0 uses the pose only.
+    pub fb_gaze_weight: f32,
+    /// How long the HMD position must be invalid before the eyes are closed.
+    pub tracking_loss_grace: Duration,
+}
+
+impl XrConfig {
+    /// Creates the OpenXR settings from the command-line arguments.
+    pub fn new(args: &Args) -> Self {
+        Self {
+            region_confidence_gate: args.region_confidence_gate,
+            face_confidence_floor: args.face_confidence_floor,
+            face_confidence_behavior: args.face_confidence_behavior,
+            // Created once here, so that re-initializing the OpenXR session keeps writing to the same file.
+            raw_dump: args.dump_raw_face.as_deref().and_then(RawFaceDump::new),
+            reference_space: args.reference_space,
+            blink_pitch_offset: args.blink_pitch_offset,
+            // Guard against dividing by zero.
+            blink_pitch_range: args.blink_pitch_range.max(1.),
+            blink_velocity_deg: args.blink_velocity_deg,
+            blink_hold_frames: args.blink_hold_frames,
+            fb_gaze_weight: args.fb_gaze_weight.clamp(0., 1.),
+            tracking_loss_grace: Duration::from_millis(args.tracking_loss_grace_ms),
+        }
+    }
+}
+
 /// Represents a receiver for OpenXR face tracking data.
 /// It holds an optional `XrState` and tracks the last attempt time for initialization,
 /// allowing for periodic retries if initialization fails.
 pub struct OpenXrReceiver {
     state: Option<XrState>,
     last_attempt: Instant,
+    config: XrConfig,
 }
 
 impl OpenXrReceiver {
     /// Creates a new `OpenXrReceiver` with no initial state.
-    pub fn new() -> Self {
+    pub fn new(config: XrConfig) -> Self {
         Self {
             state: None,
             last_attempt: Instant::now(),
+            config,
         }
     }
 
     /// Tries to initialize the OpenXR state.
     /// If initialization fails, an error is logged.
     fn try_init(&mut self) {
-        self.state = XrState::new().map_err(|e| log::error!("XR: {}", e)).ok();
+        self.state = XrState::new(self.config.clone())
+            .map_err(|e| log::error!("XR: {}", e))
+            .ok();
         self.last_attempt = Instant::now();
     }
 }
@@ -85,8 +145,10 @@ impl FaceReceiver for OpenXrReceiver {
                 self.try_init();
             }
             // Update status to indicate that tracking is off.
-            app.status.add_item(STA_GAZE_OFF.clone());
-            app.status.add_item(STA_FACE_OFF.clone());
+            app.status
+                .add_state(STA_GAZE_OFF.clone(), false, StatusPriority::High);
+            app.status
+                .add_state(STA_FACE_OFF.clone(), false, StatusPriority::High);
             return;
         };
 
@@ -105,7 +167,8 @@ pub(super) struct XrState {
     session: xr::Session<xr::Headless>,
     frame_waiter: xr::FrameWaiter,
     frame_stream: xr::FrameStream<xr::Headless>,
-    stage_space: xr::Space,
+    /// The STAGE or LOCAL space that head and hand poses are located in.
+    tracking_space: xr::Space,
     view_space: xr::Space,
     eye_space: xr::Space,
     aim_spaces: [xr::Space; 2],
@@ -118,15 +181,24 @@ pub(super) struct XrState {
     // Optional face trackers for different vendor extensions.
     face_tracker_fb: Option<MyFaceTrackerFB>,
     face_tracker_htc: Option<MyFaceTrackerHTC>,
+    face_tracker_pico: Option<MyFaceTrackerPico>,
 
     // Counter for frames where eyes are considered closed, used for blink detection.
     eyes_closed_frames: u32,
+    // The gaze of the last frame, before the per-eye offsets and look shapes were applied.
+    last_gaze: Option<Quat>,
+
+    // Since when the HMD position has been invalid, if it is.
+    hmd_lost_since: Option<Instant>,
+
+    // Settings taken from the command line.
+    config: XrConfig,
 }
 
 impl XrState {
     /// Creates a new `XrState` by initializing the OpenXR runtime, session, actions, and spaces.
     /// It also attempts to create face trackers for supported extensions.
-    fn new() -> anyhow::Result<Self> {
+    fn new(config: XrConfig) -> anyhow::Result<Self> {
         let (instance, system) = xr_init()?;
 
         // Create an action set for the application's actions.
@@ -171,8 +243,7 @@ impl XrState {
         session.attach_action_sets(&[&actions])?;
 
         // Create reference spaces for tracking.
-        let stage_space =
-            session.create_reference_space(xr::ReferenceSpaceType::STAGE, xr::Posef::IDENTITY)?;
+        let tracking_space = create_tracking_space(&session, config.reference_space)?;
 
         let view_space =
             session.create_reference_space(xr::ReferenceSpaceType::VIEW, xr::Posef::IDENTITY)?;
@@ -194,7 +265,8 @@ impl XrState {
             frame_stream,
             face_tracker_fb: None,
             face_tracker_htc: None,
-            stage_space,
+            face_tracker_pico: None,
+            tracking_space,
             view_space,
             eye_space,
             aim_spaces,
@@ -204,6 +276,9 @@ impl XrState {
             events: xr::EventDataBuffer::new(),
             session_running: false,
             eyes_closed_frames: 0,
+            last_gaze: None,
+            hmd_lost_since: None,
+            config,
         };
 
         // Attempt to create face trackers, logging info on failure.
@@ -214,28 +289,24 @@ impl XrState {
             .map_err(|e| log::info!("HTC_facial_tracking: {}", e))
             .ok();
 
+        // FB and Pico report the same face, so only one of them is used. FB is preferred.
+        if me.face_tracker_fb.is_some() {
+            if me.instance.exts().bd_facial_simulation.is_some() {
+                log::info!("BD_facial_simulation: Not used, FB_face_tracking2 is preferred.");
+            }
+        } else {
+            me.face_tracker_pico = MyFaceTrackerPico::new(&me)
+                .map_err(|e| log::info!("BD_facial_simulation: {}", e))
+                .ok();
+        }
+
         Ok(me)
     }
 
     /// Helper function to load system properties with a specific extension structure.
     /// This is used to query for support of face tracking extensions.
     fn load_properties<T>(&self, next: *mut T) -> xr::Result<()> {
-        unsafe {
-            let mut p = xr::sys::SystemProperties {
-                ty: xr::sys::SystemProperties::TYPE,
-                next: next as _,
-                ..std::mem::zeroed()
-            };
-            let res = (self.instance.fp().get_system_properties)(
-                self.instance.as_raw(),
-                self.system,
-                &mut p,
-            );
-            if res != xr::sys::Result::SUCCESS {
-                return Err(res);
-            }
-            Ok(())
-        }
+        load_system_properties(&self.instance, self.system, next)
     }
 
     /// Polls for OpenXR events, syncs actions, and retrieves tracking data.
@@ -293,27 +364,34 @@ impl XrState {
         self.session.sync_actions(&[(&self.actions).into()])?;
 
         // Locate the HMD in stage space.
-        let hmd_loc = self.view_space.locate(&self.stage_space, next_frame)?;
+        let hmd_loc = self.view_space.locate(&self.tracking_space, next_frame)?;
         if hmd_loc
             .location_flags
             .contains(xr::SpaceLocationFlags::POSITION_VALID)
         {
             state.tracking.head = to_affine(&hmd_loc);
-            state.tracking.last_received = Instant::now();
+            state.tracking.last_received = Some(Instant::now());
+            state.tracking.received_over_osc = false;
+            self.hmd_lost_since = None;
         } else {
             // If HMD position is not valid (e.g., sleeping), close the avatar's eyes.
-            data.shapes.setu(UnifiedExpressions::EyeClosedLeft, 1.0);
-            data.shapes.setu(UnifiedExpressions::EyeClosedRight, 1.0);
+            // Single dropped frames are common, so only once it has been invalid for a while.
+            let lost_since = *self.hmd_lost_since.get_or_insert_with(Instant::now);
+            if lost_since.elapsed() >= self.config.tracking_loss_grace {
+                data.shapes.setu(UnifiedExpressions::EyeClosedLeft, 1.0);
+                data.shapes.setu(UnifiedExpressions::EyeClosedRight, 1.0);
+            }
         }
 
         // Locate the aim poses for hands.
-        let aim_loc = self.aim_spaces[0].locate(&self.stage_space, next_frame)?;
+        let aim_loc = self.aim_spaces[0].locate(&self.tracking_space, next_frame)?;
         state.tracking.left_hand = to_affine(&aim_loc);
-        let aim_loc = self.aim_spaces[1].locate(&self.stage_space, next_frame)?;
+        let aim_loc = self.aim_spaces[1].locate(&self.tracking_space, next_frame)?;
         state.tracking.right_hand = to_affine(&aim_loc);
 
         // Locate the eye gaze pose relative to the view space.
         let eye_loc = self.eye_space.locate(&self.view_space, next_frame)?;
+        let mut gaze_valid = false;
         if eye_loc.location_flags.contains(
             xr::SpaceLocationFlags::ORIENTATION_VALID | xr::SpaceLocationFlags::ORIENTATION_TRACKED,
         ) {
@@ -321,16 +399,18 @@ impl XrState {
             let (y, x, z) = now_q.to_euler(EulerRot::YXZ);
 
             // Calculate eye closure based on the pitch of the eye rotation.
-            let mut eye_closed = ((x.to_degrees() + 5.0) / -55.0).max(0.0);
+            let mut eye_closed = ((x.to_degrees() + self.config.blink_pitch_offset)
+                / -self.config.blink_pitch_range)
+                .max(0.0);
 
             // Simple blink detection: if eye rotation changes rapidly, force eyes closed for a few frames.
-            if let Some(last) = data.eyes[0] {
-                let last_q = Quat::from_euler(EulerRot::YXZ, last.y, last.x, last.z);
-
-                if last_q.angle_between(now_q).to_degrees() > 10.0 {
-                    self.eyes_closed_frames = 5;
+            // Compared against the raw gaze, so that the per-eye offsets applied below can't trigger it.
+            if let Some(last_q) = self.last_gaze {
+                if last_q.angle_between(now_q).to_degrees() > self.config.blink_velocity_deg {
+                    self.eyes_closed_frames = self.config.blink_hold_frames;
                 }
             }
+            self.last_gaze = Some(now_q);
 
             if self.eyes_closed_frames > 0 {
                 self.eyes_closed_frames -= 1;
@@ -343,53 +423,310 @@ impl XrState {
             data.shapes
                 .setu(UnifiedExpressions::EyeClosedRight, eye_closed);
 
+            // Both eyes start out mirrored; per-eye face data may split them up below.
             data.eyes[0] = Some(vec3(x, y, z));
             data.eyes[1] = data.eyes[0];
-            state.status.add_item(STA_GAZE.clone());
-        } else {
-            state.status.add_item(STA_GAZE_OFF.clone());
+            data.last_eyes_received = Some(Instant::now());
+            gaze_valid = true;
         }
+        // The HTC eye tracker provides eye data without the eye gaze extension, so the status is added after it.
+        let mut eyes_received = gaze_valid;
 
         // Get face tracking data from the Facebook extension if available.
         if let Some(face_tracker) = self.face_tracker_fb.as_ref() {
             let mut weights = [0f32; 70];
             let mut confidences = [0f32; 2];
 
-            let is_valid = face_tracker.get_face_expression_weights(
+            let (is_valid, eye_following_valid) = face_tracker.get_face_expression_weights(
                 next_frame,
                 &mut weights,
                 &mut confidences,
             )?;
 
-            if is_valid {
-                if let Some(shapes) = super::face2_fb::face2_fb_to_unified(&weights) {
+            // After losing the face, the tracker may keep reporting valid but meaningless weights.
+            let confident = self
+                .config
+                .face_confidence_floor
+                .is_none_or(|floor| confidences.iter().any(|c| *c >= floor));
+
+            if is_valid && !confident {
+                if self.config.face_confidence_behavior == LowConfidenceBehavior::Neutral {
+                    let keep = (1. - LOW_CONFIDENCE_FADE_RATE * state.delta_t).max(0.);
+                    data.shapes[..UnifiedExpressions::COUNT]
+                        .iter_mut()
+                        .for_each(|shape| *shape *= keep);
+                }
+                state
+                    .status
+                    .add_state(STA_FACE_OFF.clone(), false, StatusPriority::High);
+            } else if is_valid {
+                if let Some(dump) = self.config.raw_dump.as_ref() {
+                    let mut raw = weights.to_vec();
+                    raw.extend_from_slice(&confidences);
+                    dump.send("fb", &raw);
+                }
+                // Let each eye turn on its own, based on the per-eye look shapes,
+                // as long as the tracker says that they follow the eyes.
+                if gaze_valid && eye_following_valid {
+                    if let Some(offsets) = super::face2_fb::fb_eye_yaw_offsets(&weights) {
+                        for (eye, offset) in data.eyes.iter_mut().zip(offsets) {
+                            if let Some(eye) = eye.as_mut() {
+                                eye.y += offset;
+                            }
+                        }
+                    }
+
+                    // The pose tends to drift while the eyes close, so blending in the look shapes
+                    // keeps the gaze from popping around blinks.
+                    let weight = self.config.fb_gaze_weight;
+                    if weight > 0. {
+                        if let Some(gaze) = super::face2_fb::fb_eye_gaze(&weights) {
+                            for (eye, shape_eye) in data.eyes.iter_mut().zip(gaze) {
+                                if let Some(eye) = eye.as_mut() {
+                                    *eye = eye.lerp(shape_eye, weight);
+                                }
+                            }
+                        }
+                    }
+                }
+                if let Some(mut shapes) = super::face2_fb::face2_fb_to_unified(&weights) {
+                    if let Some(gate) = self.config.region_confidence_gate {
+                        gate_face_regions(&mut shapes, &confidences, gate);
+                    }
                     data.shapes[..=UnifiedExpressions::COUNT]
                         .copy_from_slice(&shapes[..=UnifiedExpressions::COUNT]);
                 }
-                state.status.add_item(STA_FACE.clone());
+                data.last_face_received = Some(Instant::now());
+                state
+                    .status
+                    .add_state(STA_FACE.clone(), true, StatusPriority::High);
             } else {
-                state.status.add_item(STA_FACE_OFF.clone());
+                state
+                    .status
+                    .add_state(STA_FACE_OFF.clone(), false, StatusPriority::High);
             }
         };
 
+        // Get face tracking data from the Pico extension if available.
+        if let Some(face_tracker) = self.face_tracker_pico.as_ref() {
+            let mut weights = [0f32; PicoFace::COUNT];
+
+            if face_tracker.get_face_expression_weights(next_frame, &mut weights)? {
+                if let Some(dump) = self.config.raw_dump.as_ref() {
+                    dump.send("pico", &weights);
+                }
+                if let Some(shapes) = pico_to_unified(&weights) {
+                    data.shapes[..UnifiedExpressions::COUNT]
+                        .copy_from_slice(&shapes[..UnifiedExpressions::COUNT]);
+                }
+                data.last_face_received = Some(Instant::now());
+                state
+                    .status
+                    .add_state(STA_FACE.clone(), true, StatusPriority::High);
+            } else {
+                state
+                    .status
+                    .add_state(STA_FACE_OFF.clone(), false, StatusPriority::High);
+            }
+        }
+
         // Get face tracking data from the HTC extension if available.
         if let Some(face_tracker) = self.face_tracker_htc.as_ref() {
             let htc_data = face_tracker.get_expressions(next_frame);
 
             if htc_data.eye.is_some() || htc_data.lip.is_some() {
+                if let Some(dump) = self.config.raw_dump.as_ref() {
+                    if let Some(eye) = htc_data.eye.as_ref() {
+                        dump.send("htc_eye", eye);
+                    }
+                    if let Some(lip) = htc_data.lip.as_ref() {
+                        dump.send("htc_lip", lip);
+                    }
+                }
                 let shapes = htc_to_unified(&htc_data);
                 data.shapes[..=UnifiedExpressions::COUNT]
                     .copy_from_slice(&shapes[..=UnifiedExpressions::COUNT]);
-                state.status.add_item(STA_FACE.clone());
+                data.last_face_received = Some(Instant::now());
+                if htc_data.eye.is_some() {
+                    data.last_eyes_received = data.last_face_received;
+                    eyes_received = true;
+                }
+                state
+                    .status
+                    .add_state(STA_FACE.clone(), true, StatusPriority::High);
             } else {
-                state.status.add_item(STA_FACE_OFF.clone());
+                state
+                    .status
+                    .add_state(STA_FACE_OFF.clone(), false, StatusPriority::High);
+            }
+        }
+
+        if eyes_received {
+            state
+                .status
+                .add_state(STA_GAZE.clone(), true, StatusPriority::High);
+        } else {
+            state
+                .status
+                .add_state(STA_GAZE_OFF.clone(), false, StatusPriority::High);
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates the reference space that head and hand poses are located in.
+/// If STAGE is preferred but the runtime can't provide it (e.g. no playspace is configured), LOCAL is used instead.
+fn create_tracking_space(
+    session: &xr::Session<xr::Headless>,
+    preferred: ReferenceSpace,
+) -> anyhow::Result<xr::Space> {
+    if preferred == ReferenceSpace::Stage {
+        match session.create_reference_space(xr::ReferenceSpaceType::STAGE, xr::Posef::IDENTITY) {
+            Ok(space) => {
+                log::info!("Using STAGE reference space for tracking.");
+                return Ok(space);
             }
+            Err(e) => log::warn!(
+                "Could not create STAGE reference space, falling back to LOCAL: {}",
+                e
+            ),
         }
+    }
+
+    let space =
+        session.create_reference_space(xr::ReferenceSpaceType::LOCAL, xr::Posef::IDENTITY)?;
+    log::info!("Using LOCAL reference space for tracking.");
+    Ok(space)
+}
 
+/// Zeroes the shapes of each face region whose FB confidence is below `gate`,
+/// so that the reliable half of the face keeps moving while the other half is occluded.
+/// FB reports the lower face (mouth, jaw, cheeks, nose) at index 0 and the upper face (eyes, brows) at index 1.
+fn gate_face_regions(shapes: &mut UnifiedShapes, confidences: &[f32; 2], gate: f32) {
+    let split = UnifiedExpressions::NasalDilationRight as usize;
+
+    if confidences[0] < gate {
+        shapes[split..UnifiedExpressions::COUNT].fill(0.);
+    }
+    if confidences[1] < gate {
+        shapes[..split].fill(0.);
+    }
+}
+
+/// Queries the system properties, filling in the extension struct chained as `next`.
+fn load_system_properties<T>(
+    instance: &xr::Instance,
+    system: xr::SystemId,
+    next: *mut T,
+) -> xr::Result<()> {
+    unsafe {
+        let mut p = xr::sys::SystemProperties {
+            ty: xr::sys::SystemProperties::TYPE,
+            next: next as _,
+            ..std::mem::zeroed()
+        };
+        let res = (instance.fp().get_system_properties)(instance.as_raw(), system, &mut p);
+        if res != xr::sys::Result::SUCCESS {
+            return Err(res);
+        }
         Ok(())
     }
 }
 
+/// Prints which of the extensions used for face tracking the runtime exposes,
+/// and what the system reports it can track with them, for `--list-extensions`.
+pub(crate) fn list_extensions() -> anyhow::Result<()> {
+    let entry = xr::Entry::linked();
+    let Ok(available) = entry.enumerate_extensions() else {
+        anyhow::bail!("Failed to enumerate OpenXR extensions.");
+    };
+
+    let yes_no = |b: bool| {
+        if b {
+            "yes".color(Color::Green)
+        } else {
+            "no".color(Color::Red)
+        }
+    };
+    let bool32 = |b: xr::sys::Bool32| yes_no(b.into_raw() != 0);
+
+    println!("{:<32} Present", "Extension");
+    for (name, present) in [
+        ("XR_MND_headless", available.mnd_headless),
+        (
+            "XR_EXT_eye_gaze_interaction",
+            available.ext_eye_gaze_interaction,
+        ),
+        ("XR_FB_face_tracking2", available.fb_face_tracking2),
+        ("XR_HTC_facial_tracking", available.htc_facial_tracking),
+        ("XR_BD_facial_simulation", available.bd_facial_simulation),
+    ] {
+        println!("{:<32} {}", name, yes_no(present));
+    }
+
+    // The capabilities can only be queried from an instance, which needs MND_headless.
+    let (instance, system) = xr_init()?;
+    println!();
+    println!("{:<32} Supported", "Capability");
+
+    if instance.exts().fb_face_tracking2.is_some() {
+        let mut props = xr::sys::SystemFaceTrackingProperties2FB {
+            ty: xr::StructureType::SYSTEM_FACE_TRACKING_PROPERTIES2_FB,
+            next: std::ptr::null_mut(),
+            supports_visual_face_tracking: xr::sys::Bool32::from_raw(0),
+            supports_audio_face_tracking: xr::sys::Bool32::from_raw(0),
+        };
+        load_system_properties(&instance, system, &mut props)?;
+        println!(
+            "{:<32} {}",
+            "FB visual face tracking",
+            bool32(props.supports_visual_face_tracking)
+        );
+        println!(
+            "{:<32} {}",
+            "FB audio face tracking",
+            bool32(props.supports_audio_face_tracking)
+        );
+    }
+
+    if instance.exts().htc_facial_tracking.is_some() {
+        let mut props = xr::sys::SystemFacialTrackingPropertiesHTC {
+            ty: xr::StructureType::SYSTEM_FACIAL_TRACKING_PROPERTIES_HTC,
+            next: std::ptr::null_mut(),
+            support_eye_facial_tracking: xr::sys::Bool32::from_raw(0),
+            support_lip_facial_tracking: xr::sys::Bool32::from_raw(0),
+        };
+        load_system_properties(&instance, system, &mut props)?;
+        println!(
+            "{:<32} {}",
+            "HTC eye facial tracking",
+            bool32(props.support_eye_facial_tracking)
+        );
+        println!(
+            "{:<32} {}",
+            "HTC lip facial tracking",
+            bool32(props.support_lip_facial_tracking)
+        );
+    }
+
+    if instance.exts().bd_facial_simulation.is_some() {
+        let mut props = xr::sys::SystemFacialSimulationPropertiesBD {
+            ty: xr::StructureType::SYSTEM_FACIAL_SIMULATION_PROPERTIES_BD,
+            next: std::ptr::null_mut(),
+            supports_face_tracking: xr::sys::Bool32::from_raw(0),
+        };
+        load_system_properties(&instance, system, &mut props)?;
+        println!(
+            "{:<32} {}",
+            "BD face tracking",
+            bool32(props.supports_face_tracking)
+        );
+    }
+
+    Ok(())
+}
+
 /// Initializes the OpenXR entry, instance, and system.
 /// It enumerates and enables required and optional extensions.
 fn xr_init() -> anyhow::Result<(xr::Instance, xr::SystemId)> {
@@ -424,6 +761,10 @@ fn xr_init() -> anyhow::Result<(xr::Instance, xr::SystemId)> {
         enabled_extensions.htc_facial_tracking = true;
     }
 
+    if available_extensions.bd_facial_simulation {
+        enabled_extensions.bd_facial_simulation = true;
+    }
+
     // Create the OpenXR instance.
     let Ok(instance) = entry.create_instance(
         &xr::ApplicationInfo {
@@ -517,12 +858,13 @@ impl MyFaceTrackerFB {
     }
 
     /// Gets the latest face expression weights.
+    /// Returns whether the weights are valid, and whether the eye look shapes follow the eyes.
     pub fn get_face_expression_weights(
         &self,
         time: xr::Time,
         weights: &mut [f32],
         confidences: &mut [f32],
-    ) -> anyhow::Result<bool> {
+    ) -> anyhow::Result<(bool, bool)> {
         let mut expressions = xr::sys::FaceExpressionWeights2FB {
             ty: xr::StructureType::FACE_EXPRESSION_WEIGHTS2_FB,
             next: std::ptr::null_mut(),
@@ -549,7 +891,19 @@ impl MyFaceTrackerFB {
             anyhow::bail!("Failed to get expression weights");
         }
 
-        Ok(expressions.is_valid.into_raw() != 0)
+        Ok((
+            expressions.is_valid.into_raw() != 0,
+            expressions.is_eye_following_blendshapes_valid.into_raw() != 0,
+        ))
+    }
+}
+
+impl Drop for XrState {
+    /// Destroys the face trackers before the session and instance they were created from.
+    fn drop(&mut self) {
+        self.face_tracker_fb = None;
+        self.face_tracker_htc = None;
+        self.face_tracker_pico = None;
     }
 }
 
@@ -704,6 +1058,103 @@ impl Drop for MyFaceTrackerHTC {
     }
 }
 
+/// Wrapper for the Pico face tracking extension (BD_facial_simulation).
+pub(super) struct MyFaceTrackerPico {
+    api: xr::raw::FacialSimulationBD,
+    tracker: xr::sys::FaceTrackerBD,
+}
+
+impl MyFaceTrackerPico {
+    /// Creates a new Pico face tracker.
+    /// It checks for extension support and initializes the tracker.
+    pub fn new(xr_state: &XrState) -> anyhow::Result<Self> {
+        if xr_state.instance.exts().bd_facial_simulation.is_none() {
+            anyhow::bail!("Extension not supported.");
+        }
+
+        // Query system properties for face tracking support.
+        let mut props = xr::sys::SystemFacialSimulationPropertiesBD {
+            ty: xr::StructureType::SYSTEM_FACIAL_SIMULATION_PROPERTIES_BD,
+            next: std::ptr::null_mut(),
+            supports_face_tracking: xr::sys::Bool32::from_raw(0),
+        };
+
+        xr_state.load_properties(&mut props)?;
+
+        if props.supports_face_tracking.into_raw() == 0 {
+            anyhow::bail!("Unable to provide face data.");
+        }
+
+        // Load the extension's raw API functions.
+        let api = unsafe {
+            xr::raw::FacialSimulationBD::load(
+                xr_state.session.instance().entry(),
+                xr_state.session.instance().as_raw(),
+            )?
+        };
+
+        let info = xr::sys::FaceTrackerCreateInfoBD {
+            ty: xr::StructureType::FACE_TRACKER_CREATE_INFO_BD,
+            next: std::ptr::null(),
+            mode: xr::sys::FacialSimulationModeBD::DEFAULT,
+        };
+
+        let mut tracker = xr::sys::FaceTrackerBD::default();
+
+        // Create the face tracker.
+        let res =
+            unsafe { (api.create_face_tracker)(xr_state.session.as_raw(), &info, &mut tracker) };
+        if res.into_raw() != 0 {
+            anyhow::bail!("Could not initialize: {:?}", res);
+        }
+
+        log::info!("Using BD_facial_simulation (Pico) for face.");
+
+        Ok(Self { api, tracker })
+    }
+
+    /// Gets the latest face expression weights.
+    /// Returns whether the weights are valid, which they are if either half of the face is tracked.
+    pub fn get_face_expression_weights(
+        &self,
+        time: xr::Time,
+        weights: &mut [f32],
+    ) -> anyhow::Result<bool> {
+        let info = xr::sys::FaceSimulationDataGetInfoBD {
+            ty: xr::StructureType::FACE_SIMULATION_DATA_GET_INFO_BD,
+            next: std::ptr::null(),
+            time,
+        };
+
+        let mut data = xr::sys::FaceSimulationDataBD {
+            ty: xr::StructureType::FACE_SIMULATION_DATA_BD,
+            next: std::ptr::null_mut(),
+            face_expression_weight_count: weights.len() as _,
+            face_expression_weights: weights.as_mut_ptr(),
+            is_upper_face_data_valid: xr::sys::Bool32::from_raw(0),
+            is_lower_face_data_valid: xr::sys::Bool32::from_raw(0),
+            time,
+        };
+
+        let res = unsafe { (self.api.get_face_simulation_data)(self.tracker, &info, &mut data) };
+        if res.into_raw() != 0 {
+            anyhow::bail!("Failed to get face simulation data");
+        }
+
+        Ok(data.is_upper_face_data_valid.into_raw() != 0
+            || data.is_lower_face_data_valid.into_raw() != 0)
+    }
+}
+
+impl Drop for MyFaceTrackerPico {
+    /// Destroys the face tracker when the struct is dropped.
+    fn drop(&mut self) {
+        unsafe {
+            (self.api.destroy_face_tracker)(self.tracker);
+        }
+    }
+}
+
 /// Converts an `xr::Quaternionf` to a `glam::Quat`.
 fn to_quat(p: xr::Quaternionf) -> Quat {
     let q: Quaternion<f32> = p.into();
@@ -0,0 +1,63 @@
+use super::unified::{neutral_shapes, CombinedExpression, UnifiedShapes, UnifiedTrackingData};
+
+/// Fades the sent face towards neutral while tracking is paused (AFK, `FaceFreeze`, ...),
+/// instead of leaving the avatar stuck mid-expression.
+///
+/// Only the sent copy is faded, the tracking data itself keeps the last frame. Once tracking resumes,
+/// the weight climbs back at the same rate, so the face fades in from wherever the ramp left off.
+pub struct PauseRamp {
+    /// How much (per second) the weight changes.
+    rate: f32,
+    /// The current weight of the sent face, from 1 (as tracked) to 0 (neutral).
+    weight: f32,
+    /// The neutral face that is faded towards, e.g. open eye lids.
+    neutral: UnifiedShapes,
+}
+
+impl PauseRamp {
+    /// Creates a new `PauseRamp` that takes `ramp_ms` to go from the tracked face to neutral.
+    /// Returns `None` if the duration is zero, which keeps the hard freeze.
+    pub fn new(ramp_ms: u64) -> Option<Self> {
+        if ramp_ms == 0 {
+            return None;
+        }
+
+        log::info!("Pause ramp: fading to neutral over {}ms", ramp_ms);
+        Some(Self {
+            rate: 1000. / ramp_ms as f32,
+            weight: 1.,
+            neutral: neutral_shapes(),
+        })
+    }
+
+    /// Moves the weight towards neutral while paused, or back towards the tracked face otherwise.
+    pub fn update(&mut self, paused: bool, delta_t: f32) {
+        let step = self.rate * delta_t;
+        self.weight = if paused {
+            (self.weight - step).max(0.)
+        } else {
+            (self.weight + step).min(1.)
+        };
+    }
+
+    /// Whether the sent face differs from the tracked one.
+    pub fn active(&self) -> bool {
+        self.weight < 1.
+    }
+
+    /// Returns a copy of the tracking data, faded towards neutral by the current weight.
+    /// The eyes are faded towards the center. Blush is left as-is, since it isn't driven by the tracking.
+    pub fn apply(&self, data: &UnifiedTrackingData) -> UnifiedTrackingData {
+        let mut faded = data.clone();
+        for (idx, shape) in faded.shapes.iter_mut().enumerate() {
+            if idx != CombinedExpression::Blush as usize {
+                let neutral = self.neutral[idx];
+                *shape = neutral + (*shape - neutral) * self.weight;
+            }
+        }
+        for eye in faded.eyes.iter_mut().flatten() {
+            *eye *= self.weight;
+        }
+        faded
+    }
+}
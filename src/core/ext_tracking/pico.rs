@@ -0,0 +1,91 @@
+//! This module handles the conversion of face tracking data from Pico headsets,
+//! which expose it through the `BD_facial_simulation` extension (BD being Pico's parent company),
+//! to the application's `UnifiedExpressions` format. The weights are ARKit blendshapes,
+//! so they are converted through the same mapping as VMC.
+
+use std::collections::HashMap;
+
+use strum::{EnumCount, IntoEnumIterator, IntoStaticStr};
+
+use super::{arkit::arkit_to_unified, unified::UnifiedShapes};
+
+/// The blendshapes reported by the Pico face tracker, in the order of its weight array.
+/// These are the 52 ARKit blendshapes, named so that the lowercase variant name matches the ARKit name.
+#[allow(unused)]
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, EnumCount, strum::EnumIter, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+pub(super) enum PicoFace {
+    EyeLookDownLeft,
+    NoseSneerLeft,
+    EyeLookInLeft,
+    BrowInnerUp,
+    BrowDownRight,
+    MouthClose,
+    MouthLowerDownRight,
+    JawOpen,
+    MouthUpperUpRight,
+    MouthShrugUpper,
+    MouthFunnel,
+    EyeLookInRight,
+    EyeLookDownRight,
+    NoseSneerRight,
+    MouthRollUpper,
+    JawRight,
+    BrowDownLeft,
+    MouthShrugLower,
+    MouthRollLower,
+    MouthSmileLeft,
+    MouthPressLeft,
+    MouthSmileRight,
+    MouthPressRight,
+    MouthDimpleRight,
+    MouthLeft,
+    JawForward,
+    EyeSquintLeft,
+    MouthFrownLeft,
+    EyeBlinkLeft,
+    CheekSquintLeft,
+    BrowOuterUpLeft,
+    EyeLookUpLeft,
+    JawLeft,
+    MouthStretchLeft,
+    MouthPucker,
+    EyeLookUpRight,
+    BrowOuterUpRight,
+    CheekSquintRight,
+    EyeBlinkRight,
+    MouthUpperUpLeft,
+    MouthFrownRight,
+    EyeSquintRight,
+    MouthStretchRight,
+    CheekPuff,
+    EyeLookOutLeft,
+    EyeLookOutRight,
+    EyeWideRight,
+    EyeWideLeft,
+    MouthRight,
+    MouthDimpleLeft,
+    MouthLowerDownLeft,
+    TongueOut,
+}
+
+/// Converts the weight array of the Pico face tracker into the application's `UnifiedShapes` format.
+///
+/// # Returns
+///
+/// The converted data, or `None` if the input slice is too short.
+pub(super) fn pico_to_unified(weights: &[f32]) -> Option<UnifiedShapes> {
+    if weights.len() < PicoFace::COUNT {
+        return None;
+    }
+
+    let values: HashMap<String, f32> = PicoFace::iter()
+        .map(|e| {
+            let name: &str = e.into();
+            (name.to_string(), weights[e as usize])
+        })
+        .collect();
+
+    Some(arkit_to_unified(&values))
+}
@@ -0,0 +1,112 @@
+//! Writes the raw tracker weight arrays to a CSV file for offline analysis and mapping tuning.
+//!
+//! Each line is one array from one frame:
+//!
+//! ```text
+//! <timestamp>,<source>,<value 0>,<value 1>,...
+//! ```
+//!
+//! * `timestamp` - Seconds since the UNIX epoch, with millisecond precision.
+//! * `source` - Which array the line holds:
+//!   * `fb` - 70 `FB_face_tracking2` weights in `FaceFb`/`Face2Fb` order, followed by
+//!     the 2 confidences (lower face, upper face). 72 values in total.
+//!   * `htc_eye` - The `HTC_facial_tracking` eye expressions, in `EyeExpressionHTC` order.
+//!   * `htc_lip` - The `HTC_facial_tracking` lip expressions, in `LipExpressionHTC` order.
+//!   * `pico` - The 52 `BD_facial_simulation` (Pico) weights, in `PicoFace` order.
+
+use std::{
+    fs::OpenOptions,
+    io::{BufWriter, Write},
+    sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// How often the writer thread flushes the file, so that it's usable while still running.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A single array to be written, along with when it was captured.
+struct RawFrame {
+    timestamp: f64,
+    source: &'static str,
+    values: Vec<f32>,
+}
+
+/// A handle to the background thread that writes the raw face dump.
+/// Cloning the handle shares the same file.
+#[derive(Debug, Clone)]
+pub struct RawFaceDump {
+    sender: SyncSender<RawFrame>,
+}
+
+impl RawFaceDump {
+    /// Opens (appends to) the dump file and starts the writer thread.
+    /// Returns `None` if the file can't be opened.
+    pub fn new(path: &str) -> Option<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| log::error!("Could not open raw face dump {}: {:?}", path, e))
+            .ok()?;
+
+        log::info!("Dumping raw face data to {}", path);
+
+        let (sender, receiver) = sync_channel(256);
+        thread::spawn(move || write_loop(BufWriter::new(file), receiver));
+
+        Some(Self { sender })
+    }
+
+    /// Queues an array for writing. Never blocks; if the writer falls behind, the frame is dropped.
+    pub fn send(&self, source: &'static str, values: &[f32]) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.);
+
+        let frame = RawFrame {
+            timestamp,
+            source,
+            values: values.to_vec(),
+        };
+
+        if let Err(e) = self.sender.try_send(frame) {
+            log::debug!("Dropped raw face frame: {}", e);
+        }
+    }
+}
+
+/// Writes incoming frames until all handles are dropped.
+fn write_loop(mut writer: BufWriter<std::fs::File>, receiver: Receiver<RawFrame>) {
+    let mut next_flush = Instant::now() + FLUSH_INTERVAL;
+
+    loop {
+        // Wake up for the next flush even if the tracker goes quiet.
+        let timeout = next_flush.saturating_duration_since(Instant::now());
+        let frame = match receiver.recv_timeout(timeout) {
+            Ok(frame) => Some(frame),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        let mut result = Ok(());
+        if let Some(frame) = frame {
+            let mut line = format!("{:.3},{}", frame.timestamp, frame.source);
+            for v in frame.values.iter() {
+                line.push_str(&format!(",{}", v));
+            }
+            line.push('\n');
+            result = writer.write_all(line.as_bytes());
+        }
+        if result.is_ok() && Instant::now() >= next_flush {
+            result = writer.flush();
+            next_flush = Instant::now() + FLUSH_INTERVAL;
+        }
+        if let Err(e) = result {
+            log::error!("Could not write raw face dump: {:?}", e);
+            return;
+        }
+    }
+    let _ = writer.flush();
+}
@@ -0,0 +1,87 @@
+use std::time::{Duration, Instant};
+
+use strum::EnumCount;
+
+use super::unified::{
+    neutral_shapes, CombinedExpression, UnifiedExpressions, UnifiedShapes, NUM_SHAPES,
+};
+
+/// Relaxes the face towards neutral when face data stops arriving.
+///
+/// Without this, the avatar keeps the last received expression forever, which looks like a frozen face.
+/// Base (unified) shapes and combined shapes fade out separately, so e.g. a smile can settle faster
+/// than the underlying mouth shapes. The fade is undone immediately once face data comes back.
+pub struct Relax {
+    /// How long face data must be missing before relaxing starts.
+    after: Duration,
+    /// How fast (per second) base shapes fade towards neutral.
+    base_rate: f32,
+    /// How fast (per second) combined shapes fade towards neutral.
+    combined_rate: f32,
+    /// The current weight of the base shapes, from 1 (as received) to 0 (neutral).
+    base: f32,
+    /// The current weight of the combined shapes, from 1 (as calculated) to 0 (neutral).
+    combined: f32,
+    /// The neutral values that the combined shapes fade towards, e.g. open eye lids.
+    neutral: UnifiedShapes,
+}
+
+impl Relax {
+    /// Creates a new `Relax`. Returns `None` if both rates are zero, leaving the face as-is on data loss.
+    /// If no combined rate is given, combined shapes fade at the same rate as base shapes.
+    pub fn new(after_ms: u64, base_rate: f32, combined_rate: Option<f32>) -> Option<Self> {
+        let combined_rate = combined_rate.unwrap_or(base_rate);
+        if base_rate <= 0. && combined_rate <= 0. {
+            return None;
+        }
+
+        Some(Self {
+            after: Duration::from_millis(after_ms),
+            base_rate,
+            combined_rate,
+            base: 1.,
+            combined: 1.,
+            neutral: neutral_shapes(),
+        })
+    }
+
+    /// Advances the fade based on when face data was last received.
+    pub fn update(&mut self, last_face_received: Option<Instant>, delta_t: f32) {
+        let lost = last_face_received.is_some_and(|t| t.elapsed() > self.after);
+
+        if lost {
+            self.base = (self.base - self.base_rate * delta_t).max(0.);
+            self.combined = (self.combined - self.combined_rate * delta_t).max(0.);
+        } else {
+            self.base = 1.;
+            self.combined = 1.;
+        }
+    }
+
+    /// Fades the base shapes towards neutral. Must be called before `calc_combined`.
+    pub fn apply_base(&self, shapes: &mut UnifiedShapes) {
+        if self.base < 1. {
+            shapes[..UnifiedExpressions::COUNT]
+                .iter_mut()
+                .for_each(|s| *s *= self.base);
+        }
+    }
+
+    /// Fades the combined shapes towards neutral. Must be called after `calc_combined`.
+    pub fn apply_combined(&self, shapes: &mut UnifiedShapes) {
+        if self.combined < 1. {
+            for (idx, s) in shapes
+                .iter_mut()
+                .enumerate()
+                .take(NUM_SHAPES)
+                .skip(UnifiedExpressions::COUNT)
+            {
+                // Blush builds up from its own previous value, so it must not be faded every frame.
+                if idx != CombinedExpression::Blush as usize {
+                    let neutral = self.neutral[idx];
+                    *s = neutral + (*s - neutral) * self.combined;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Records tracking sessions to a binary log, and replays them as a face receiver,
+//! so that mapping bugs can be reproduced offline without the headset.
+//!
+//! All numbers are little endian. The file starts with a header:
+//!
+//! ```text
+//! "OAMR" <version: u16> <shape count: u16>
+//! ```
+//!
+//! Followed by one record per frame, each prefixed with its length in bytes as a `u32`:
+//!
+//! * `time: f64` - Seconds since the recording started.
+//! * `flags: u8` - Bit 0: left eye present, bit 1: right eye present,
+//!   bit 2: head & wrists were received over OSC.
+//! * `face_age, eyes_age, track_age: f32` - Seconds since face, eye and head data was last received, -1 for never.
+//! * `eyes: 6 × f32` - Left and right eye euler angles, zero if not present.
+//! * `shapes: <shape count> × f32` - All unified and combined shapes.
+//! * `head, left_hand, right_hand: 3 × 12 × f32` - The column-major affine transforms.
+//!
+//! Readers skip any bytes at the end of a record that they don't know about.
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    sync::mpsc::{sync_channel, Receiver, SyncSender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use glam::{Affine3A, Vec3};
+
+use crate::core::{AppState, OscTrack};
+
+use super::{
+    unified::{UnifiedTrackingData, NUM_SHAPES},
+    FaceReceiver,
+};
+
+const MAGIC: &[u8; 4] = b"OAMR";
+const VERSION: u16 = 1;
+
+const FLAG_LEFT_EYE: u8 = 1 << 0;
+const FLAG_RIGHT_EYE: u8 = 1 << 1;
+const FLAG_OVER_OSC: u8 = 1 << 2;
+
+/// How often the writer thread flushes the file, so that a session cut short by a crash or kill is still readable.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A single recorded frame.
+struct Frame {
+    time: f64,
+    flags: u8,
+    face_age: f32,
+    eyes_age: f32,
+    track_age: f32,
+    eyes: [Vec3; 2],
+    shapes: Vec<f32>,
+    poses: [Affine3A; 3],
+}
+
+/// The age of a timestamp in seconds, -1 for `None`.
+fn age(time: Option<Instant>) -> f32 {
+    time.map(|t| t.elapsed().as_secs_f32()).unwrap_or(-1.)
+}
+
+/// Turns an age written by `age` back into a timestamp.
+fn from_age(age: f32) -> Option<Instant> {
+    if age < 0. {
+        return None;
+    }
+    Instant::now().checked_sub(Duration::from_secs_f32(age))
+}
+
+/// A handle to the background thread that writes the session log.
+pub struct SessionRecorder {
+    sender: SyncSender<Frame>,
+    start: Instant,
+}
+
+impl SessionRecorder {
+    /// Creates (truncates) the log file, writes the header and starts the writer thread.
+    /// Returns `None` if the file can't be created.
+    pub fn new(path: &str) -> Option<Self> {
+        let mut writer = File::create(path)
+            .map(BufWriter::new)
+            .map_err(|e| log::error!("Could not create session log {}: {:?}", path, e))
+            .ok()?;
+
+        let mut header = MAGIC.to_vec();
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&(NUM_SHAPES as u16).to_le_bytes());
+        if let Err(e) = writer.write_all(&header) {
+            log::error!("Could not write session log {}: {:?}", path, e);
+            return None;
+        }
+
+        log::info!("Recording tracking session to {}", path);
+
+        let (sender, receiver) = sync_channel(256);
+        thread::spawn(move || write_loop(writer, receiver));
+
+        Some(Self {
+            sender,
+            start: Instant::now(),
+        })
+    }
+
+    /// Queues the raw tracking data of the current frame for writing.
+    /// Never blocks; if the writer falls behind, the frame is dropped.
+    pub fn record(&self, data: &UnifiedTrackingData, tracking: &OscTrack) {
+        let mut flags = 0;
+        if data.eyes[0].is_some() {
+            flags |= FLAG_LEFT_EYE;
+        }
+        if data.eyes[1].is_some() {
+            flags |= FLAG_RIGHT_EYE;
+        }
+        if tracking.received_over_osc {
+            flags |= FLAG_OVER_OSC;
+        }
+
+        let frame = Frame {
+            time: self.start.elapsed().as_secs_f64(),
+            flags,
+            face_age: age(data.last_face_received),
+            eyes_age: age(data.last_eyes_received),
+            track_age: age(tracking.last_received),
+            eyes: data.eyes.map(Option::unwrap_or_default),
+            shapes: data.shapes.to_vec(),
+            poses: [tracking.head, tracking.left_hand, tracking.right_hand],
+        };
+
+        if let Err(e) = self.sender.try_send(frame) {
+            log::debug!("Dropped session frame: {}", e);
+        }
+    }
+}
+
+/// Writes incoming frames until the recorder is dropped.
+fn write_loop(mut writer: BufWriter<File>, receiver: Receiver<Frame>) {
+    let mut next_flush = Instant::now() + FLUSH_INTERVAL;
+
+    for frame in receiver.iter() {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&frame.time.to_le_bytes());
+        buf.push(frame.flags);
+        for f in [frame.face_age, frame.eyes_age, frame.track_age]
+            .into_iter()
+            .chain(frame.eyes.iter().flat_map(|e| e.to_array()))
+            .chain(frame.shapes.iter().copied())
+            .chain(frame.poses.iter().flat_map(|p| p.to_cols_array()))
+        {
+            buf.extend_from_slice(&f.to_le_bytes());
+        }
+
+        let mut result = writer
+            .write_all(&(buf.len() as u32).to_le_bytes())
+            .and_then(|_| writer.write_all(&buf));
+        if result.is_ok() && Instant::now() >= next_flush {
+            result = writer.flush();
+            next_flush = Instant::now() + FLUSH_INTERVAL;
+        }
+        if let Err(e) = result {
+            log::error!("Could not write session log: {:?}", e);
+            return;
+        }
+    }
+    let _ = writer.flush();
+}
+
+/// Reads the header, returning the number of recorded shapes.
+fn read_header(reader: &mut impl Read) -> Result<usize, String> {
+    let mut header = [0u8; 8];
+    reader
+        .read_exact(&mut header)
+        .map_err(|e| format!("could not read header: {:?}", e))?;
+    if &header[..4] != MAGIC {
+        return Err("not a session log".into());
+    }
+
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    let num_shapes = u16::from_le_bytes([header[6], header[7]]) as usize;
+    if version != VERSION {
+        return Err(format!(
+            "unsupported version {}, expected {}",
+            version, VERSION
+        ));
+    }
+    // Shapes are stored by index, so a different count means the indices no longer line up.
+    if num_shapes != NUM_SHAPES {
+        return Err(format!(
+            "recorded with {} shapes, but this build has {}",
+            num_shapes, NUM_SHAPES
+        ));
+    }
+    Ok(num_shapes)
+}
+
+/// Reads the next frame, `None` at the end of the file.
+fn read_frame(reader: &mut impl Read, num_shapes: usize) -> Option<Frame> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len).ok()?;
+    let mut buf = vec![0u8; u32::from_le_bytes(len) as usize];
+    reader.read_exact(&mut buf).ok()?;
+
+    let floats_len = 3 + 6 + num_shapes + 36;
+    if buf.len() < 9 + floats_len * 4 {
+        log::warn!("Replay: truncated frame, stopping.");
+        return None;
+    }
+
+    let time = f64::from_le_bytes(buf[..8].try_into().ok()?);
+    let flags = buf[8];
+    let floats: Vec<f32> = buf[9..9 + floats_len * 4]
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect();
+
+    let (ages, rest) = floats.split_at(3);
+    let (eyes, rest) = rest.split_at(6);
+    let (shapes, poses) = rest.split_at(num_shapes);
+    let pose = |i: usize| Affine3A::from_cols_slice(&poses[i * 12..(i + 1) * 12]);
+
+    Some(Frame {
+        time,
+        flags,
+        face_age: ages[0],
+        eyes_age: ages[1],
+        track_age: ages[2],
+        eyes: [Vec3::from_slice(&eyes[..3]), Vec3::from_slice(&eyes[3..])],
+        shapes: shapes.to_vec(),
+        poses: [pose(0), pose(1), pose(2)],
+    })
+}
+
+/// Plays back a recorded session at its original cadence, looping at the end.
+pub struct ReplayReceiver {
+    path: String,
+    frames: Vec<Frame>,
+    /// The index of the next frame to play.
+    next: usize,
+    /// When the current loop of the playback started.
+    start: Instant,
+}
+
+impl ReplayReceiver {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            frames: Vec::new(),
+            next: 0,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl FaceReceiver for ReplayReceiver {
+    fn start_loop(&mut self) {
+        let mut reader = match File::open(&self.path) {
+            Ok(file) => BufReader::new(file),
+            Err(e) => {
+                log::error!("Could not open session log {}: {:?}", self.path, e);
+                return;
+            }
+        };
+
+        let num_shapes = match read_header(&mut reader) {
+            Ok(num_shapes) => num_shapes,
+            Err(e) => {
+                log::error!("Could not replay {}: {}", self.path, e);
+                return;
+            }
+        };
+
+        while let Some(frame) = read_frame(&mut reader, num_shapes) {
+            self.frames.push(frame);
+        }
+
+        log::info!(
+            "Replaying {} frame(s) ({:.1}s) from {}",
+            self.frames.len(),
+            self.frames.last().map(|f| f.time).unwrap_or(0.),
+            self.path
+        );
+        self.start = Instant::now();
+    }
+
+    fn receive(&mut self, data: &mut UnifiedTrackingData, state: &mut AppState) {
+        if self.frames.is_empty() {
+            return;
+        }
+
+        if self.next >= self.frames.len() {
+            log::info!("Replay: reached the end, starting over.");
+            self.next = 0;
+            self.start = Instant::now();
+        }
+
+        // Skip to the latest frame that is due.
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let mut due = None;
+        while self.next < self.frames.len() && self.frames[self.next].time <= elapsed {
+            due = Some(self.next);
+            self.next += 1;
+        }
+        let Some(idx) = due else {
+            return;
+        };
+        let frame = &self.frames[idx];
+
+        data.shapes.copy_from_slice(&frame.shapes);
+        data.eyes = [
+            (frame.flags & FLAG_LEFT_EYE != 0).then_some(frame.eyes[0]),
+            (frame.flags & FLAG_RIGHT_EYE != 0).then_some(frame.eyes[1]),
+        ];
+        data.last_face_received = from_age(frame.face_age);
+        data.last_eyes_received = from_age(frame.eyes_age);
+
+        if let Some(last_received) = from_age(frame.track_age) {
+            state.tracking.head = frame.poses[0];
+            state.tracking.left_hand = frame.poses[1];
+            state.tracking.right_hand = frame.poses[2];
+            state.tracking.last_received = Some(last_received);
+            state.tracking.received_over_osc = frame.flags & FLAG_OVER_OSC != 0;
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::f32::consts::PI;
+
+use strum::EnumCount;
+
+use super::unified::{UnifiedExpressions, UnifiedShapes};
+
+/// The cutoff frequency (in Hz) used to smooth the derivative, as recommended by the one-euro paper.
+const DERIVATIVE_CUTOFF: f32 = 1.;
+
+/// The per-shape state of the one-euro filter.
+#[derive(Clone, Copy, Default)]
+struct FilterState {
+    /// The last filtered value.
+    value: f32,
+    /// The last filtered derivative.
+    derivative: f32,
+}
+
+/// A one-euro filter over the unified shapes.
+///
+/// The filter adapts its cutoff to the speed of each shape: slow movements are smoothed heavily,
+/// which removes jitter at low tracker confidence, while fast movements pass through with little lag.
+/// See <https://gery.casiez.net/1euro/> for the details.
+pub struct OneEuroSmoothing {
+    /// The cutoff frequency (in Hz) when the shape is at rest. Lower means smoother, but laggier.
+    min_cutoff: f32,
+    /// How much the cutoff rises with speed. Higher means less lag on fast movements.
+    beta: f32,
+    /// The filter state of each unified shape, or `None` right after a reset.
+    state: Option<[FilterState; UnifiedExpressions::COUNT]>,
+}
+
+impl OneEuroSmoothing {
+    /// Creates a new `OneEuroSmoothing`. Returns `None` without a minimum cutoff, passing shapes through as-is.
+    pub fn new(min_cutoff: Option<f32>, beta: f32) -> Option<Self> {
+        let min_cutoff = min_cutoff.filter(|c| *c > 0.)?;
+
+        log::info!(
+            "Smoothing: one-euro filter, min cutoff {:.2} Hz, beta {:.3}",
+            min_cutoff,
+            beta
+        );
+
+        Some(Self {
+            min_cutoff,
+            beta,
+            state: None,
+        })
+    }
+
+    /// Forgets the filter state, so that stale velocities don't cause a jump once tracking resumes.
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+
+    /// Smooths the unified shapes in place.
+    /// `delta_t` is the time elapsed since the previous call, in seconds.
+    pub fn apply(&mut self, shapes: &mut UnifiedShapes, delta_t: f32) {
+        let Some(state) = self.state.as_mut() else {
+            // Start out from the current values, with no movement.
+            let mut state = [FilterState::default(); UnifiedExpressions::COUNT];
+            for (s, value) in state.iter_mut().zip(shapes.iter()) {
+                s.value = *value;
+            }
+            self.state = Some(state);
+            return;
+        };
+
+        if delta_t <= 0. {
+            return;
+        }
+
+        for (s, value) in state.iter_mut().zip(shapes.iter_mut()) {
+            let derivative = (*value - s.value) / delta_t;
+            s.derivative = lerp(s.derivative, derivative, alpha(DERIVATIVE_CUTOFF, delta_t));
+
+            let cutoff = self.min_cutoff + self.beta * s.derivative.abs();
+            s.value = lerp(s.value, *value, alpha(cutoff, delta_t));
+            *value = s.value;
+        }
+    }
+}
+
+/// The smoothing factor of a low-pass filter with the given cutoff frequency.
+fn alpha(cutoff: f32, delta_t: f32) -> f32 {
+    let tau = 1. / (2. * PI * cutoff);
+    1. / (1. + tau / delta_t)
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
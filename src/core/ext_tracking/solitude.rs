@@ -0,0 +1,64 @@
+use rosc::OscType;
+
+use crate::core::AvatarParameters;
+
+use super::cadence::Cadence;
+
+/// Lowers the face data send rate while nobody is around to see it.
+///
+/// The avatar tells us whether anyone is nearby through a parameter, e.g. a proximity contact
+/// or a bool set by the animator. While its value is at or below the threshold, face data is only
+/// sent at the reduced rate (or not at all). As soon as it rises above, the full rate is back.
+pub struct Solitude {
+    /// The avatar parameter that indicates whether anyone is nearby.
+    param: String,
+    /// The parameter value above which someone counts as nearby.
+    threshold: f32,
+    /// The reduced send rate while alone. `None` suppresses sending entirely.
+    cadence: Option<Cadence>,
+    /// Whether the avatar was alone on the last frame, to log transitions.
+    alone: bool,
+}
+
+impl Solitude {
+    /// Creates a new `Solitude`. Returns `None` if no parameter is configured.
+    pub fn new(param: Option<&str>, threshold: f32, hz: f32) -> Option<Self> {
+        let param = param?;
+        log::info!(
+            "Solitude: sending face data at {}Hz while {} <= {}",
+            hz.max(0.),
+            param,
+            threshold
+        );
+
+        Some(Self {
+            param: param.to_string(),
+            threshold,
+            cadence: Cadence::new(hz),
+            alone: false,
+        })
+    }
+
+    /// Returns `Some(due)` while alone, telling whether face data should be sent this frame.
+    /// Returns `None` while someone is nearby, in which case the normal send rate applies.
+    /// A missing parameter counts as someone being nearby, so avatars without it are never throttled.
+    pub fn due(&mut self, params: &AvatarParameters) -> Option<bool> {
+        let value = match params.get(self.param.as_str()) {
+            Some(OscType::Float(f)) => *f,
+            Some(OscType::Int(i)) => *i as f32,
+            Some(OscType::Bool(b)) => *b as i32 as f32,
+            _ => f32::INFINITY,
+        };
+
+        let alone = value <= self.threshold;
+        if alone != self.alone {
+            log::debug!("Solitude: {}", if alone { "alone" } else { "observed" });
+            self.alone = alone;
+        }
+
+        if !alone {
+            return None;
+        }
+        Some(self.cadence.as_mut().is_some_and(Cadence::due))
+    }
+}
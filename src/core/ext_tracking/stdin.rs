@@ -0,0 +1,134 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{stdin, BufRead},
+    str::FromStr,
+    sync::{
+        mpsc::{sync_channel, Receiver, SyncSender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use colored::{Color, Colorize};
+use once_cell::sync::Lazy;
+
+use crate::core::{status::StatusPriority, AppState, INSTRUCTIONS_END, INSTRUCTIONS_START};
+
+use super::{
+    unified::{UnifiedExpressions, UnifiedTrackingData},
+    FaceReceiver,
+};
+
+static STA_STDIN1: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "STDIN".color(Color::Green)).into());
+static STA_STDIN0: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "STDIN".color(Color::Red)).into());
+
+/// Reads face data as newline-delimited JSON from stdin, e.g. from an external script:
+///
+/// ```json
+/// {"JawOpen": 0.4, "EyeClosedLeft": 1.0}
+/// ```
+///
+/// Keys are unified expression names. Each line only updates the shapes it names, the others keep their values.
+pub(super) struct StdinReceiver {
+    sender: SyncSender<Vec<(usize, f32)>>,
+    receiver: Receiver<Vec<(usize, f32)>>,
+    last_received: Instant,
+}
+
+impl StdinReceiver {
+    pub fn new() -> Self {
+        let (sender, receiver) = sync_channel(64);
+        Self {
+            sender,
+            receiver,
+            last_received: Instant::now(),
+        }
+    }
+}
+
+impl FaceReceiver for StdinReceiver {
+    fn start_loop(&mut self) {
+        log::info!("{}", *INSTRUCTIONS_START);
+        log::info!("");
+        log::info!("Selected stdin to provide face data.");
+        log::info!("");
+        log::info!("• Write one JSON object per line, e.g. {{\"JawOpen\": 0.4}}");
+        log::info!("• Keys are unified expression names");
+        log::info!("");
+        log::info!("Status bar tickers:");
+        log::info!("• {} → face data is being received", *STA_STDIN1);
+        log::info!("");
+        log::info!("{}", *INSTRUCTIONS_END);
+
+        let sender = self.sender.clone();
+        thread::spawn(move || stdin_loop(sender));
+    }
+
+    fn receive(&mut self, data: &mut UnifiedTrackingData, state: &mut AppState) {
+        // Lines may only update some shapes, so every pending line is applied in order.
+        for values in self.receiver.try_iter() {
+            for (idx, value) in values {
+                data.shapes[idx] = value;
+            }
+            self.last_received = Instant::now();
+            data.last_face_received = Some(self.last_received);
+        }
+
+        if self.last_received.elapsed() < Duration::from_secs(1) {
+            state
+                .status
+                .add_state(STA_STDIN1.clone(), true, StatusPriority::High);
+        } else {
+            state
+                .status
+                .add_state(STA_STDIN0.clone(), false, StatusPriority::High);
+        }
+    }
+}
+
+/// Reads lines until stdin is closed.
+fn stdin_loop(sender: SyncSender<Vec<(usize, f32)>>) {
+    // Names that were already reported as unknown, so that each is only reported once.
+    let mut unknown: HashSet<String> = HashSet::new();
+
+    for line in stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                log::error!("Stdin: could not read: {:?}", e);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let entries: HashMap<String, f32> = match serde_json::from_str(&line) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("Stdin: could not parse line: {}", e);
+                continue;
+            }
+        };
+
+        let values = entries
+            .into_iter()
+            .filter_map(|(name, value)| match UnifiedExpressions::from_str(&name) {
+                Ok(e) => Some((e as usize, value)),
+                Err(_) => {
+                    if unknown.insert(name.clone()) {
+                        log::warn!("Stdin: unknown shape {}", name);
+                    }
+                    None
+                }
+            })
+            .collect();
+
+        if let Err(e) = sender.try_send(values) {
+            log::debug!("Failed to send stdin frame: {}", e);
+        }
+    }
+
+    log::info!("Stdin: reached the end of the input.");
+}
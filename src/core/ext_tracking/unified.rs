@@ -1,9 +1,13 @@
+use std::time::Instant;
+
 use glam::{Quat, Vec3};
 use rosc::{OscBundle, OscType};
 use strum::{EnumCount, EnumIter, EnumString, IntoStaticStr};
 
 use crate::core::{bundle::AvatarBundle, ext_oscjson::MysteryParam, AppState};
 
+use super::{combined::apply_combined_weights, curves::ShapeCurves};
+
 /// Represents a 3D pose with orientation (as a quaternion) and position (as a vector).
 /// Used for tracking the orientation and position of eyes.
 #[derive(Debug, Default, Clone)]
@@ -63,9 +67,48 @@ impl UnifiedShapeAccessors for UnifiedShapes {
     }
 }
 
+/// A master multiplier that exaggerates or subdues all expressions right before they are sent.
+/// Eye shapes (gaze, lids, squint) are never scaled, since that quickly looks wrong.
+#[derive(Debug, Clone, Copy)]
+pub struct ExpressionIntensity {
+    /// The multiplier; above 1.0 amplifies, below 1.0 subdues.
+    pub multiplier: f32,
+    /// Whether combined expressions are scaled as well.
+    pub combined: bool,
+}
+
+impl ExpressionIntensity {
+    /// Scales the shape at `idx`, clamping it to the valid range of its kind.
+    pub fn apply(&self, idx: usize, value: f32) -> f32 {
+        if self.multiplier == 1. {
+            return value;
+        }
+
+        let unified = (UnifiedExpressions::BrowPinchRight as usize)..UnifiedExpressions::COUNT;
+        // Eye lids and squint come before JawX, ears and blush after TongueY.
+        let combined = (CombinedExpression::JawX as usize)..=(CombinedExpression::TongueY as usize);
+
+        if unified.contains(&idx) {
+            (value * self.multiplier).clamp(0., 1.)
+        } else if self.combined && combined.contains(&idx) {
+            (value * self.multiplier).clamp(-1., 1.)
+        } else {
+            value
+        }
+    }
+}
+
 /// A type alias for a single expression shape value.
 pub type UnifiedExpressionShape = f32;
 
+/// The shapes of a neutral face: every unified shape at 0, and the combined shapes calculated from that,
+/// e.g. the eye lids at 0.75 (open). Blush is left at 0, since it isn't driven by the tracking.
+pub fn neutral_shapes() -> UnifiedShapes {
+    let mut neutral = UnifiedTrackingData::default();
+    neutral.calc_combined_shapes();
+    neutral.shapes
+}
+
 /// This struct represents the complete state of face and eye tracking at a single point in time.
 /// It holds raw eye tracking data, an array of all expression values (`shapes`), and state
 /// for managing the data flow to the OSC bundle.
@@ -81,6 +124,10 @@ pub struct UnifiedTrackingData {
     expression_tracking: bool,
     /// Flag to indicate if lip tracking is currently active and being sent.
     lip_tracking: bool,
+    /// The last time the receiver got valid face (not just eye) data.
+    pub last_face_received: Option<Instant>,
+    /// The last time the receiver got valid eye data.
+    pub last_eyes_received: Option<Instant>,
 }
 
 impl Default for UnifiedTrackingData {
@@ -92,6 +139,8 @@ impl Default for UnifiedTrackingData {
             old_shapes: None,
             expression_tracking: false,
             lip_tracking: false,
+            last_face_received: None,
+            last_eyes_received: None,
         }
     }
 }
@@ -126,6 +175,12 @@ impl UnifiedTrackingData {
     /// from separate inner and outer brow movements) from the detailed tracking data.
     /// It also handles custom logic, like the "Blush" parameter.
     pub fn calc_combined(&mut self, state: &mut AppState) {
+        self.calc_combined_shapes();
+        self.calc_blush(state);
+    }
+
+    /// Calculates the combined expressions that only depend on the unified ones, i.e. all but Blush.
+    fn calc_combined_shapes(&mut self) {
         // --- Eye Openness ---
         let left_eye_openness =
             (1. - self.getu(UnifiedExpressions::EyeClosedLeft) * 1.5).clamp(0., 1.);
@@ -427,6 +482,12 @@ impl UnifiedTrackingData {
             self.getu(UnifiedExpressions::TongueUp) - self.getu(UnifiedExpressions::TongueDown),
         );
 
+        // Apply the user's formulas last, so that they win over the built-in ones above.
+        apply_combined_weights(&mut self.shapes);
+    }
+
+    /// Fades Blush in or out, based on the blush parameters and the gaze.
+    fn calc_blush(&mut self, state: &AppState) {
         // --- Blush Logic ---
         // Determines if the blush effect should be active based on OSC parameters or eye gaze.
         let allow_blush = !matches!(state.params.get("AllowBlush"), Some(OscType::Bool(false)));
@@ -478,10 +539,15 @@ impl UnifiedTrackingData {
     /// # Arguments
     /// * `params` - An array of `MysteryParam`s which defines how each expression is named and sent.
     /// * `bundle` - The `OscBundle` to which the messages will be added.
+    /// * `splits` - The split halves of signed expressions, sent next to `params`.
+    /// * `intensity` - The master intensity that is applied to the shapes as they are sent.
     pub fn apply_to_bundle(
         &mut self,
         params: &mut [Option<MysteryParam>; NUM_SHAPES],
+        splits: &mut [SplitParam],
         bundle: &mut OscBundle,
+        intensity: &ExpressionIntensity,
+        curves: &ShapeCurves,
     ) {
         // Ensure that the game knows expression and lip tracking are active.
         if !self.expression_tracking {
@@ -497,9 +563,14 @@ impl UnifiedTrackingData {
         // Iterate through all shapes and send them if a corresponding parameter mapping exists.
         for (idx, shape) in self.shapes.iter().enumerate() {
             if let Some(param) = &mut params[idx] {
-                param.send(*shape, bundle);
+                param.send(curves.apply(idx, intensity.apply(idx, *shape)), bundle);
             }
         }
+        for split in splits.iter_mut() {
+            let idx = split.source as usize;
+            let signed = curves.apply(idx, intensity.apply(idx, self.shapes[idx]));
+            split.param.send(split.value(signed), bundle);
+        }
         // Save the current shapes for the next frame's `dirty_shapes` check.
         self.old_shapes = Some(self.shapes);
 
@@ -529,6 +600,38 @@ impl UnifiedTrackingData {
     }
 }
 
+/// The parameter names of the positive-only halves of signed combined expressions: the expression they are
+/// taken from, and whether they take its negative half.
+pub const SPLIT_PARAMS: [(&str, CombinedExpression, bool); 6] = [
+    ("SmileLeft", CombinedExpression::SmileSadLeft, false),
+    ("SadLeft", CombinedExpression::SmileSadLeft, true),
+    ("SmileRight", CombinedExpression::SmileSadRight, false),
+    ("SadRight", CombinedExpression::SmileSadRight, true),
+    ("Smile", CombinedExpression::SmileSad, false),
+    ("Sad", CombinedExpression::SmileSad, true),
+];
+
+/// A positive-only half of a signed combined expression, for avatars that have separate blendshapes
+/// for both directions instead of one slider, e.g. `SmileLeft` and `SadLeft` instead of `SmileSadLeft`.
+pub struct SplitParam {
+    /// The signed expression this half is taken from.
+    pub source: CombinedExpression,
+    /// Whether this is the negative half, sent as a positive value.
+    pub negative: bool,
+    pub param: MysteryParam,
+}
+
+impl SplitParam {
+    /// The half of the signed value that this parameter receives, 0 for the other half.
+    pub fn value(&self, signed: f32) -> f32 {
+        if self.negative {
+            (-signed).max(0.)
+        } else {
+            signed.max(0.)
+        }
+    }
+}
+
 /// The total number of expression shapes, which is the sum of all `UnifiedExpressions` and `CombinedExpression` variants.
 pub const NUM_SHAPES: usize = UnifiedExpressions::COUNT + CombinedExpression::COUNT;
 
@@ -670,65 +773,121 @@ pub enum UnifiedExpressions {
 /// These are calculated in the `calc_combined` function from the raw `UnifiedExpressions`.
 /// They provide more game-friendly or abstract controls that are easier for avatar creators
 /// to work with (e.g., a single `BrowUp` instead of separate inner/outer/left/right brow movements).
+/// Each variant lists its built-in formula, which `--combined-weights` can replace.
 #[allow(unused)]
 #[repr(usize)]
 #[derive(Debug, Clone, Copy, EnumIter, EnumCount, EnumString, IntoStaticStr)]
 pub enum CombinedExpression {
+    /// Openness (1 - EyeClosedLeft × 1.5, clamped) × 0.75, plus EyeWideLeft × openness × 0.25.
     EyeLidLeft = UnifiedExpressions::COUNT,
+    /// Openness (1 - EyeClosedRight × 1.5, clamped) × 0.75, plus EyeWideRight × openness × 0.25.
     EyeLidRight,
+    /// Average of EyeLidLeft and EyeLidRight.
     EyeLid,
+    /// Average of EyeSquintLeft and EyeSquintRight.
     EyeSquint,
+    /// JawRight - JawLeft.
     JawX,
+    /// JawForward - JawBackward.
     JawZ,
+    /// BrowLowererLeft × 0.75 + BrowPinchLeft × 0.25.
     BrowDownLeft,
+    /// BrowLowererRight × 0.75 + BrowPinchRight × 0.25.
     BrowDownRight,
+    /// Average of BrowOuterUpLeft and BrowOuterUpRight.
     BrowOuterUp,
+    /// Average of BrowInnerUpLeft and BrowInnerUpRight.
     BrowInnerUp,
+    /// Average of BrowOuterUp and BrowInnerUp, i.e. of all four brow raisers.
     BrowUp,
+    /// (BrowInnerUpLeft + BrowOuterUpLeft) × 0.5 - BrowDownLeft.
     BrowExpressionLeft,
+    /// (BrowInnerUpRight + BrowOuterUpRight) × 0.5 - BrowDownRight.
     BrowExpressionRight,
+    /// Average of BrowExpressionLeft and BrowExpressionRight.
     BrowExpression,
+    /// (MouthUpperRight + MouthLowerRight - MouthUpperLeft - MouthLowerLeft) × 0.5.
     MouthX,
+    /// MouthUpperRight - MouthUpperLeft.
     MouthUpperX,
+    /// MouthLowerRight - MouthLowerLeft.
     MouthLowerX,
+    /// Average of MouthUpperUpLeft and MouthUpperUpRight.
     MouthUpperUp,
+    /// Average of MouthLowerDownLeft and MouthLowerDownRight.
     MouthLowerDown,
+    /// Average of MouthUpperUp and MouthLowerDown.
     MouthOpen,
+    /// MouthCornerPullLeft × 0.75 + MouthCornerSlantLeft × 0.25.
     MouthSmileLeft,
+    /// MouthCornerPullRight × 0.75 + MouthCornerSlantRight × 0.25.
     MouthSmileRight,
+    /// MouthFrownLeft × 0.75 + MouthStretchLeft × 0.25.
     MouthSadLeft,
+    /// MouthFrownRight × 0.75 + MouthStretchRight × 0.25.
     MouthSadRight,
+    /// MouthStretchLeft - MouthTightenerLeft.
     MouthStretchTightenLeft,
+    /// MouthStretchRight - MouthTightenerRight.
     MouthStretchTightenRight,
+    /// Average of MouthStretchLeft and MouthStretchRight.
     MouthStretch,
+    /// Average of MouthTightenerLeft and MouthTightenerRight.
     MouthTightener,
+    /// Average of MouthDimpleLeft and MouthDimpleRight.
     MouthDimple,
+    /// Average of MouthPressLeft and MouthPressRight.
     MouthPress,
+    /// MouthSmileLeft - MouthFrownLeft.
     SmileFrownLeft,
+    /// MouthSmileRight - MouthFrownRight.
     SmileFrownRight,
+    /// Average of SmileFrownLeft and SmileFrownRight.
     SmileFrown,
+    /// MouthSmileLeft - MouthSadLeft.
     SmileSadLeft,
+    /// MouthSmileRight - MouthSadRight.
     SmileSadRight,
+    /// Average of SmileSadLeft and SmileSadRight.
     SmileSad,
+    /// Average of LipSuckUpperLeft and LipSuckUpperRight.
     LipSuckUpper,
+    /// Average of LipSuckLowerLeft and LipSuckLowerRight.
     LipSuckLower,
+    /// Average of all four LipSuck shapes.
     LipSuck,
+    /// Average of LipFunnelUpperLeft and LipFunnelUpperRight.
     LipFunnelUpper,
+    /// Average of LipFunnelLowerLeft and LipFunnelLowerRight.
     LipFunnelLower,
+    /// Average of LipFunnelUpper and LipFunnelLower.
     LipFunnel,
+    /// Not computed, 0 unless given combined weights.
     LipPuckerUpper,
+    /// Not computed, 0 unless given combined weights.
     LipPuckerLower,
+    /// Average of all four LipPucker shapes.
     LipPucker,
+    /// Average of NoseSneerLeft and NoseSneerRight.
     NoseSneer,
+    /// CheekPuffLeft - CheekSuckLeft.
     CheekPuffSuckLeft,
+    /// CheekPuffRight - CheekSuckRight.
     CheekPuffSuckRight,
+    /// Average of CheekPuffSuckLeft and CheekPuffSuckRight.
     CheekPuffSuck,
+    /// Average of CheekSquintLeft and CheekSquintRight.
     CheekSquint,
+    /// TongueRight - TongueLeft.
     TongueX,
+    /// TongueUp - TongueDown.
     TongueY,
 
     // Non-standard
+    /// BrowInnerUpLeft + EyeWideLeft - EyeSquintLeft - BrowPinchLeft, clamped to -1..1.
     EarLeft,
+    /// BrowInnerUpLeft + EyeWideRight - EyeSquintRight - BrowPinchRight, clamped to -1..1.
     EarRight,
+    /// Fades in while the AllowBlush/BlushFace/BlushNade parameters or the gaze say so; not shape driven.
     Blush,
 }
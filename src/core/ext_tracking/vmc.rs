@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{sync_channel, Receiver, SyncSender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use colored::{Color, Colorize};
+use once_cell::sync::Lazy;
+use rosc::{OscMessage, OscPacket, OscType};
+use strum::EnumCount;
+
+use crate::core::{
+    ext_tracking::unified::{UnifiedExpressions, UnifiedShapes},
+    status::StatusPriority,
+    AppState, INSTRUCTIONS_END, INSTRUCTIONS_START,
+};
+
+use super::{arkit::arkit_to_unified, unified::UnifiedTrackingData, FaceReceiver};
+
+static STA_VMC1: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "VMC".color(Color::Green)).into());
+static STA_VMC0: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "VMC".color(Color::Red)).into());
+static STA_NOPORT: Lazy<Arc<str>> =
+    Lazy::new(|| format!("{}", "VMC:PORT".color(Color::BrightRed)).into());
+
+/// Receives ARKit-style blendshapes over the VMC protocol,
+/// e.g. from iFacialMocap, VSeeFace or other "perfect sync" senders.
+pub(super) struct VmcReceiver {
+    listen_port: u16,
+    sender: SyncSender<Box<UnifiedShapes>>,
+    receiver: Receiver<Box<UnifiedShapes>>,
+    last_received: Instant,
+    /// Whether the listen port is currently bound. False while retrying after a failed bind.
+    listening: Arc<AtomicBool>,
+}
+
+impl VmcReceiver {
+    pub fn new(listen_port: u16) -> Self {
+        let (sender, receiver) = sync_channel(8);
+        Self {
+            listen_port,
+            sender,
+            receiver,
+            last_received: Instant::now(),
+            listening: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl FaceReceiver for VmcReceiver {
+    fn start_loop(&mut self) {
+        let sender = self.sender.clone();
+        let listen_port = self.listen_port;
+
+        log::info!("{}", *INSTRUCTIONS_START);
+        log::info!("");
+        log::info!("Selected VMC protocol to provide face data.");
+        log::info!("");
+        log::info!(
+            "• Point your VMC sender to this machine, port {}",
+            format!("{}", listen_port).color(Color::Cyan),
+        );
+        log::info!("• Enable sending of ARKit / perfect sync blendshapes");
+        log::info!("");
+        log::info!("Status bar tickers:");
+        log::info!("• {} → face data is being received", *STA_VMC1);
+        log::info!("• {} → the listen port could not be bound", *STA_NOPORT);
+        log::info!("");
+        log::info!("{}", *INSTRUCTIONS_END);
+
+        let listening = self.listening.clone();
+        thread::spawn(move || vmc_loop(listen_port, sender, listening));
+    }
+
+    fn receive(&mut self, data: &mut UnifiedTrackingData, state: &mut AppState) {
+        // Only the latest frame matters.
+        if let Some(shapes) = self.receiver.try_iter().last() {
+            data.shapes[..UnifiedExpressions::COUNT]
+                .copy_from_slice(&shapes[..UnifiedExpressions::COUNT]);
+            self.last_received = Instant::now();
+            data.last_face_received = Some(self.last_received);
+        }
+
+        if !self.listening.load(Ordering::Relaxed) {
+            state
+                .status
+                .add_item_priority(STA_NOPORT.clone(), StatusPriority::High);
+        }
+
+        if self.last_received.elapsed() < Duration::from_secs(1) {
+            state
+                .status
+                .add_state(STA_VMC1.clone(), true, StatusPriority::High);
+        } else {
+            state
+                .status
+                .add_state(STA_VMC0.clone(), false, StatusPriority::High);
+        }
+    }
+}
+
+fn vmc_loop(listen_port: u16, sender: SyncSender<Box<UnifiedShapes>>, listening: Arc<AtomicBool>) {
+    loop {
+        if let Some(()) = receive_vmc_osc(listen_port, &sender, &listening) {
+            break;
+        } else {
+            thread::sleep(Duration::from_millis(5000));
+        }
+    }
+}
+
+fn receive_vmc_osc(
+    listen_port: u16,
+    sender: &SyncSender<Box<UnifiedShapes>>,
+    listening: &AtomicBool,
+) -> Option<()> {
+    // VMC senders often run on another device (e.g. a phone), so listen on all interfaces.
+    let ip = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+    // Don't crash if the port is taken; vmc_loop will retry in a few seconds.
+    let listener = match UdpSocket::bind(SocketAddr::new(ip, listen_port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!(
+                "VMC port {} is in use — is another tracker running? ({}) Retrying in 5s.",
+                listen_port,
+                e
+            );
+            listening.store(false, Ordering::Relaxed);
+            return None;
+        }
+    };
+    listening.store(true, Ordering::Relaxed);
+
+    // Blendshape values by lowercase name. VMC sends them one by one, then an Apply to commit the frame.
+    let mut values: HashMap<String, f32> = HashMap::new();
+    let mut buf = [0u8; rosc::decoder::MTU];
+    loop {
+        if let Ok((size, _addr)) = listener.recv_from(&mut buf) {
+            if let Ok((_, packet)) = rosc::decoder::decode_udp(&buf[..size]) {
+                handle_packet(packet, &mut values, sender);
+            }
+        }
+    }
+}
+
+/// Walks a packet, recursing into bundles.
+fn handle_packet(
+    packet: OscPacket,
+    values: &mut HashMap<String, f32>,
+    sender: &SyncSender<Box<UnifiedShapes>>,
+) {
+    match packet {
+        OscPacket::Message(msg) => handle_message(msg, values, sender),
+        OscPacket::Bundle(bundle) => {
+            for packet in bundle.content {
+                handle_packet(packet, values, sender);
+            }
+        }
+    }
+}
+
+fn handle_message(
+    msg: OscMessage,
+    values: &mut HashMap<String, f32>,
+    sender: &SyncSender<Box<UnifiedShapes>>,
+) {
+    match msg.addr.as_str() {
+        "/VMC/Ext/Blend/Val" => match msg.args.as_slice() {
+            [OscType::String(name), OscType::Float(value)] => {
+                values.insert(name.to_ascii_lowercase(), *value);
+            }
+            args => log::warn!("VMC OSC: Unsupported blend args {:?}", args),
+        },
+        "/VMC/Ext/Blend/Apply" => {
+            let shapes = Box::new(arkit_to_unified(values));
+            if let Err(e) = sender.try_send(shapes) {
+                log::debug!("Failed to send VMC frame: {}", e);
+            }
+        }
+        _ => {}
+    }
+}
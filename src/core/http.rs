@@ -0,0 +1,70 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+/// Answers the requests on `listener` on a background thread, for the small read-only servers
+/// (metrics, OSCQuery). Only `GET` is supported, and every connection is closed after one response.
+///
+/// `handler` is called with the path and query of each request, and returns the body of the response,
+/// or `None` for a 404. It is sent as `content_type`. `name` prefixes the logged errors.
+pub fn serve<F>(listener: TcpListener, name: &'static str, content_type: &'static str, handler: F)
+where
+    F: Fn(&str, &str) -> Option<String> + Send + 'static,
+{
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Err(e) = handle_request(stream, content_type, &handler) {
+                log::debug!("{}: request failed: {:?}", name, e);
+            }
+        }
+    });
+}
+
+/// Answers a single HTTP request and closes the connection.
+fn handle_request(
+    mut stream: TcpStream,
+    content_type: &str,
+    handler: &impl Fn(&str, &str) -> Option<String>,
+) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+
+    let mut buf = [0u8; 4096];
+    let size = stream.read(&mut buf)?;
+    let request = String::from_utf8_lossy(&buf[..size]);
+
+    // e.g. "GET /avatar/parameters?HOST_INFO HTTP/1.1"
+    let target = match request
+        .lines()
+        .next()
+        .map(|l| l.split(' ').collect::<Vec<_>>())
+    {
+        Some(parts) if parts.len() >= 2 && parts[0] == "GET" => parts[1].to_string(),
+        _ => return respond(&mut stream, "405 Method Not Allowed", content_type, ""),
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target.as_str(), ""));
+
+    match handler(path, query) {
+        Some(body) => respond(&mut stream, "200 OK", content_type, &body),
+        None => respond(&mut stream, "404 Not Found", content_type, ""),
+    }
+}
+
+/// Writes a response with the given status and body.
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
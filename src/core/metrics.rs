@@ -0,0 +1,95 @@
+use std::{
+    fmt::Write as _,
+    net::{IpAddr, TcpListener},
+    sync::{Arc, Mutex},
+};
+
+use super::http;
+
+/// A snapshot of the values shown on the status line.
+#[derive(Clone, Copy, Default)]
+pub struct Metrics {
+    /// Ticks per second of the main loop.
+    pub fps: f32,
+    /// OSC packets sent per second.
+    pub send_rate: f32,
+    /// OSC packets received per second.
+    pub recv_rate: f32,
+    /// Whether face data is being received (`FACE`).
+    pub face: bool,
+    /// Whether eye data is being received (`GAZE`).
+    pub gaze: bool,
+    /// Whether head & wrist data is being received (`TRACK`).
+    pub track: bool,
+}
+
+/// A minimal HTTP server that exposes the status line values at `/metrics`,
+/// in the Prometheus text exposition format, for monitoring headless setups.
+pub struct MetricsServer {
+    /// The latest snapshot, replaced on every frame.
+    metrics: Arc<Mutex<Metrics>>,
+}
+
+impl MetricsServer {
+    /// Starts the HTTP server on `bind:port`. Returns `None` if the port can't be bound.
+    pub fn new(bind: IpAddr, port: u16) -> Option<Self> {
+        let listener = TcpListener::bind((bind, port))
+            .map_err(|e| log::error!("Metrics: could not bind port {}: {:?}", port, e))
+            .ok()?;
+
+        let metrics = Arc::new(Mutex::new(Metrics::default()));
+
+        http::serve(listener, "Metrics", "text/plain; version=0.0.4", {
+            let metrics = metrics.clone();
+            move |path, _| (path == "/metrics").then(|| render(&metrics.lock().unwrap()))
+        });
+
+        log::info!("Metrics: serving on http://{}:{}/metrics", bind, port);
+        Some(Self { metrics })
+    }
+
+    /// Replaces the published snapshot.
+    pub fn update(&self, metrics: Metrics) {
+        if let Ok(mut current) = self.metrics.lock() {
+            *current = metrics;
+        }
+    }
+}
+
+/// Formats the snapshot in the Prometheus text exposition format.
+fn render(metrics: &Metrics) -> String {
+    let mut out = String::new();
+    let mut gauge = |name: &str, help: &str, value: f32| {
+        let _ = writeln!(out, "# HELP oscavmgr_{} {}", name, help);
+        let _ = writeln!(out, "# TYPE oscavmgr_{} gauge", name);
+        let _ = writeln!(out, "oscavmgr_{} {}", name, value);
+    };
+
+    gauge("fps", "Ticks per second of the main loop.", metrics.fps);
+    gauge(
+        "sent_per_second",
+        "OSC packets sent per second.",
+        metrics.send_rate,
+    );
+    gauge(
+        "recv_per_second",
+        "OSC packets received per second.",
+        metrics.recv_rate,
+    );
+    gauge(
+        "face_present",
+        "Whether face data is being received.",
+        metrics.face as u8 as f32,
+    );
+    gauge(
+        "gaze_present",
+        "Whether eye data is being received.",
+        metrics.gaze as u8 as f32,
+    );
+    gauge(
+        "track_present",
+        "Whether head and wrist data is being received.",
+        metrics.track as u8 as f32,
+    );
+    out
+}
@@ -1,13 +1,15 @@
+use anyhow::{anyhow, Context};
 use colored::{Color, Colorize};
-use ext_oscjson::AvatarIdentifier;
+use ext_oscjson::{AvatarIdentifier, AvatarLoad, OscJsonNode};
 use glam::{Affine3A, Quat, Vec3};
-use indicatif::MultiProgress;
+use indicatif::{MultiProgress, ProgressDrawTarget};
 use log::info;
 use once_cell::sync::Lazy;
-use rosc::{OscBundle, OscPacket, OscType};
+use rosc::{OscBundle, OscMessage, OscPacket, OscType};
 use std::{
     collections::HashMap,
-    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -16,29 +18,46 @@ use std::{
     time::{Duration, Instant},
 };
 
-use crate::Args;
+use crate::{Args, StatusFormat, WatchdogAction};
 
-use self::bundle::AvatarBundle;
+use self::{bundle::AvatarBundle, status::StatusPriority};
 
 // Module declarations for the different components of the application core.
+mod avatar_watch; // Reloads the avatar file when it changes.
 mod bundle; // Handles OSC bundle creation.
 mod ext_autopilot; // Manages autonomous avatar behaviors.
+mod ext_chatbox; // Shows the tracking state in the chatbox.
 mod ext_gogo; // Implements "GoGo Loco" style movement adjustments.
 mod ext_oscjson; // Handles OSC/JSON configuration for avatars.
+mod ext_pulse; // Pulses parameters when expressions cross a threshold.
 mod ext_storage; // Manages persistent parameter storage.
 mod ext_tracking; // Processes and forwards face and body tracking data.
-mod folders; // Manages application-related folders.
+pub(crate) mod folders; // Manages application-related folders.
+mod http; // A minimal HTTP responder for the metrics and OSCQuery servers.
+mod metrics; // Serves the status line values in the Prometheus format.
+mod oscquery; // Publishes the sent parameters over OSCQuery.
+mod param_jitter; // Smooths out bursty parameter updates.
+mod strict_types; // Enforces the avatar's declared parameter types.
+mod tracker_filter; // Suppresses redundant tracker updates.
+mod tracker_forward; // Forwards the receiver's head and wrist poses as trackers.
+mod transport; // Sends packets upstream over UDP or TCP.
 mod watchdog; // A watchdog to ensure the application remains responsive.
 
 // Public module for status bar management.
 pub mod status;
+// Public module for the `--tui` dashboard, its logger is installed by `main`.
+pub mod tui;
 
 // OSC address prefixes used for routing messages.
 pub const PARAM_PREFIX: &str = "/avatar/parameters/";
 const AVATAR_PREFIX: &str = "/avatar/change";
 const TRACK_PREFIX: &str = "/tracking/trackers/";
+const EYE_PREFIX: &str = "/tracking/eye/";
 const INPUT_PREFIX: &str = "/input/";
 
+/// How often a failing upstream target is warned about, since every frame would fail the same way.
+const SEND_WARN_INTERVAL: Duration = Duration::from_secs(10);
+
 /// A type alias for a HashMap storing avatar parameters, mapping parameter names to OSC types.
 pub type AvatarParameters = HashMap<Arc<str>, OscType>;
 
@@ -62,23 +81,216 @@ pub struct AppState {
 /// It manages OSC communication, extensions, and the main application loop.
 pub struct AvatarOsc {
     osc_port: u16,
-    upstream: UdpSocket,
+    /// The address the OSC listener is bound to.
+    bind: IpAddr,
+    /// One socket per game that everything is sent to.
+    upstream: Vec<Box<dyn transport::Upstream>>,
+    /// When each failing upstream target was last warned about.
+    send_warned: HashMap<SocketAddr, Instant>,
+    /// A read-only tap that receives a copy of everything sent upstream, e.g. for a local visualizer.
+    monitor: Option<UdpSocket>,
+    /// Log outgoing packets instead of sending them upstream.
+    dry_run: bool,
+    /// Serves the status line values for scraping, if enabled.
+    metrics: Option<metrics::MetricsServer>,
     ext_autopilot: ext_autopilot::ExtAutoPilot,
     ext_oscjson: ext_oscjson::ExtOscJson,
     ext_storage: ext_storage::ExtStorage,
     ext_gogo: ext_gogo::ExtGogo,
     ext_tracking: ext_tracking::ExtTracking,
+    ext_pulse: ext_pulse::ExtPulse,
+    /// Shows the tracking state in the chatbox, if enabled.
+    ext_chatbox: Option<ext_chatbox::ExtChatbox>,
+    tracker_filter: tracker_filter::TrackerFilter,
+    /// Holds back incoming parameter updates to smooth out bursts, if enabled.
+    param_jitter: Option<param_jitter::ParamJitter>,
+    /// Coerces outgoing parameters to their declared types, if enabled.
+    strict_types: Option<strict_types::StrictTypes>,
+    /// Whether to forward the receiver's head and wrist poses as trackers.
+    forward_trackers: bool,
+    /// How much of the previous smoothed head pose is kept each frame.
+    head_smoothing: f32,
+    /// Whether to send the `TrackingActive` parameter.
+    emit_tracking_active: bool,
+    /// The last sent value of `TrackingActive`, `None` if it needs to be (re)sent.
+    tracking_active: Option<bool>,
+    /// Whether all tracking is paused by the `OscAvMgrPause` parameter.
+    paused: bool,
+    /// The last received value of `VSync`, to skip frames that were received twice.
+    last_vsync: Option<OscType>,
     multi: MultiProgress,
     avatar_file: Option<String>,
+    /// Watches the `--avatar` file, so that it's reloaded when edited.
+    avatar_watch: Option<avatar_watch::AvatarWatch>,
+    /// An avatar UID to load from the OSC JSON service on the first frame.
+    avatar_uid: Option<String>,
+    /// The names under which incoming trackers are recognized.
+    tracker_aliases: TrackerAliases,
+    /// The upper bound for `delta_t` as seen by the extensions, 0 for unlimited.
+    max_delta_t: f32,
+    /// The maximum visible width of the status line, 0 for unlimited.
+    status_max_width: usize,
+    /// The maximum number of items on the status line, 0 for unlimited.
+    status_max_items: usize,
+    /// Whether the status line is drawn as a spinner or printed as JSON.
+    status_format: StatusFormat,
+    /// The full-screen dashboard, which replaces the status line if enabled.
+    tui: Option<tui::Tui>,
+    /// How long the main loop may stall before the watchdog acts.
+    watchdog_timeout_ms: u64,
+    /// What the watchdog does when the main loop stalls.
+    watchdog_action: WatchdogAction,
+}
+
+/// The tracker names (after `TRACK_PREFIX`) that map to the head and wrist slots of `OscTrack`.
+/// VRChat uses `head`, `leftwrist` and `rightwrist`, but other senders may use e.g. `hmd` or `left_wrist`.
+struct TrackerAliases {
+    head: Vec<String>,
+    left_wrist: Vec<String>,
+    right_wrist: Vec<String>,
+}
+
+impl TrackerAliases {
+    /// Checks if the tracker name starts with any of the given aliases.
+    fn matches(aliases: &[String], tracker: &str) -> bool {
+        aliases
+            .iter()
+            .any(|alias| tracker.starts_with(alias.as_str()))
+    }
+}
+
+/// Prints the OpenXR face tracking extensions and capabilities of the runtime, for `--list-extensions`.
+#[cfg(feature = "openxr")]
+pub fn list_openxr_extensions() -> anyhow::Result<()> {
+    ext_tracking::list_extensions()
+}
+
+/// Pairs up the `--vrc-host` and `--vrc-port` lists into the addresses to send to.
+/// The shorter list is padded with its last entry, so that e.g. one host with two ports sends to both ports.
+fn upstream_addrs(hosts: &[String], ports: &[u16]) -> Vec<SocketAddr> {
+    let hosts: Vec<IpAddr> = hosts
+        .iter()
+        .map(|host| resolve_host(host, "--vrc-host"))
+        .collect();
+    let count = hosts.len().max(ports.len());
+    (0..count)
+        .filter_map(|i| {
+            let host = hosts.get(i).or(hosts.last())?;
+            let port = ports.get(i).or(ports.last())?;
+            Some(SocketAddr::new(*host, *port))
+        })
+        .collect()
+}
+
+/// Resolves an IPv4/IPv6 address or host name given on the command line,
+/// exiting with a clear error if it can't be resolved. Host names use the first resolved address.
+fn resolve_host(value: &str, arg: &str) -> IpAddr {
+    // IPv6 addresses may be given in brackets, as in URLs.
+    let host = value.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = host.parse() {
+        return ip;
+    }
+
+    match (host, 0).to_socket_addrs().map(|mut addrs| addrs.next()) {
+        Ok(Some(addr)) => {
+            info!("Resolved {} {} to {}", arg, value, addr.ip());
+            addr.ip()
+        }
+        Ok(None) => {
+            log::error!("{} {} did not resolve to any address", arg, value);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            log::error!(
+                "{} must be an IP address or host name, got {}: {}",
+                arg,
+                value,
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The wildcard address of the same family as `ip`, for binding a socket that talks to it.
+fn unspecified_for(ip: IpAddr) -> SocketAddr {
+    match ip {
+        IpAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+        IpAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+    }
+}
+
+/// Whether a switch parameter such as `FaceFreeze` or `OscAvMgrPause` is set.
+/// Avatars drive these as bools or ints, but animators can only drive floats, so those count as set from 0.5 on.
+fn is_truthy(value: &OscType) -> bool {
+    match value {
+        OscType::Bool(b) => *b,
+        OscType::Int(i) => *i != 0,
+        OscType::Float(f) => *f >= 0.5,
+        _ => false,
+    }
+}
+
+/// Logs the contents of an outgoing packet for `--dry-run`, one message per line.
+fn log_packet(packet: &OscPacket, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match packet {
+        OscPacket::Message(msg) => {
+            let args = msg
+                .args
+                .iter()
+                .map(|arg| match arg {
+                    OscType::Float(f) => format!("{:.4}", f),
+                    OscType::Int(i) => format!("{}", i),
+                    OscType::Bool(b) => format!("{}", b),
+                    OscType::String(s) => format!("{:?}", s),
+                    arg => format!("{:?}", arg),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            info!("Dry run: {}{} {}", indent, msg.addr, args);
+        }
+        OscPacket::Bundle(bundle) => {
+            info!(
+                "Dry run: {}bundle ({} item(s))",
+                indent,
+                bundle.content.len()
+            );
+            for packet in bundle.content.iter() {
+                log_packet(packet, depth + 1);
+            }
+        }
+    }
 }
 
 /// Holds OSC tracking data for the head and hands.
 pub struct OscTrack {
     pub head: Affine3A,
+    /// The head with `--head-smoothing` applied, for features that want a steady head pose.
+    pub head_smoothed: Affine3A,
     pub left_hand: Affine3A,
     pub right_hand: Affine3A,
-    /// The timestamp of the last received tracking data.
-    pub last_received: Instant,
+    /// The timestamp of the last received tracking data, `None` if none was received yet.
+    pub last_received: Option<Instant>,
+    /// Whether the head was last received over OSC rather than from the face receiver.
+    pub received_over_osc: bool,
+}
+
+impl OscTrack {
+    /// Moves `head_smoothed` towards the raw `head`: exponentially for the translation,
+    /// and with a slerp for the rotation. `factor` is how much of the previous pose is kept each frame (0 = raw).
+    pub fn smooth_head(&mut self, factor: f32) {
+        if factor <= 0. {
+            self.head_smoothed = self.head;
+            return;
+        }
+
+        let (_, raw_rot, raw_pos) = self.head.to_scale_rotation_translation();
+        let (_, rot, pos) = self.head_smoothed.to_scale_rotation_translation();
+        let t = 1. - factor;
+        self.head_smoothed =
+            Affine3A::from_rotation_translation(rot.slerp(raw_rot, t), pos.lerp(raw_pos, t));
+    }
 }
 
 impl AvatarOsc {
@@ -91,77 +303,223 @@ impl AvatarOsc {
     pub fn new(args: Args, multi: MultiProgress) -> AvatarOsc {
         let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
 
-        // Set up the UDP socket to send OSC messages to the game (e.g., VRChat).
-        let upstream = UdpSocket::bind("0.0.0.0:0").expect("bind upstream socket");
-        upstream
-            .connect(SocketAddr::new(ip, args.vrc_port))
-            .expect("upstream connect");
+        bundle::set_haptic_address(&args.haptic_address);
+
+        // Validate the configured addresses before anything is started.
+        let vrc_addrs = upstream_addrs(&args.vrc_host, &args.vrc_port);
+        let bind = resolve_host(&args.bind, "--bind");
+
+        // Set up a connection per game (e.g., VRChat) to send OSC messages to.
+        let upstream = vrc_addrs
+            .into_iter()
+            .map(|vrc_addr| {
+                transport::connect(
+                    vrc_addr,
+                    unspecified_for(vrc_addr.ip()),
+                    args.upstream_transport,
+                )
+                .unwrap_or_else(|e| {
+                    log::error!("Could not connect to VRChat at {}: {}", vrc_addr, e);
+                    std::process::exit(1);
+                })
+            })
+            .collect();
+
+        // Set up the optional monitor socket. Unlike upstream, failing to set it up is not fatal.
+        let monitor = args.monitor_port.and_then(|port| {
+            UdpSocket::bind("0.0.0.0:0")
+                .and_then(|socket| socket.connect(SocketAddr::new(ip, port)).map(|_| socket))
+                .map_err(|e| log::error!("Could not set up monitor port {}: {:?}", port, e))
+                .ok()
+        });
 
         // Initialize all the extensions.
-        let ext_autopilot = ext_autopilot::ExtAutoPilot::new();
+        let ext_autopilot = ext_autopilot::ExtAutoPilot::new(&args);
         let ext_storage = ext_storage::ExtStorage::new();
         let ext_gogo = ext_gogo::ExtGogo::new();
-        let ext_tracking = ext_tracking::ExtTracking::new(args.face);
-        let ext_oscjson = ext_oscjson::ExtOscJson::new();
+        let mut ext_tracking = ext_tracking::ExtTracking::new(&args);
+        let ext_oscjson = ext_oscjson::ExtOscJson::new(&args);
+        let ext_pulse = ext_pulse::ExtPulse::new(&args);
+
+        // Start out with the last avatar's mapping rather than the defaults, until the current avatar is known.
+        if !args.no_cache && args.avatar.is_none() {
+            if let Some(root_node) = ext_oscjson.cached_avatar() {
+                ext_tracking.osc_json(&root_node);
+            }
+        }
+        ext_oscjson.publish(&ext_tracking.param_addresses());
+        let tracker_filter = tracker_filter::TrackerFilter::new(&args);
+
+        if args.avatar_uid.is_some() && args.oscjson_url.is_none() {
+            log::warn!("--avatar-uid has no effect until an OSCJSON service is found, consider --oscjson-url.");
+        }
+
+        // The dashboard takes over the terminal, so the spinner and JSON status lines must stay out of its way.
+        let tui = args.tui.then(tui::Tui::new).flatten();
+        let status_format = if tui.is_some() {
+            multi.set_draw_target(ProgressDrawTarget::hidden());
+            StatusFormat::Pretty
+        } else {
+            args.status_format
+        };
 
         AvatarOsc {
             osc_port: args.osc_port,
+            bind,
             upstream,
+            send_warned: HashMap::new(),
+            monitor,
+            dry_run: args.dry_run,
+            metrics: args
+                .metrics_port
+                .and_then(|port| metrics::MetricsServer::new(bind, port)),
             ext_autopilot,
             ext_oscjson,
             ext_storage,
             ext_gogo,
             ext_tracking,
+            ext_pulse,
+            ext_chatbox: ext_chatbox::ExtChatbox::new(&args),
+            tracker_filter,
+            param_jitter: param_jitter::ParamJitter::new(args.param_jitter_ms),
+            strict_types: args.strict_types.then(strict_types::StrictTypes::default),
+            forward_trackers: args.forward_trackers,
+            head_smoothing: args.head_smoothing.clamp(0., 0.99),
+            emit_tracking_active: args.emit_tracking_active,
+            tracking_active: None,
+            paused: false,
+            last_vsync: None,
             multi,
+            avatar_watch: args.avatar.clone().map(avatar_watch::AvatarWatch::new),
             avatar_file: args.avatar,
+            avatar_uid: args.avatar_uid,
+            max_delta_t: args.max_delta_t,
+            status_max_width: args.status_max_width,
+            status_max_items: args.status_max_items,
+            status_format,
+            tui,
+            watchdog_timeout_ms: args.watchdog_timeout_ms,
+            watchdog_action: args.watchdog_action,
+            tracker_aliases: TrackerAliases {
+                head: args.head_tracker,
+                left_wrist: args.left_wrist_tracker,
+                right_wrist: args.right_wrist_tracker,
+            },
         }
     }
 
-    /// Sends a buffer of data to the upstream OSC endpoint (the game).
-    pub fn send_upstream(&self, buf: &[u8]) -> std::io::Result<usize> {
-        self.upstream.send(buf)
+    /// Sends a buffer of data to the upstream OSC endpoints (the games).
+    /// A failure to send to one of them is logged (at most every `SEND_WARN_INTERVAL` per target),
+    /// but doesn't stop the others; an error is only returned if every send failed.
+    /// A copy is also sent to the monitor port, if set. Monitor failures never affect the upstream send.
+    /// In dry-run mode, the decoded packet is logged instead and nothing is sent upstream.
+    pub fn send_upstream(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let result = if self.dry_run {
+            match rosc::decoder::decode_udp(buf) {
+                Ok((_, packet)) => log_packet(&packet, 0),
+                Err(e) => log::warn!("Dry run: could not decode outgoing packet: {:?}", e),
+            }
+            Ok(buf.len())
+        } else {
+            let mut result = Err(std::io::ErrorKind::NotConnected.into());
+            for upstream in self.upstream.iter() {
+                match upstream.send(buf) {
+                    Ok(size) => result = Ok(size),
+                    Err(e) => {
+                        let addr = upstream.peer_addr();
+                        let due = self
+                            .send_warned
+                            .get(&addr)
+                            .is_none_or(|t| t.elapsed() >= SEND_WARN_INTERVAL);
+                        if due {
+                            log::warn!("Could not send to {}: {}", addr, e);
+                            self.send_warned.insert(addr, Instant::now());
+                        }
+                        if result.is_err() {
+                            result = Err(e);
+                        }
+                    }
+                }
+            }
+            result
+        };
+        if let Some(monitor) = self.monitor.as_ref() {
+            let _ = monitor.send(buf);
+        }
+        result
     }
 
     /// The main message handling loop of the application.
     /// It listens for incoming OSC messages, processes them, and drives the application state.
-    pub fn handle_messages(&mut self) {
-        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    /// Returns once `shutdown` is set, so that the extensions (and their trackers) can be dropped cleanly.
+    /// Fails if the sockets can't be set up, e.g. because the port is taken.
+    pub fn handle_messages(&mut self, shutdown: Arc<AtomicBool>) -> anyhow::Result<()> {
+        let listen_addr = SocketAddr::new(self.bind, self.osc_port);
         let listener =
-            UdpSocket::bind(SocketAddr::new(ip, self.osc_port)).expect("bind listener socket");
+            UdpSocket::bind(listen_addr).map_err(|e| self.listen_error(listen_addr, e))?;
 
         // A loopback socket to self-trigger the processing loop when in self-driven mode.
-        let lo = UdpSocket::bind("0.0.0.0:0").expect("bind self socket");
-        lo.connect(SocketAddr::new(ip, self.osc_port)).unwrap();
-        let lo_addr = lo.local_addr().unwrap();
+        // It always stays local: a wildcard listener is reached through the localhost of its family.
+        // The socket has the listener's family, so that its address matches what the listener receives.
+        let lo_target = match self.bind {
+            IpAddr::V4(ip) if ip.is_unspecified() => {
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), self.osc_port)
+            }
+            IpAddr::V6(ip) if ip.is_unspecified() => {
+                SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), self.osc_port)
+            }
+            _ => listen_addr,
+        };
+        let lo = UdpSocket::bind(unspecified_for(lo_target.ip()))
+            .and_then(|lo| lo.connect(lo_target).map(|_| lo))
+            .with_context(|| format!("Could not set up the loopback socket to {}", lo_target))?;
+        let lo_addr = lo.local_addr()?;
+
+        // Wake up regularly even without incoming messages, so that a shutdown is noticed quickly.
+        let _ = listener.set_read_timeout(Some(Duration::from_millis(200)));
 
         // Initialize the application state.
         let mut state = AppState {
-            status: status::StatusBar::new(&self.multi),
+            status: status::StatusBar::new(
+                &self.multi,
+                self.status_max_width,
+                self.status_max_items,
+                self.status_format,
+            ),
             params: AvatarParameters::new(),
             tracking: OscTrack {
                 head: Affine3A::IDENTITY,
+                head_smoothed: Affine3A::IDENTITY,
                 left_hand: Affine3A::IDENTITY,
                 right_hand: Affine3A::IDENTITY,
-                last_received: Instant::now(),
+                last_received: None,
+                received_over_osc: false,
             },
             self_drive: Arc::new(AtomicBool::new(true)),
             delta_t: 0.011f32,
         };
 
         // Start the watchdog to monitor responsiveness.
-        let watchdog = watchdog::Watchdog::new(state.self_drive.clone());
+        let watchdog = watchdog::Watchdog::new(
+            state.self_drive.clone(),
+            self.watchdog_timeout_ms,
+            self.watchdog_action,
+        );
         watchdog.run();
         // Spawn a thread to periodically send a message to the loopback socket if in self-drive mode.
         // This ensures the `process` function is called regularly.
-        thread::spawn({
+        let self_drive_thread = thread::spawn({
             let drive = state.self_drive.clone();
-            move || loop {
-                if drive.load(Ordering::Relaxed) {
-                    let _ = lo.send(&[0u8; 1]);
-                    thread::sleep(Duration::from_millis(11)); // ~90 Hz
-                } else {
-                    // If not in self-drive mode, sleep longer as we wait for VSync messages.
-                    thread::sleep(Duration::from_millis(200));
+            let shutdown = shutdown.clone();
+            move || {
+                while !shutdown.load(Ordering::Relaxed) {
+                    if drive.load(Ordering::Relaxed) {
+                        let _ = lo.send(&[0u8; 1]);
+                        thread::sleep(Duration::from_millis(11)); // ~90 Hz
+                    } else {
+                        // If not in self-drive mode, sleep longer as we wait for VSync messages.
+                        thread::sleep(Duration::from_millis(200));
+                    }
                 }
             }
         });
@@ -173,84 +531,192 @@ impl AvatarOsc {
 
         let mut last_frame = Instant::now();
         let mut buf = [0u8; rosc::decoder::MTU];
-        loop {
+        while !shutdown.load(Ordering::Relaxed) {
             if let Ok((size, addr)) = listener.recv_from(&mut buf) {
                 // If the message is from our loopback socket, it's a tick for the process loop.
                 if addr == lo_addr {
                     self.process(&mut state);
+                    if self.tui.as_ref().is_some_and(tui::Tui::quit_requested) {
+                        shutdown.store(true, Ordering::Relaxed);
+                    }
                     watchdog.update();
                     state.delta_t = last_frame.elapsed().as_secs_f32();
                     last_frame = Instant::now();
                     continue;
                 }
 
-                // Decode the received UDP packet as an OSC message.
-                if let Ok((_, OscPacket::Message(packet))) = rosc::decoder::decode_udp(&buf[..size])
-                {
-                    state.status.trip_recv_counter();
-                    // Handle avatar parameter changes.
-                    if packet.addr.starts_with(PARAM_PREFIX) {
-                        let name: Arc<str> = packet.addr[PARAM_PREFIX.len()..].into();
-                        // The "VSync" parameter is special: it drives the main loop when available.
-                        if &*name == "VSync" {
-                            state.self_drive.store(false, Ordering::Relaxed);
-                            self.process(&mut state);
-                            state.delta_t = last_frame.elapsed().as_secs_f32();
-                            last_frame = Instant::now();
-                            watchdog.update();
-                        } else if let Some(arg) = packet.args.into_iter().next() {
-                            // Notify extensions of parameter changes and update the state.
-                            self.ext_storage.notify(&name, &arg);
-                            self.ext_gogo.notify(&name, &arg);
-                            state.params.insert(name, arg);
-                        }
-                    // Handle tracker data.
-                    } else if packet.addr.starts_with(TRACK_PREFIX) {
-                        if let [OscType::Float(x), OscType::Float(y), OscType::Float(z), OscType::Float(ex), OscType::Float(ey), OscType::Float(ez)] =
-                            packet.args[..]
-                        {
-                            let transform = Affine3A::from_rotation_translation(
-                                Quat::from_euler(glam::EulerRot::ZXY, ex, ey, ez),
-                                Vec3::new(x, y, z),
-                            );
-
-                            if packet.addr[TRACK_PREFIX.len()..].starts_with("head") {
-                                state.tracking.last_received = Instant::now();
-                                state.tracking.head = transform;
-                            } else if packet.addr[TRACK_PREFIX.len()..].starts_with("leftwrist") {
-                                state.tracking.left_hand = transform;
-                            } else if packet.addr[TRACK_PREFIX.len()..].starts_with("rightwrist") {
-                                state.tracking.right_hand = transform;
-                            }
-                        }
-                    // Handle avatar changes.
-                    } else if packet.addr.starts_with(AVATAR_PREFIX) {
-                        if let [OscType::String(avatar)] = &packet.args[..] {
-                            self.avatar(AvatarIdentifier::Uid(avatar.clone()), &mut state);
-                        }
-                    } else {
-                        log::info!("Received data: {:?}", packet);
-                    }
+                // Decode the received UDP packet, which can be a single message or a bundle of them.
+                if let Ok((_, packet)) = rosc::decoder::decode_udp(&buf[..size]) {
+                    self.handle_packet(packet, &mut state, &watchdog, &mut last_frame);
                 }
             };
         }
+
+        info!("Shutting down.");
+        let _ = self_drive_thread.join();
+        Ok(())
+    }
+
+    /// Explains why the OSC listener couldn't be bound, which mostly happens when the port is taken.
+    fn listen_error(&self, listen_addr: SocketAddr, e: io::Error) -> anyhow::Error {
+        if self
+            .upstream
+            .iter()
+            .any(|u| u.peer_addr().port() == self.osc_port)
+        {
+            return anyhow!(
+                "Could not listen for OSC on {}: {}. --osc-port is also the port that OscAvMgr sends to (--vrc-port), \
+                 pick a different --osc-port and point the game's OSC output at it.",
+                listen_addr,
+                e
+            );
+        }
+        if e.kind() == io::ErrorKind::AddrInUse {
+            return anyhow!(
+                "Could not listen for OSC on {}: the port is already in use. Another instance of OscAvMgr may be running; \
+                 otherwise pick a different --osc-port and point the game's OSC output at it.",
+                listen_addr
+            );
+        }
+        anyhow!(
+            "Could not listen for OSC on {}: {}. Try a different --osc-port or --bind.",
+            listen_addr,
+            e
+        )
+    }
+
+    /// Routes a decoded OSC packet. Bundles are walked recursively,
+    /// so that every message inside them is routed just like a single message.
+    fn handle_packet(
+        &mut self,
+        packet: OscPacket,
+        state: &mut AppState,
+        watchdog: &watchdog::Watchdog,
+        last_frame: &mut Instant,
+    ) {
+        match packet {
+            OscPacket::Message(packet) => self.handle_message(packet, state, watchdog, last_frame),
+            OscPacket::Bundle(bundle) => {
+                for packet in bundle.content {
+                    self.handle_packet(packet, state, watchdog, last_frame);
+                }
+            }
+        }
+    }
+
+    /// Routes a single OSC message to the parameter, tracker or avatar change handling.
+    fn handle_message(
+        &mut self,
+        packet: OscMessage,
+        state: &mut AppState,
+        watchdog: &watchdog::Watchdog,
+        last_frame: &mut Instant,
+    ) {
+        state.status.trip_recv_counter();
+        // Handle avatar parameter changes.
+        if packet.addr.starts_with(PARAM_PREFIX) {
+            let name: Arc<str> = packet.addr[PARAM_PREFIX.len()..].into();
+            // The "VSync" parameter is special: it drives the main loop when available.
+            if &*name == "VSync" {
+                // A flipping bool or a randomized float: the same value again is a resent frame, not a new one.
+                // Other types can't be told apart, so every message counts as a frame.
+                let value = packet.args.into_iter().next();
+                if matches!(value, Some(OscType::Bool(_) | OscType::Float(_))) {
+                    if value == self.last_vsync {
+                        return;
+                    }
+                    self.last_vsync = value;
+                }
+                state.self_drive.store(false, Ordering::Relaxed);
+                self.process(state);
+                state.delta_t = last_frame.elapsed().as_secs_f32();
+                *last_frame = Instant::now();
+                watchdog.update();
+            } else if let Some(arg) = packet.args.into_iter().next() {
+                // VSync is handled above, so it's never delayed by the jitter buffer.
+                match self.param_jitter.as_mut() {
+                    Some(jitter) => jitter.push(name, arg),
+                    None => self.apply_param(name, arg, state),
+                }
+            }
+        // Handle tracker data.
+        } else if packet.addr.starts_with(TRACK_PREFIX) {
+            if let [OscType::Float(x), OscType::Float(y), OscType::Float(z), OscType::Float(ex), OscType::Float(ey), OscType::Float(ez)] =
+                packet.args[..]
+            {
+                let transform = Affine3A::from_rotation_translation(
+                    Quat::from_euler(glam::EulerRot::ZXY, ex, ey, ez),
+                    Vec3::new(x, y, z),
+                );
+
+                let tracker = &packet.addr[TRACK_PREFIX.len()..];
+                let aliases = &self.tracker_aliases;
+                if TrackerAliases::matches(&aliases.head, tracker) {
+                    state.tracking.last_received = Some(Instant::now());
+                    state.tracking.received_over_osc = true;
+                    state.tracking.head = transform;
+                } else if TrackerAliases::matches(&aliases.left_wrist, tracker) {
+                    state.tracking.left_hand = transform;
+                } else if TrackerAliases::matches(&aliases.right_wrist, tracker) {
+                    state.tracking.right_hand = transform;
+                }
+            }
+        // Handle eye data sent by another app in VRChat's format.
+        } else if packet.addr.starts_with(EYE_PREFIX) {
+            self.ext_tracking.ingest_eye_osc(&packet);
+        // Handle avatar changes.
+        } else if packet.addr.starts_with(AVATAR_PREFIX) {
+            if let [OscType::String(avatar)] = &packet.args[..] {
+                self.avatar(AvatarIdentifier::Uid(avatar.clone()), state);
+            }
+        } else {
+            log::info!("Received data: {:?}", packet);
+        }
+    }
+
+    /// Notifies extensions of a parameter change and updates the state.
+    /// The game repeats unchanged values a lot, so those are skipped; the first value of a parameter always notifies.
+    fn apply_param(&mut self, name: Arc<str>, arg: OscType, state: &mut AppState) {
+        if state.params.get(&name) == Some(&arg) {
+            return;
+        }
+        self.ext_storage.notify(&name, &arg);
+        self.ext_gogo.notify(&name, &arg);
+        state.params.insert(name, arg);
     }
 
     /// Handles avatar changes. This is called when a `/avatar/change` message is received.
-    /// It loads the new avatar's OSC JSON configuration and notifies extensions.
+    /// It loads the new avatar's OSC JSON configuration, which is applied right away or once it's fetched.
     fn avatar(&mut self, avatar: AvatarIdentifier, state: &mut AppState) {
         info!("Avatar changed: {:?}", avatar);
-        let osc_root_node = self.ext_oscjson.avatar(&avatar);
+        match self.ext_oscjson.avatar(&avatar) {
+            AvatarLoad::Loaded(osc_root_node) => self.apply_avatar(osc_root_node, state),
+            AvatarLoad::Fetching => {}
+        }
+    }
+
+    /// Applies the new avatar's OSC JSON configuration (or the lack of one) and notifies extensions.
+    fn apply_avatar(&mut self, osc_root_node: Option<OscJsonNode>, state: &mut AppState) {
+        let mut bundle = OscBundle::new_bundle();
         if let Some(osc_root_node) = osc_root_node.as_ref() {
+            // Clear the previous mapping's parameters before they're forgotten.
+            self.ext_tracking.reset_params(&mut bundle);
             self.ext_tracking.osc_json(osc_root_node);
+            self.ext_oscjson
+                .publish(&self.ext_tracking.param_addresses());
+        }
+        if let Some(strict_types) = self.strict_types.as_mut() {
+            strict_types.avatar(osc_root_node.as_ref());
         }
 
+        // Make sure the new avatar gets told about the tracking state.
+        self.tracking_active = None;
+
         // Let the GoGo extension know about the avatar change.
-        let mut bundle = OscBundle::new_bundle();
         self.ext_gogo.avatar(&mut bundle);
-        bundle
-            .serialize()
-            .and_then(|buf| self.send_upstream(&buf).ok());
+        for buf in bundle.serialize_chunked(rosc::decoder::MTU) {
+            let _ = self.send_upstream(&buf);
+        }
 
         // Determine if the application should be self-driven or VSync-driven based on the new avatar's capabilities.
         state.self_drive.store(
@@ -287,34 +753,116 @@ impl AvatarOsc {
     fn process(&mut self, state: &mut AppState) {
         let mut bundle = OscBundle::new_bundle();
 
+        // Clamp the frame time once for all extensions, so that a single hitch doesn't make
+        // every time-aware feature jump at once.
+        if self.max_delta_t > 0. {
+            state.delta_t = state.delta_t.min(self.max_delta_t);
+        }
+
+        // Apply the buffered parameter updates that are due.
+        if let Some(jitter) = self.param_jitter.as_mut() {
+            for (name, arg) in jitter.release() {
+                self.apply_param(name, arg, state);
+            }
+        }
+
         // Update status bar items.
-        state
-            .status
-            .add_item(match state.self_drive.load(Ordering::Relaxed) {
+        state.status.add_item_priority(
+            match state.self_drive.load(Ordering::Relaxed) {
                 true => DRIVE_ON.clone(),
                 false => DRIVE_OFF.clone(),
-            });
+            },
+            StatusPriority::High,
+        );
 
-        state.status.add_item(
-            match state.tracking.last_received.elapsed() < Duration::from_secs(1) {
+        let tracking_live = state
+            .tracking
+            .last_received
+            .is_some_and(|t| t.elapsed() < Duration::from_secs(1));
+        state.status.add_state(
+            match tracking_live {
                 true => TRACK_ON.clone(),
                 false => TRACK_OFF.clone(),
             },
+            tracking_live,
+            StatusPriority::High,
         );
 
         // Check for avatar changes from OSC JSON or command line arguments.
+        // A UID is kept until it can be loaded, e.g. once the OSCJSON service is found.
         if self.ext_oscjson.step() {
             self.avatar(AvatarIdentifier::Default, state);
         } else if let Some(path) = self.avatar_file.take() {
             self.avatar(AvatarIdentifier::Path(path.clone()), state);
+        } else if let Some(uid) = self
+            .avatar_uid
+            .take_if(|uid| self.ext_oscjson.can_load_uid(uid))
+        {
+            self.avatar(AvatarIdentifier::Uid(uid), state);
+        } else if let Some(path) = self
+            .avatar_watch
+            .as_mut()
+            .and_then(|watch| watch.changed().then(|| watch.path().to_string()))
+        {
+            info!("Avatar file changed, reloading.");
+            self.avatar(AvatarIdentifier::Path(path), state);
+        }
+        if let Some(osc_root_node) = self.ext_oscjson.fetched() {
+            self.apply_avatar(osc_root_node, state);
+        }
+
+        // Let the avatar know whether head & wrist data is being received. Only sent on change.
+        if self.emit_tracking_active && self.tracking_active != Some(tracking_live) {
+            bundle.send_parameter("TrackingActive", OscType::Bool(tracking_live));
+            self.tracking_active = Some(tracking_live);
+        }
+
+        // A master switch that can be flipped from the avatar's menu: while it's set, the avatar is left neutral
+        // and no extension is stepped.
+        let paused = state.params.get("OscAvMgrPause").is_some_and(is_truthy);
+        if paused != self.paused {
+            if paused {
+                info!("OscAvMgrPause: tracking paused.");
+                self.ext_tracking.send_neutral(&mut bundle);
+                self.ext_autopilot.release(&mut bundle);
+            } else {
+                info!("OscAvMgrPause: tracking resumed.");
+            }
+            self.paused = paused;
+        }
+
+        if paused {
+            state
+                .status
+                .add_item_priority(PAUSED.clone(), StatusPriority::High);
+        } else {
+            // Step through each extension, allowing them to add messages to the OSC bundle.
+            self.ext_storage.step(&mut bundle);
+            self.ext_tracking.step(state, &mut bundle);
+            state.tracking.smooth_head(self.head_smoothing);
+            self.ext_pulse.step(&self.ext_tracking, &mut bundle);
+            self.ext_gogo.step(&state.params, &mut bundle);
+            self.ext_autopilot
+                .step(state, &self.ext_tracking, &mut bundle);
+
+            if self.forward_trackers {
+                tracker_forward::forward(&state.tracking, &mut bundle);
+            }
+        }
+
+        if let Some(chatbox) = self.ext_chatbox.as_mut() {
+            chatbox.step(&state.status, &mut bundle);
         }
 
-        // Step through each extension, allowing them to add messages to the OSC bundle.
-        self.ext_storage.step(&mut bundle);
-        self.ext_tracking.step(state, &mut bundle);
-        self.ext_gogo.step(&state.params, &mut bundle);
-        self.ext_autopilot
-            .step(state, &self.ext_tracking, &mut bundle);
+        // Send each parameter only once, with the last value set this frame.
+        bundle.dedup_parameters();
+
+        // Drop tracker updates that didn't move since they were last sent.
+        self.tracker_filter.filter(&mut bundle);
+
+        if let Some(strict_types) = self.strict_types.as_mut() {
+            strict_types.enforce(&mut bundle);
+        }
 
         // If the first item in the bundle is a single message, send it immediately.
         // This is likely for low-latency updates.
@@ -332,18 +880,26 @@ impl AvatarOsc {
         state.status.set_sent_count(bundle.content.len() as _);
         state.status.recv_summary();
 
+        if let Some(metrics) = self.metrics.as_ref() {
+            metrics.update(metrics::Metrics {
+                fps: state.status.fps(),
+                send_rate: state.status.send_rate(),
+                recv_rate: state.status.recv_rate(),
+                face: self.ext_tracking.face_live(),
+                gaze: self.ext_tracking.gaze_live(),
+                track: tracking_live,
+            });
+        }
+
         // Chunk the remaining bundle content and send it upstream.
-        // This avoids sending UDP packets that are too large.
-        for bundle in bundle.content.chunks(30).map(|chunk| {
-            let mut bundle = OscBundle::new_bundle();
-            bundle.content.extend_from_slice(chunk);
-            bundle
-        }) {
-            bundle
-                .serialize()
-                .and_then(|buf| self.send_upstream(&buf).ok());
+        // Chunks are packed by their encoded size, so that no UDP packet is larger than the MTU.
+        for buf in bundle.serialize_chunked(rosc::decoder::MTU) {
+            let _ = self.send_upstream(&buf);
         }
 
+        if let Some(tui) = self.tui.as_mut() {
+            tui.step(&state.status, &self.ext_tracking);
+        }
         state.status.display();
     }
 }
@@ -357,6 +913,8 @@ pub static TRACK_ON: Lazy<Arc<str>> =
 pub static TRACK_OFF: Lazy<Arc<str>> =
     Lazy::new(|| format!("{}", "TRACK".color(Color::Red)).into());
 
+static PAUSED: Lazy<Arc<str>> = Lazy::new(|| format!("{}", "PAUSED".color(Color::Yellow)).into());
+
 // Static lazy-initialized strings for instruction headers in the terminal.
 pub static INSTRUCTIONS_START: Lazy<Arc<str>> = Lazy::new(|| {
     format!(
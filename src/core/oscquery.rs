@@ -0,0 +1,131 @@
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, TcpListener},
+    sync::{Arc, Mutex},
+};
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+
+use super::{ext_oscjson::OscJsonNode, http, PARAM_PREFIX};
+
+/// A minimal OSCQuery HTTP server that publishes the parameters this application sends,
+/// so that external tools can discover and enumerate them.
+///
+/// Only the read-only parts of OSCQuery are implemented: `GET /` (or any sub-path) returns the
+/// node tree as JSON, and `GET /?HOST_INFO` returns the host info.
+pub struct OscQueryServer {
+    /// The published node tree, replaced whenever the sent parameters change.
+    tree: Arc<Mutex<OscJsonNode>>,
+    /// The port the HTTP server listens on.
+    http_port: u16,
+}
+
+impl OscQueryServer {
+    /// Starts the HTTP server on `http_port` and advertises it over mDNS as `_oscjson._tcp`.
+    /// Returns `None` if the port can't be bound.
+    pub fn new(mdns: &ServiceDaemon, http_port: u16, osc_port: u16) -> Option<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, http_port))
+            .map_err(|e| log::error!("OSCQuery: could not bind port {}: {:?}", http_port, e))
+            .ok()?;
+
+        let tree = Arc::new(Mutex::new(container("/")));
+        let host_info = serde_json::json!({
+            "NAME": "OscAvMgr",
+            "OSC_IP": "127.0.0.1",
+            "OSC_PORT": osc_port,
+            "OSC_TRANSPORT": "UDP",
+            "EXTENSIONS": {
+                "ACCESS": true,
+                "CONTENTS": true,
+                "FULL_PATH": true,
+                "TYPE": true,
+            },
+        })
+        .to_string();
+
+        http::serve(listener, "OSCQuery", "application/json", {
+            let tree = tree.clone();
+            move |path, query| {
+                if query == "HOST_INFO" {
+                    return Some(host_info.clone());
+                }
+
+                let tree = tree.lock().unwrap();
+                let path = path.trim_matches('/');
+                let node = if path.is_empty() {
+                    Some(&*tree)
+                } else {
+                    tree.get(path)
+                };
+                node.and_then(|node| serde_json::to_string(node).ok())
+            }
+        });
+
+        let server = Self { tree, http_port };
+        server.advertise(mdns);
+        log::info!("OSCQuery: serving on http://127.0.0.1:{}", http_port);
+        Some(server)
+    }
+
+    /// Advertises the service on the given mDNS daemon, e.g. again after the daemon was recreated.
+    pub fn advertise(&self, mdns: &ServiceDaemon) {
+        let instance = format!("OscAvMgr-{}", self.http_port);
+        if let Err(e) = ServiceInfo::new(
+            "_oscjson._tcp.local.",
+            &instance,
+            "oscavmgr.local.",
+            "127.0.0.1",
+            self.http_port,
+            HashMap::<String, String>::new(),
+        )
+        .and_then(|info| mdns.register(info))
+        {
+            log::error!("OSCQuery: could not advertise service: {:?}", e);
+        }
+    }
+
+    /// Replaces the published tree with the given parameters.
+    /// Addresses are relative to `/avatar/parameters/` and come with their OSC type tag.
+    pub fn publish(&self, params: &[(Arc<str>, &'static str)]) {
+        let mut root = container("/");
+        for (addr, data_type) in params.iter() {
+            insert(&mut root, &format!("{}{}", PARAM_PREFIX, addr), data_type);
+        }
+        if let Ok(mut tree) = self.tree.lock() {
+            *tree = root;
+        }
+    }
+}
+
+/// Creates an empty container node.
+fn container(full_path: &str) -> OscJsonNode {
+    OscJsonNode {
+        full_path: full_path.into(),
+        access: 0,
+        data_type: None,
+        contents: Some(HashMap::new()),
+    }
+}
+
+/// Inserts a read-only leaf node at `full_path`, creating the containers leading up to it.
+fn insert(root: &mut OscJsonNode, full_path: &str, data_type: &str) {
+    let mut node = root;
+    let mut path = String::new();
+    let parts: Vec<&str> = full_path.trim_start_matches('/').split('/').collect();
+
+    for (i, part) in parts.iter().enumerate() {
+        path.push('/');
+        path.push_str(part);
+
+        let contents = node.contents.get_or_insert_with(HashMap::new);
+        node = contents
+            .entry((*part).into())
+            .or_insert_with(|| container(&path));
+
+        if i == parts.len() - 1 {
+            node.access = 1;
+            node.data_type = Some(data_type.into());
+            node.contents = None;
+        }
+    }
+}
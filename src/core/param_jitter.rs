@@ -0,0 +1,107 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use rosc::OscType;
+
+/// The arrival and release history of a single parameter.
+struct ParamTiming {
+    /// When the last update for this parameter arrived.
+    last_arrival: Instant,
+    /// When the last update for this parameter is (or was) released.
+    last_release: Instant,
+    /// A running average of the time between two updates of this parameter.
+    avg_interval: Duration,
+}
+
+/// A parameter update waiting to be applied.
+struct PendingParam {
+    release_at: Instant,
+    name: Arc<str>,
+    value: OscType,
+}
+
+/// Smooths out bursty incoming parameter updates.
+///
+/// On some networks, updates arrive clumped together and then nothing arrives for a while,
+/// which makes the avatar move unevenly. Each update is held back for a short delay, and updates
+/// of the same parameter that arrive in a burst are spread out at that parameter's usual update rate.
+/// This trades a few milliseconds of latency for smoother playback.
+pub struct ParamJitter {
+    /// How long each update is held back.
+    delay: Duration,
+    /// Updates that haven't been released yet, ordered by arrival.
+    pending: VecDeque<PendingParam>,
+    /// Timing history of each parameter.
+    timings: HashMap<Arc<str>, ParamTiming>,
+}
+
+impl ParamJitter {
+    /// Creates a new `ParamJitter`. Returns `None` if `delay_ms` is 0, applying updates instantly.
+    pub fn new(delay_ms: u64) -> Option<Self> {
+        if delay_ms == 0 {
+            return None;
+        }
+
+        Some(Self {
+            delay: Duration::from_millis(delay_ms),
+            pending: VecDeque::new(),
+            timings: HashMap::new(),
+        })
+    }
+
+    /// Queues an incoming parameter update.
+    pub fn push(&mut self, name: Arc<str>, value: OscType) {
+        let now = Instant::now();
+        let mut release_at = now + self.delay;
+
+        if let Some(timing) = self.timings.get_mut(&name) {
+            // Track the usual update rate, but never wait longer than the delay between two updates.
+            let interval = now.duration_since(timing.last_arrival).min(self.delay);
+            timing.avg_interval = timing.avg_interval.mul_f32(0.9) + interval.mul_f32(0.1);
+
+            // Spread out bursts, but never hold an update for more than twice the delay.
+            release_at = release_at
+                .max(timing.last_release + timing.avg_interval)
+                .min(now + self.delay * 2);
+
+            timing.last_arrival = now;
+            timing.last_release = release_at;
+        } else {
+            self.timings.insert(
+                name.clone(),
+                ParamTiming {
+                    last_arrival: now,
+                    last_release: release_at,
+                    avg_interval: Duration::ZERO,
+                },
+            );
+        }
+
+        self.pending.push_back(PendingParam {
+            release_at,
+            name,
+            value,
+        });
+    }
+
+    /// Takes all updates that are due, in the order they arrived.
+    pub fn release(&mut self) -> Vec<(Arc<str>, OscType)> {
+        let now = Instant::now();
+        let mut released = Vec::new();
+
+        // Bursts can be scheduled later than updates that arrive after them, so check every entry.
+        self.pending.retain(|p| {
+            if p.release_at <= now {
+                released.push((p.name.clone(), p.value.clone()));
+                false
+            } else {
+                true
+            }
+        });
+
+        released
+    }
+}
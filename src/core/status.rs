@@ -6,11 +6,38 @@
 use std::{collections::VecDeque, sync::Arc, time::Instant};
 
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde_json::{Map, Value};
+
+use crate::StatusFormat;
+
+/// The priority of a status bar item. When the line gets too long, the least important items are dropped first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StatusPriority {
+    /// Counters such as `TICK`, `RECV` and `SEND`.
+    Low,
+    /// Items of optional features, such as the autopilot mode.
+    Normal,
+    /// The state of the main loop and the tracking sources.
+    High,
+}
+
+/// A single item on the status line.
+#[derive(Clone)]
+pub struct StatusItem {
+    priority: StatusPriority,
+    text: Arc<str>,
+    /// Whether the module is active, for items added with `add_state`.
+    active: Option<bool>,
+}
 
 /// Manages a spinner-based status bar in the terminal.
 pub struct StatusBar {
     /// A vector of messages to be displayed in the status bar for the current frame.
-    messages: Vec<Arc<str>>,
+    messages: Vec<StatusItem>,
+    /// Whether to draw the spinner or print JSON lines.
+    format: StatusFormat,
+    /// The uptime in seconds at which the last JSON line was printed.
+    last_json: u64,
     /// The `ProgressBar` from `indicatif` used to render the spinner and messages.
     spinner: ProgressBar,
     /// A queue to track the number of sent OSC packets over the last second.
@@ -21,10 +48,18 @@ pub struct StatusBar {
     fps_counter: VecDeque<Instant>,
     /// The calculated ticks per second (FPS) of the main application loop.
     fps: f32,
+    /// The calculated sent OSC packets per second.
+    send_rate: f32,
+    /// The calculated received OSC packets per second.
+    recv_rate: f32,
     /// The time when the `StatusBar` was created, used for calculating uptime.
     start: Instant,
     /// The time elapsed since the last frame, used for time-delta calculations.
     pub last_frame_time: f32,
+    /// The maximum visible width of the status line, 0 for unlimited.
+    max_width: usize,
+    /// The maximum number of items on the status line, 0 for unlimited.
+    max_items: usize,
 }
 
 impl StatusBar {
@@ -33,14 +68,27 @@ impl StatusBar {
     /// # Arguments
     ///
     /// * `multi` - A `MultiProgress` manager from `indicatif` to which the new progress bar will be added.
-    pub fn new(multi: &MultiProgress) -> Self {
-        let spinner = multi.add(ProgressBar::new_spinner());
+    /// * `max_width` - The maximum visible width of the status line, 0 for unlimited.
+    /// * `max_items` - The maximum number of items on the status line, 0 for unlimited.
+    /// * `format` - Whether to draw the spinner or print JSON lines to stdout.
+    pub fn new(
+        multi: &MultiProgress,
+        max_width: usize,
+        max_items: usize,
+        format: StatusFormat,
+    ) -> Self {
+        let spinner = match format {
+            StatusFormat::Pretty => multi.add(ProgressBar::new_spinner()),
+            StatusFormat::Json => ProgressBar::hidden(),
+        };
         spinner.set_style(
             ProgressStyle::default_spinner().tick_chars("⠁⠂⠄⡀⡈⡐⡠⣀⣁⣂⣄⣌⣔⣤⣥⣦⣮⣶⣷⣿⡿⠿⢟⠟⡛⠛⠫⢋⠋⠍⡉⠉⠑⠡⢁"),
         );
 
         Self {
             messages: Vec::new(),
+            format,
+            last_json: 0,
             spinner,
             send_counter: VecDeque::new(),
             recv_counter: VecDeque::new(),
@@ -48,6 +96,10 @@ impl StatusBar {
             start: Instant::now(),
             last_frame_time: 0f32,
             fps: 1f32,
+            send_rate: 0f32,
+            recv_rate: 0f32,
+            max_width,
+            max_items,
         }
     }
 
@@ -76,7 +128,10 @@ impl StatusBar {
 
         // Calculate FPS and add it to the display messages.
         self.fps = self.fps_counter.len() as f32 / total_elapsed;
-        self.add_item(format!("TICK:{:.0}/s", self.fps).into());
+        self.add_item_priority(
+            format!("TICK:{:.0}/s", self.fps).into(),
+            StatusPriority::Low,
+        );
     }
 
     /// Records that a packet has been received.
@@ -101,12 +156,10 @@ impl StatusBar {
             .map(|time| time.elapsed().as_secs_f32())
             .unwrap_or(0f32);
 
-        self.add_item(
-            format!(
-                "RECV:{:.0}/s",
-                self.recv_counter.len() as f32 / total_elapsed
-            )
-            .into(),
+        self.recv_rate = self.recv_counter.len() as f32 / total_elapsed;
+        self.add_item_priority(
+            format!("RECV:{:.0}/s", self.recv_rate).into(),
+            StatusPriority::Low,
         );
     }
 
@@ -130,28 +183,165 @@ impl StatusBar {
             .unwrap_or(0f32);
 
         // Sum all counts in the window and divide by the elapsed time to get the rate.
-        let total = self
+        self.send_rate = self
             .send_counter
             .iter()
             .map(|(count, _)| count)
             .sum::<f32>()
             / total_elapsed;
 
-        self.add_item(format!("SEND:{:.1}/s", total).into());
+        self.add_item_priority(
+            format!("SEND:{:.1}/s", self.send_rate).into(),
+            StatusPriority::Low,
+        );
+    }
+
+    /// The ticks per second of the main loop, as of the last `trip_fps_counter`.
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    /// The sent OSC packets per second, as of the last `set_sent_count`.
+    pub fn send_rate(&self) -> f32 {
+        self.send_rate
+    }
+
+    /// The received OSC packets per second, as of the last `recv_summary`.
+    pub fn recv_rate(&self) -> f32 {
+        self.recv_rate
     }
 
     /// Adds a string item to be displayed in the status bar for the current frame.
     pub fn add_item(&mut self, str: Arc<str>) {
-        self.messages.push(str);
+        self.add_item_priority(str, StatusPriority::Normal);
+    }
+
+    /// Adds a string item with the given priority to be displayed in the status bar for the current frame.
+    pub fn add_item_priority(&mut self, str: Arc<str>, priority: StatusPriority) {
+        self.messages.push(StatusItem {
+            priority,
+            text: str,
+            active: None,
+        });
+    }
+
+    /// Adds the on/off item of a module, such as `FACE`, to be displayed in the status bar for the current frame.
+    /// In JSON output, the item becomes a named bool field instead of a plain string.
+    pub fn add_state(&mut self, str: Arc<str>, active: bool, priority: StatusPriority) {
+        self.messages.push(StatusItem {
+            priority,
+            text: str,
+            active: Some(active),
+        });
+    }
+
+    /// The number of items added for the current frame so far, to be passed to `items_since`.
+    pub fn item_count(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// The items added for the current frame since `item_count` returned `start`, e.g. by a single module.
+    pub fn items_since(&self, start: usize) -> Vec<StatusItem> {
+        self.messages[start.min(self.messages.len())..].to_vec()
+    }
+
+    /// Adds items taken with `items_since` to the current frame again.
+    pub fn add_items(&mut self, items: &[StatusItem]) {
+        self.messages.extend_from_slice(items);
+    }
+
+    /// The on/off states added with `add_state` for the current frame, by their lowercase names without colors.
+    pub fn states(&self) -> impl Iterator<Item = (String, bool)> + '_ {
+        self.messages.iter().filter_map(|item| {
+            item.active
+                .map(|active| (strip_colors(&item.text).to_lowercase(), active))
+        })
+    }
+
+    /// The status line of the current frame without colors, e.g. for the dashboard.
+    pub fn plain_line(&self) -> String {
+        strip_colors(&self.render())
+    }
+
+    /// Joins the messages into a single line that respects `max_width` and `max_items`.
+    /// The most important items are picked first, but the picked items keep the order they were added in.
+    fn render(&self) -> String {
+        const SEPARATOR: &str = "  ";
+        // The spinner character and the space after it.
+        const SPINNER_WIDTH: usize = 2;
+
+        let mut order: Vec<usize> = (0..self.messages.len()).collect();
+        // Stable sort, so that items of the same priority keep their relative order.
+        order.sort_by(|a, b| self.messages[*b].priority.cmp(&self.messages[*a].priority));
+
+        let mut keep = vec![false; self.messages.len()];
+        let mut width = SPINNER_WIDTH;
+        let mut count = 0;
+
+        for idx in order {
+            if self.max_items > 0 && count >= self.max_items {
+                break;
+            }
+            let item_width = visible_width(&self.messages[idx].text)
+                + if count > 0 { SEPARATOR.len() } else { 0 };
+            if self.max_width > 0 && width + item_width > self.max_width {
+                continue;
+            }
+            width += item_width;
+            count += 1;
+            keep[idx] = true;
+        }
+
+        self.messages
+            .iter()
+            .zip(keep)
+            .filter_map(|(item, keep)| keep.then_some(item.text.as_ref()))
+            .collect::<Vec<_>>()
+            .join(SEPARATOR)
+    }
+
+    /// Builds a JSON object of the rates and the collected messages.
+    /// Module states become named bool fields, other items are listed under `items` without their colors.
+    /// The counters are left out, since the rates are already included as numbers.
+    fn render_json(&self, uptime: u64) -> String {
+        let mut obj = Map::new();
+        obj.insert("uptime".into(), uptime.into());
+        obj.insert("fps".into(), self.fps.into());
+        obj.insert("send_rate".into(), self.send_rate.into());
+        obj.insert("recv_rate".into(), self.recv_rate.into());
+
+        let mut items = vec![];
+        for item in self.messages.iter() {
+            let text = strip_colors(&item.text);
+            match item.active {
+                Some(active) => {
+                    obj.insert(text.to_lowercase(), active.into());
+                }
+                None if item.priority > StatusPriority::Low => items.push(Value::from(text)),
+                None => {}
+            }
+        }
+        obj.insert("items".into(), items.into());
+
+        Value::Object(obj).to_string()
     }
 
     /// Updates the spinner with the collected messages for the current frame.
     /// After displaying, it clears the message buffer for the next frame.
     pub fn display(&mut self) {
         let uptime = self.start.elapsed().as_secs();
+        if self.format == StatusFormat::Json {
+            // Once per second is plenty, since the rates are averaged over a second anyway.
+            if uptime > self.last_json {
+                println!("{}", self.render_json(uptime));
+                self.last_json = uptime;
+            }
+            self.messages.clear();
+            return;
+        }
         if uptime >= 1 {
             // Join all messages with spaces and set it as the spinner's message.
-            let str = self.messages.join("  ");
+            let str = self.render();
             self.spinner.set_message(str);
         } else {
             // Display "Initializing..." for the first second.
@@ -161,3 +351,25 @@ impl StatusBar {
         self.messages.clear();
     }
 }
+
+/// Removes the ANSI color codes from a string.
+fn strip_colors(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut in_escape = false;
+    for c in s.chars() {
+        if in_escape {
+            // Color codes are terminated by a letter, e.g. `\x1b[32m`.
+            in_escape = !c.is_ascii_alphabetic();
+        } else if c == '\x1b' {
+            in_escape = true;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Calculates the width of a string as it appears in the terminal, ignoring ANSI color codes.
+fn visible_width(s: &str) -> usize {
+    strip_colors(s).chars().count()
+}
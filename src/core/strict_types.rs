@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+
+use rosc::{OscBundle, OscPacket, OscType};
+
+use super::{ext_oscjson::OscJsonNode, PARAM_PREFIX};
+
+/// The OSC types that an avatar can declare for a parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParamType {
+    Float,
+    Int,
+    Bool,
+}
+
+impl ParamType {
+    /// Parses a declared OSC JSON type. OSCQuery uses type tags (`f`, `i`, `T`/`F`), but the
+    /// spelled out names are accepted as well. Returns `None` for types that aren't enforced.
+    fn from_declared(data_type: &str) -> Option<Self> {
+        match data_type {
+            "f" | "Float" => Some(Self::Float),
+            "i" | "Int" => Some(Self::Int),
+            "T" | "F" | "Bool" => Some(Self::Bool),
+            _ => None,
+        }
+    }
+
+    /// Converts a value to this type.
+    fn coerce(self, value: &OscType) -> Option<OscType> {
+        let value = match (self, value) {
+            (Self::Float, OscType::Float(_))
+            | (Self::Int, OscType::Int(_))
+            | (Self::Bool, OscType::Bool(_)) => return None,
+            (Self::Float, OscType::Int(i)) => OscType::Float(*i as f32),
+            (Self::Float, OscType::Bool(b)) => OscType::Float(if *b { 1. } else { 0. }),
+            (Self::Int, OscType::Float(f)) => OscType::Int(f.round() as i32),
+            (Self::Int, OscType::Bool(b)) => OscType::Int(*b as i32),
+            (Self::Bool, OscType::Float(f)) => OscType::Bool(*f > 0.5),
+            (Self::Bool, OscType::Int(i)) => OscType::Bool(*i != 0),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Coerces every outgoing avatar parameter to exactly the type the avatar declares for it.
+///
+/// Some strict receivers drop a parameter entirely when it arrives with the wrong OSC type,
+/// e.g. an Int sent as a Float. The declared types are taken from the avatar's OSC JSON;
+/// parameters without a declared type are sent as-is, with a warning the first time they're seen.
+#[derive(Default)]
+pub struct StrictTypes {
+    /// The declared type of each parameter of the current avatar, by full OSC address.
+    types: Option<HashMap<String, ParamType>>,
+    /// Addresses that have already been warned about, so each is only reported once.
+    warned: HashSet<String>,
+}
+
+impl StrictTypes {
+    /// Loads the declared parameter types of a new avatar.
+    /// Without an OSC JSON, nothing is enforced until the next avatar change.
+    pub fn avatar(&mut self, root: Option<&OscJsonNode>) {
+        self.warned.clear();
+        self.types = root.map(|root| {
+            let mut types = HashMap::new();
+            collect_types(root, &mut types);
+            log::info!("Strict types: {} declared parameter(s)", types.len());
+            types
+        });
+    }
+
+    /// Coerces the parameters in the bundle to their declared types.
+    pub fn enforce(&mut self, bundle: &mut OscBundle) {
+        let Some(types) = self.types.as_ref() else {
+            return;
+        };
+
+        for packet in bundle.content.iter_mut() {
+            let OscPacket::Message(msg) = packet else {
+                continue;
+            };
+            if !msg.addr.starts_with(PARAM_PREFIX) {
+                continue;
+            }
+
+            match types.get(&msg.addr) {
+                Some(declared) => {
+                    for arg in msg.args.iter_mut() {
+                        if let Some(coerced) = declared.coerce(arg) {
+                            *arg = coerced;
+                        }
+                    }
+                }
+                None => {
+                    if self.warned.insert(msg.addr.clone()) {
+                        log::warn!(
+                            "Strict types: {} has no declared type, sending as-is.",
+                            msg.addr
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Recursively collects the declared type of every node in the tree.
+fn collect_types(node: &OscJsonNode, types: &mut HashMap<String, ParamType>) {
+    if let Some(declared) = node.data_type.as_deref().and_then(ParamType::from_declared) {
+        types.insert(node.full_path.to_string(), declared);
+    }
+    if let Some(contents) = node.contents.as_ref() {
+        for child in contents.values() {
+            collect_types(child, types);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use glam::{EulerRot, Quat, Vec3};
+use rosc::{OscBundle, OscPacket, OscType};
+
+use crate::Args;
+
+use super::TRACK_PREFIX;
+
+/// The last transform that was sent for a single tracker address.
+struct SentTracker {
+    position: Vec3,
+    rotation: Quat,
+    sent_at: Instant,
+}
+
+/// Suppresses redundant `/tracking/trackers/*` messages.
+///
+/// Similar to how `MysteryParam` only sends a parameter once its value has changed, a tracker is only
+/// re-sent once its position or rotation has moved far enough from the last sent transform.
+/// A keepalive forces a resend every so often, so that a lost packet doesn't leave a tracker stale.
+pub struct TrackerFilter {
+    /// The minimum position change (in meters) that causes a resend.
+    pos_epsilon: f32,
+    /// The minimum rotation change (in radians) that causes a resend.
+    rot_epsilon: f32,
+    /// The maximum time between two sends of the same tracker.
+    keepalive: Duration,
+    /// The last sent transform for each tracker address.
+    last_sent: HashMap<String, SentTracker>,
+}
+
+impl TrackerFilter {
+    /// Creates a new `TrackerFilter` using the epsilons and keepalive from the command line.
+    pub fn new(args: &Args) -> Self {
+        Self {
+            pos_epsilon: args.tracker_pos_epsilon,
+            rot_epsilon: args.tracker_rot_epsilon.to_radians(),
+            keepalive: Duration::from_millis(args.tracker_keepalive_ms),
+            last_sent: HashMap::new(),
+        }
+    }
+
+    /// Removes all tracker messages from the bundle that didn't change enough since they were last sent.
+    /// Messages that aren't 6-float tracker transforms are left untouched.
+    pub fn filter(&mut self, bundle: &mut OscBundle) {
+        let now = Instant::now();
+
+        bundle.content.retain(|packet| {
+            let OscPacket::Message(msg) = packet else {
+                return true;
+            };
+            if !msg.addr.starts_with(TRACK_PREFIX) {
+                return true;
+            }
+            let [OscType::Float(x), OscType::Float(y), OscType::Float(z), OscType::Float(ex), OscType::Float(ey), OscType::Float(ez)] =
+                msg.args[..]
+            else {
+                return true;
+            };
+
+            // Decoded the same way as they are encoded when forwarded: degrees in X, Y, Z order, applied in Z, X, Y order.
+            let position = Vec3::new(x, y, z);
+            let rotation = Quat::from_euler(
+                EulerRot::ZXY,
+                ez.to_radians(),
+                ex.to_radians(),
+                ey.to_radians(),
+            );
+
+            if let Some(last) = self.last_sent.get(&msg.addr) {
+                let moved = last.position.distance(position) > self.pos_epsilon;
+                let rotated = last.rotation.angle_between(rotation) > self.rot_epsilon;
+                let stale = now.duration_since(last.sent_at) >= self.keepalive;
+
+                if !(moved || rotated || stale) {
+                    log::trace!("Suppressing unchanged tracker {}", msg.addr);
+                    return false;
+                }
+            }
+
+            self.last_sent.insert(
+                msg.addr.clone(),
+                SentTracker {
+                    position,
+                    rotation,
+                    sent_at: now,
+                },
+            );
+            true
+        });
+    }
+}
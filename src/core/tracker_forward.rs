@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use glam::{Affine3A, EulerRot};
+use rosc::{OscBundle, OscType};
+
+use super::{bundle::AvatarBundle, OscTrack, TRACK_PREFIX};
+
+/// Forwards the head and wrist poses of the face receiver (e.g. OpenXR) as `/tracking/trackers/*`,
+/// for setups that want upper body tracking from the same source.
+///
+/// Poses are sent in the same layout that incoming trackers are parsed with: position, then ZXY euler angles.
+/// Poses that were themselves received over OSC are never forwarded, so that trackers aren't echoed back.
+/// Nothing is forwarded until real poses have arrived, rather than the identity poses from startup.
+pub fn forward(tracking: &OscTrack, bundle: &mut OscBundle) {
+    let live = tracking
+        .last_received
+        .is_some_and(|t| t.elapsed() <= Duration::from_secs(1));
+    if tracking.received_over_osc || !live {
+        return;
+    }
+
+    send_pose(bundle, "head", &tracking.head_smoothed);
+    send_pose(bundle, "leftwrist", &tracking.left_hand);
+    send_pose(bundle, "rightwrist", &tracking.right_hand);
+}
+
+fn send_pose(bundle: &mut OscBundle, name: &str, pose: &Affine3A) {
+    let (_, rotation, position) = pose.to_scale_rotation_translation();
+    // The receiver expects the angles in degrees and in X, Y, Z order, applied in Z, X, Y order.
+    // `to_euler` returns them in application order, so they are reordered here.
+    let (ez, ex, ey) = rotation.to_euler(EulerRot::ZXY);
+    bundle.send_tracking(
+        &format!("{}{}", TRACK_PREFIX, name),
+        vec![
+            OscType::Float(position.x),
+            OscType::Float(position.y),
+            OscType::Float(position.z),
+            OscType::Float(ex.to_degrees()),
+            OscType::Float(ey.to_degrees()),
+            OscType::Float(ez.to_degrees()),
+        ],
+    );
+}
@@ -0,0 +1,168 @@
+use std::{
+    io::{self, Write},
+    net::{SocketAddr, TcpStream, UdpSocket},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::UpstreamTransport;
+
+/// SLIP frame delimiter.
+const END: u8 = 0xC0;
+/// SLIP escape byte.
+const ESC: u8 = 0xDB;
+/// An escaped `END` byte.
+const ESC_END: u8 = 0xDC;
+/// An escaped `ESC` byte.
+const ESC_ESC: u8 = 0xDD;
+
+/// How long connecting may block the main loop.
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(100);
+/// How long a write may block the main loop before the connection is considered broken.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(50);
+/// The first and the longest wait between reconnection attempts.
+const MIN_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// A connection to one of the upstream OSC endpoints (the games).
+pub trait Upstream: Send {
+    /// Sends one OSC packet, returning the number of packet bytes sent.
+    fn send(&self, buf: &[u8]) -> io::Result<usize>;
+
+    /// The address the packets are sent to.
+    fn peer_addr(&self) -> SocketAddr;
+}
+
+/// Connects to `addr` with the given transport.
+/// UDP fails right away if the socket can't be set up, TCP keeps trying to connect in the background of sends.
+pub fn connect(
+    addr: SocketAddr,
+    local: SocketAddr,
+    transport: UpstreamTransport,
+) -> io::Result<Box<dyn Upstream>> {
+    Ok(match transport {
+        UpstreamTransport::Udp => Box::new(UdpUpstream::new(addr, local)?),
+        UpstreamTransport::Tcp => Box::new(TcpUpstream::new(addr)),
+    })
+}
+
+/// Plain OSC over UDP, one packet per datagram.
+pub struct UdpUpstream {
+    socket: UdpSocket,
+    addr: SocketAddr,
+}
+
+impl UdpUpstream {
+    pub fn new(addr: SocketAddr, local: SocketAddr) -> io::Result<Self> {
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        Ok(Self { socket, addr })
+    }
+}
+
+impl Upstream for UdpUpstream {
+    fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.socket.send(buf)
+    }
+
+    fn peer_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+/// OSC 1.1 style SLIP-framed packets over a persistent TCP connection.
+///
+/// The connection is (re)established lazily on send. After a failed attempt or a broken connection,
+/// sends are dropped until the backoff has passed, which doubles with each failure up to `MAX_BACKOFF`.
+pub struct TcpUpstream {
+    addr: SocketAddr,
+    state: Mutex<TcpState>,
+}
+
+struct TcpState {
+    stream: Option<TcpStream>,
+    next_attempt: Instant,
+    backoff: Duration,
+}
+
+impl TcpUpstream {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            state: Mutex::new(TcpState {
+                stream: None,
+                next_attempt: Instant::now(),
+                backoff: MIN_BACKOFF,
+            }),
+        }
+    }
+
+    fn open(&self) -> io::Result<TcpStream> {
+        let stream = TcpStream::connect_timeout(&self.addr, CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        Ok(stream)
+    }
+}
+
+impl TcpState {
+    /// Drops the connection and waits for the backoff before the next attempt.
+    fn fail(&mut self) {
+        self.stream = None;
+        self.next_attempt = Instant::now() + self.backoff;
+        self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+impl Upstream for TcpUpstream {
+    fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.stream.is_none() {
+            if Instant::now() < state.next_attempt {
+                return Err(io::ErrorKind::NotConnected.into());
+            }
+            match self.open() {
+                Ok(stream) => {
+                    log::info!("Connected to {} over TCP.", self.addr);
+                    state.stream = Some(stream);
+                    state.backoff = MIN_BACKOFF;
+                }
+                Err(e) => {
+                    log::debug!("Could not connect to {}: {}", self.addr, e);
+                    state.fail();
+                    return Err(e);
+                }
+            }
+        }
+
+        let frame = slip_encode(buf);
+        let result = state.stream.as_mut().unwrap().write_all(&frame);
+        if let Err(e) = result {
+            log::warn!("Lost the TCP connection to {}: {}", self.addr, e);
+            state.fail();
+            return Err(e);
+        }
+        Ok(buf.len())
+    }
+
+    fn peer_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+/// Frames a packet with SLIP (RFC 1055), as OSC 1.1 specifies for stream transports.
+/// The packet is delimited with `END` on both sides, so that a receiver can resync after line noise.
+pub fn slip_encode(buf: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(buf.len() + 2);
+    frame.push(END);
+    for &b in buf {
+        match b {
+            END => frame.extend_from_slice(&[ESC, ESC_END]),
+            ESC => frame.extend_from_slice(&[ESC, ESC_ESC]),
+            b => frame.push(b),
+        }
+    }
+    frame.push(END);
+    frame
+}
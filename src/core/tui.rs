@@ -0,0 +1,267 @@
+//! A full-screen dashboard for debugging mappings, enabled with `--tui`.
+//! It shows the status line, a live bar for each face shape and a scrollable pane with the log,
+//! which is captured by `TuiLogger` while the dashboard is open.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use log::{Log, Metadata, Record};
+use once_cell::sync::Lazy;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    DefaultTerminal, Frame,
+};
+
+use super::{ext_tracking::ExtTracking, status::StatusBar};
+
+/// How many log lines the log pane keeps.
+const MAX_LOG_LINES: usize = 1000;
+/// Redrawing at the full tick rate would only burn CPU, the terminal can't keep up anyway.
+const DRAW_INTERVAL: Duration = Duration::from_millis(33);
+/// The width of a shape column: the name, the bar and the value.
+const COLUMN_WIDTH: u16 = 48;
+const NAME_WIDTH: usize = 26;
+const VALUE_WIDTH: usize = 7;
+
+/// Whether log records go to the log pane instead of the terminal.
+static CAPTURE: AtomicBool = AtomicBool::new(false);
+/// The captured log lines, oldest first.
+static LOG_LINES: Lazy<Mutex<VecDeque<String>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// Routes log records to the dashboard's log pane while it is open, and to `inner` otherwise.
+pub struct TuiLogger<L: Log> {
+    inner: L,
+}
+
+impl<L: Log> TuiLogger<L> {
+    pub fn new(inner: L) -> Self {
+        Self { inner }
+    }
+}
+
+impl<L: Log> Log for TuiLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !CAPTURE.load(Ordering::Relaxed) {
+            self.inner.log(record);
+            return;
+        }
+        if !self.inner.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut lines = LOG_LINES.lock().unwrap();
+        let message = record.args().to_string();
+        for line in message.lines() {
+            lines.push_back(format!("{:<5} {}", record.level(), line));
+        }
+        while lines.len() > MAX_LOG_LINES {
+            lines.pop_front();
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// The dashboard. The terminal is restored when it's dropped.
+pub struct Tui {
+    terminal: DefaultTerminal,
+    next_draw: Instant,
+    /// Show every shape, instead of only those mapped to an avatar parameter.
+    show_all: bool,
+    /// How many lines the log pane is scrolled up from the newest line.
+    log_scroll: usize,
+    /// Whether `q`, `Esc` or `Ctrl-C` was pressed.
+    quit: bool,
+}
+
+impl Tui {
+    /// Switches the terminal to the dashboard. Returns `None` if the terminal can't be set up.
+    pub fn new() -> Option<Self> {
+        let terminal = match ratatui::try_init() {
+            Ok(terminal) => terminal,
+            Err(e) => {
+                log::error!("Could not start the dashboard: {}", e);
+                ratatui::restore();
+                return None;
+            }
+        };
+        CAPTURE.store(true, Ordering::Relaxed);
+
+        Some(Self {
+            terminal,
+            next_draw: Instant::now(),
+            show_all: false,
+            log_scroll: 0,
+            quit: false,
+        })
+    }
+
+    /// Whether the user asked to quit. The terminal is in raw mode, so Ctrl-C doesn't raise a signal.
+    pub fn quit_requested(&self) -> bool {
+        self.quit
+    }
+
+    /// Handles key presses and redraws the dashboard, if it's due.
+    /// Must run before the status line is displayed, which clears the items of the current frame.
+    pub fn step(&mut self, status: &StatusBar, tracking: &ExtTracking) {
+        self.handle_input();
+
+        if self.next_draw > Instant::now() {
+            return;
+        }
+        self.next_draw = Instant::now() + DRAW_INTERVAL;
+
+        let show_all = self.show_all;
+        let log_scroll = &mut self.log_scroll;
+        if let Err(e) = self.terminal.draw(|frame| {
+            draw(frame, status, tracking, show_all, log_scroll);
+        }) {
+            log::warn!("Could not draw the dashboard: {}", e);
+        }
+    }
+
+    fn handle_input(&mut self) {
+        while event::poll(Duration::ZERO).unwrap_or(false) {
+            let Ok(Event::Key(key)) = event::read() else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.quit = true
+                }
+                KeyCode::Char('a') => self.show_all = !self.show_all,
+                KeyCode::Up => self.log_scroll += 1,
+                KeyCode::Down => self.log_scroll = self.log_scroll.saturating_sub(1),
+                KeyCode::PageUp => self.log_scroll += 10,
+                KeyCode::PageDown => self.log_scroll = self.log_scroll.saturating_sub(10),
+                KeyCode::End => self.log_scroll = 0,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        CAPTURE.store(false, Ordering::Relaxed);
+        ratatui::restore();
+    }
+}
+
+fn draw(
+    frame: &mut Frame,
+    status: &StatusBar,
+    tracking: &ExtTracking,
+    show_all: bool,
+    log_scroll: &mut usize,
+) {
+    let [status_area, shapes_area, log_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(5),
+        Constraint::Length(12),
+    ])
+    .areas(frame.area());
+
+    frame.render_widget(
+        Paragraph::new(status.plain_line()).block(Block::bordered().title(" Status ")),
+        status_area,
+    );
+
+    draw_shapes(frame, shapes_area, tracking, show_all);
+    draw_log(frame, log_area, log_scroll);
+}
+
+/// Draws a bar per shape, in as many columns as fit. Negative values (e.g. `SmileSad`) are drawn in red.
+fn draw_shapes(frame: &mut Frame, area: Rect, tracking: &ExtTracking, show_all: bool) {
+    let shapes: Vec<_> = tracking
+        .shape_values()
+        .filter(|(_, _, mapped)| show_all || *mapped)
+        .collect();
+
+    let title = format!(
+        " {} shapes ({}), a: {} ",
+        shapes.len(),
+        if show_all { "all" } else { "mapped" },
+        if show_all { "mapped only" } else { "show all" }
+    );
+    let block = Block::bordered().title(title);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    if shapes.is_empty() || inner.height == 0 {
+        return;
+    }
+
+    let columns = (inner.width / COLUMN_WIDTH).max(1) as usize;
+    let rows = shapes.len().div_ceil(columns);
+    let areas =
+        Layout::horizontal(vec![Constraint::Ratio(1, columns as u32); columns]).split(inner);
+    let bar_width = (areas[0].width as usize).saturating_sub(NAME_WIDTH + VALUE_WIDTH + 2);
+
+    for (chunk, area) in shapes.chunks(rows).zip(areas.iter()) {
+        let lines: Vec<Line> = chunk
+            .iter()
+            .map(|(name, value, mapped)| {
+                let filled =
+                    ((value.abs().min(1.) * bar_width as f32).round() as usize).min(bar_width);
+                let color = if *value < 0. {
+                    Color::Red
+                } else {
+                    Color::Green
+                };
+                let name_style = if *mapped {
+                    Style::new()
+                } else {
+                    Style::new().fg(Color::DarkGray)
+                };
+                Line::from(vec![
+                    Span::styled(format!("{:<w$.w$} ", name, w = NAME_WIDTH), name_style),
+                    Span::styled("█".repeat(filled), Style::new().fg(color)),
+                    Span::raw(" ".repeat(bar_width - filled)),
+                    Span::raw(format!(" {:>w$.2}", value, w = VALUE_WIDTH - 1)),
+                ])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), *area);
+    }
+}
+
+/// Draws the newest log lines that fit, or older ones once scrolled up.
+fn draw_log(frame: &mut Frame, area: Rect, log_scroll: &mut usize) {
+    let block = Block::bordered().title(" Log, ↑/↓/PgUp/PgDn: scroll, End: newest, q: quit ");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let lines = LOG_LINES.lock().unwrap();
+    let height = inner.height as usize;
+    // Don't scroll past the oldest line.
+    *log_scroll = (*log_scroll).min(lines.len().saturating_sub(height));
+    let end = lines.len() - *log_scroll;
+    let start = end.saturating_sub(height);
+
+    let visible: Vec<Line> = lines
+        .range(start..end)
+        .map(|line| Line::raw(line.as_str()))
+        .collect();
+    frame.render_widget(Paragraph::new(visible), inner);
+}
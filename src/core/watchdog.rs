@@ -7,18 +7,30 @@ use std::{
     time::Instant,
 };
 
+use crate::WatchdogAction;
+
+/// Watches the main loop for stalls, i.e. no frame being processed within `timeout_ms`.
+///
+/// Frames are either driven by the avatar's `VSync` parameter, or by the internal ~90 Hz timer (self-drive).
+/// Receiving `VSync` switches self-drive off, so by default the watchdog switches it back on when `VSync` stops,
+/// e.g. after changing into an avatar without it. If `VSync` arrives slower than the timeout allows,
+/// both keep switching the mode back and forth, so the timeout must be longer than the `VSync` interval.
 pub struct Watchdog {
     start: Instant,
     self_drive: Arc<AtomicBool>,
     last_received: Arc<AtomicU64>,
+    timeout_ms: u64,
+    action: WatchdogAction,
 }
 
 impl Watchdog {
-    pub fn new(self_drive: Arc<AtomicBool>) -> Self {
+    pub fn new(self_drive: Arc<AtomicBool>, timeout_ms: u64, action: WatchdogAction) -> Self {
         Self {
             start: Instant::now(),
             self_drive,
             last_received: Arc::new(AtomicU64::new(0)),
+            timeout_ms,
+            action,
         }
     }
 
@@ -32,15 +44,46 @@ impl Watchdog {
         let self_drive = self.self_drive.clone();
         let last_received = self.last_received.clone();
         let start = self.start;
+        let timeout_ms = self.timeout_ms;
+        let action = self.action;
 
-        thread::spawn(move || loop {
-            let last_recv_time = last_received.load(std::sync::atomic::Ordering::Relaxed);
+        thread::spawn(move || {
+            // Whether the current stall was already reported, so that it's only logged once.
+            let mut stalled = false;
+            loop {
+                let last_recv_time = last_received.load(std::sync::atomic::Ordering::Relaxed);
 
-            let elapsed = start.elapsed().as_millis() as u64;
-            if elapsed - last_recv_time > 500 {
-                self_drive.store(true, Ordering::Relaxed);
+                let elapsed = start.elapsed().as_millis() as u64;
+                let since = elapsed.saturating_sub(last_recv_time);
+                if since > timeout_ms {
+                    match action {
+                        WatchdogAction::SelfDrive => self_drive.store(true, Ordering::Relaxed),
+                        WatchdogAction::Warn => {
+                            if !stalled {
+                                log::warn!(
+                                    "Watchdog: no frame was processed for {} ms, switching to self-drive.",
+                                    since
+                                );
+                            }
+                            self_drive.store(true, Ordering::Relaxed);
+                        }
+                        WatchdogAction::Exit => {
+                            log::error!(
+                                "Watchdog: no frame was processed for {} ms, exiting.",
+                                since
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                    stalled = true;
+                } else if stalled {
+                    if action == WatchdogAction::Warn {
+                        log::info!("Watchdog: frames are being processed again.");
+                    }
+                    stalled = false;
+                }
+                thread::sleep(sleep_duration);
             }
-            thread::sleep(sleep_duration);
         });
     }
 }
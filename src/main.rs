@@ -1,12 +1,18 @@
 #![allow(dead_code)]
 
-use crate::core::AvatarOsc;
+use crate::core::{tui::TuiLogger, AvatarOsc};
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
 use clap::Parser;
 use env_logger::Env;
 use indicatif::MultiProgress;
 use indicatif_log_bridge::LogWrapper;
 
+mod config;
 mod core;
 
 /// The main entry point of the application.
@@ -23,16 +29,59 @@ fn main() {
     let multi = MultiProgress::new();
     // `LogWrapper` bridges the `log` crate with `indicatif`'s progress bars,
     // ensuring that log messages don't mess up the progress bar display.
-    LogWrapper::new(multi.clone(), log).try_init().unwrap();
+    // `TuiLogger` diverts the messages into the log pane while the `--tui` dashboard is open.
+    let max_level = log.filter();
+    let logger = TuiLogger::new(LogWrapper::new(multi.clone(), log));
+    log::set_boxed_logger(Box::new(logger)).unwrap();
+    log::set_max_level(max_level);
+
+    // Parse command-line arguments using `clap`, with the config file's settings merged in.
+    let args = Args::parse_from(config::args_with_config());
+
+    // The colored status strings are built on first use, so this has to happen before anything else prints them.
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if args.no_color || no_color_env {
+        colored::control::set_override(false);
+    }
 
-    // Parse command-line arguments using `clap`.
-    let args = Args::parse();
+    // Diagnostics that print and exit without starting the main loop.
+    if args.list_extensions {
+        #[cfg(feature = "openxr")]
+        let result = core::list_openxr_extensions();
+        #[cfg(not(feature = "openxr"))]
+        let result: anyhow::Result<()> = Err(anyhow::anyhow!(
+            "--list-extensions needs the \"openxr\" feature."
+        ));
+        if let Err(e) = result {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     // Create a new instance of the main application struct, `AvatarOsc`.
     let mut osc = AvatarOsc::new(args, multi);
 
-    // Start the main message handling loop. This function will run indefinitely.
-    osc.handle_messages();
+    // Stop the main loop on Ctrl-C (SIGINT) or SIGTERM, so that the trackers are torn down properly.
+    // A leaked OpenXR session can keep the runtime from handing out the trackers on the next run.
+    let shutdown = Arc::new(AtomicBool::new(false));
+    if let Err(e) = ctrlc::set_handler({
+        let shutdown = shutdown.clone();
+        move || shutdown.store(true, Ordering::Relaxed)
+    }) {
+        log::warn!("Could not install the shutdown handler: {}", e);
+    }
+
+    // Start the main message handling loop. This function runs until a shutdown is requested.
+    let result = osc.handle_messages(shutdown);
+
+    // Dropping the application tears down the face receivers and their trackers.
+    drop(osc);
+
+    if let Err(e) = result {
+        log::error!("{:#}", e);
+        std::process::exit(1);
+    }
 }
 
 /// Defines the available face tracking setups as subcommands for the command-line interface.
@@ -60,28 +109,640 @@ pub enum FaceSetup {
         /// The port to listen on for Babble and ETVR packets.
         #[arg(short, long, default_value = "9400")]
         listen: u16,
+
+        /// A separate port for Babble (mouth) packets. Defaults to `--listen`.
+        #[arg(long)]
+        face_listen: Option<u16>,
+
+        /// A separate port for ETVR (eye) packets. Defaults to `--listen`.
+        #[arg(long)]
+        eye_listen: Option<u16>,
+    },
+
+    #[cfg(feature = "vmc")]
+    /// Retrieve ARKit-style face data over the VMC protocol.
+    /// This option is only available if the "vmc" feature is enabled during compilation.
+    Vmc {
+        /// The port to listen on for VMC packets.
+        #[arg(short, long, default_value = "39539")]
+        listen: u16,
     },
+
+    /// Read face data from stdin, as one JSON object of unified expression names and values per line.
+    Stdin,
+
+    /// Replay a tracking session recorded with `--record`, at its original pace. Loops at the end.
+    Replay {
+        /// The session log to replay.
+        #[arg(short, long)]
+        file: String,
+    },
+}
+
+/// Defines where the autopilot's jump action is sent to.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum JumpTarget {
+    /// Press the `/input/Jump` button. This is what VRChat expects.
+    #[default]
+    Input,
+    /// Set a bool avatar parameter, for targets that don't expose a jump input.
+    Parameter,
+    /// Don't jump at all.
+    Disabled,
+}
+
+/// Defines what the watchdog does when no frame was processed for `--watchdog-timeout-ms`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum WatchdogAction {
+    /// Switch to self-drive, so that frames keep coming without `VSync`.
+    #[default]
+    SelfDrive,
+    /// Like `self-drive`, but log a warning each time it happens.
+    Warn,
+    /// Log an error and exit with a nonzero code, e.g. to be restarted by a supervisor.
+    Exit,
+}
+
+/// Defines how packets are sent to `--vrc-host`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum UpstreamTransport {
+    /// One packet per UDP datagram. This is what VRChat expects.
+    #[default]
+    Udp,
+    /// SLIP-framed packets over a TCP connection, which is reconnected automatically.
+    /// Avoids dropped packets on congested networks, if the receiver supports it.
+    Tcp,
+}
+
+/// Defines how the status line is shown.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum StatusFormat {
+    /// A colored spinner line in the terminal.
+    #[default]
+    Pretty,
+    /// One JSON object per second on stdout, for log files and supervisors.
+    Json,
+}
+
+/// Defines what face tracking does while the avatar's `FacePause` parameter is set.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FacePauseBehavior {
+    /// Stop sending, leaving the last sent values latched in-game.
+    #[default]
+    Hold,
+    /// Send a neutral face once, then stop sending.
+    Neutral,
+    /// Keep re-sending the frozen values every second, in case the receiver loses its state.
+    Frozen,
+}
+
+/// Defines what OpenXR face tracking does while the FB tracker's confidence is below `--face-confidence-floor`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LowConfidenceBehavior {
+    /// Keep the last frame that was above the floor.
+    #[default]
+    Hold,
+    /// Fade the expressions towards neutral.
+    Neutral,
+}
+
+/// Defines which OpenXR reference space head and hand poses are located in.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReferenceSpace {
+    /// The configured playspace, with the origin on the floor. Falls back to `local` if unavailable.
+    #[default]
+    Stage,
+    /// The seated space, with the origin at the initial head position.
+    Local,
 }
 
 /// Defines the command-line arguments for the OSC Avatar Manager application.
 /// `clap::Parser` automatically generates a command-line parser from this struct.
 #[derive(Default, clap::Parser, Debug)]
-#[command(version, about, long_about = None)]
+// Later occurrences of an argument override earlier ones, so that the command line overrides the config file.
+#[command(version, about, long_about = None, args_override_self = true)]
 pub struct Args {
+    /// A TOML file with default settings, using the same names as the long arguments.
+    /// Arguments given on the command line override the file. Defaults to `$XDG_CONFIG_HOME/oscavmgr.toml`.
+    #[arg(long)]
+    config: Option<String>,
+
     /// Provider to use for face data. This is a subcommand that uses the `FaceSetup` enum.
     #[command(subcommand)]
     face: FaceSetup,
 
+    /// A comma-separated list of face sources (`openxr`, `alvr`, `babble[:port]`, `vmc[:port]`, `stdin`) to fall back to,
+    /// in order, while the primary source provides no face data. Higher-priority sources are switched
+    /// back to as soon as they deliver data again.
+    #[arg(long, value_delimiter = ',')]
+    face_fallback: Vec<String>,
+
+    /// Poll the face source at most this many times per second, reusing the latest data in between.
+    /// 0 polls on every tick of the main loop.
+    #[arg(long, default_value = "0")]
+    face_receive_hz: f32,
+
+    /// Send face data to the avatar at most this many times per second. 0 sends on every tick.
+    #[arg(long, default_value = "0")]
+    face_apply_hz: f32,
+
+    /// An avatar parameter that tells whether anyone is nearby, e.g. a proximity contact. While its value is
+    /// at or below `--solitude-threshold`, face data is only sent at `--solitude-hz` to save bandwidth.
+    #[arg(long)]
+    solitude_param: Option<String>,
+
+    /// The value of `--solitude-param` above which someone counts as nearby.
+    #[arg(long, default_value = "0")]
+    solitude_threshold: f32,
+
+    /// How many times per second face data is sent while nobody is nearby. 0 stops sending until someone approaches.
+    #[arg(long, default_value = "2")]
+    solitude_hz: f32,
+
+    /// How long (in milliseconds) a face source may go without data before the next fallback is used.
+    #[arg(long, default_value = "3000")]
+    face_fallback_after_ms: u64,
+
     /// The OSC port that VRChat (or a similar application) is listening on.
-    #[arg(long, default_value = "9000")]
-    vrc_port: u16,
+    /// A comma-separated list sends to several ports, paired with `--vrc-host` in order.
+    #[arg(long, value_delimiter = ',', default_value = "9000")]
+    vrc_port: Vec<u16>,
+
+    /// The host that VRChat (or a similar application) is running on, as an IPv4/IPv6 address or host name.
+    /// A comma-separated list sends everything to several hosts, e.g. to drive VRChat and Resonite at once.
+    /// If fewer hosts than ports are given (or the other way around), the last one is used for the rest.
+    #[arg(long, value_delimiter = ',', default_value = "127.0.0.1")]
+    vrc_host: Vec<String>,
+
+    /// How packets are sent to the `--vrc-host` targets.
+    #[arg(long, value_enum, default_value_t = UpstreamTransport::Udp)]
+    upstream_transport: UpstreamTransport,
 
     /// The port this application will listen on for incoming OSC messages from VRChat.
     #[arg(long, default_value = "9002")]
     osc_port: u16,
 
+    /// The address to listen on for incoming OSC messages, e.g. `0.0.0.0` (or `::` for IPv6) when VRChat
+    /// runs on another machine. A host name is resolved to its first address.
+    #[arg(long, default_value = "127.0.0.1")]
+    bind: String,
+
+    /// If set, a copy of every OSC packet sent to VRChat is also sent to this local port.
+    /// This is a read-only tap for visualizers and debugging; it doesn't affect the game connection.
+    #[arg(long)]
+    monitor_port: Option<u16>,
+
+    /// If set, every computed face shape is also sent to this local port as `/oscavmgr/debug/<ShapeName>`,
+    /// whether the avatar has a parameter for it or not. Useful for checking the tracker output with external tools.
+    #[arg(long)]
+    debug_osc_port: Option<u16>,
+
+    /// Serve the status line values (tick rate, send/receive rates and whether face, eye and head
+    /// tracking data is present) on this HTTP port at `/metrics`, in the Prometheus text format.
+    /// The server listens on the `--bind` address.
+    #[arg(long)]
+    metrics_port: Option<u16>,
+
+    /// Coerce every outgoing avatar parameter to the exact type (Float, Int or Bool) declared in the
+    /// avatar's OSC JSON, for receivers that reject mistyped parameters. Undeclared parameters are warned about.
+    #[arg(long)]
+    strict_types: bool,
+
+    /// Log every outgoing OSC message instead of sending it to VRChat, to check what an avatar would receive.
+    #[arg(long)]
+    dry_run: bool,
+
     /// An optional path to an OSC-JSON avatar configuration file.
-    /// If not provided, a default path will be used.
+    /// If not provided, a default path will be used. The file is reloaded whenever it changes.
     #[arg(long)]
     avatar: Option<String>,
+
+    /// The base URL of the OSC JSON (OSCQuery) HTTP service, e.g. `http://127.0.0.1:9001`.
+    /// The avatar JSON is fetched from `<url>/avatar`. If set, mDNS discovery results are ignored.
+    #[arg(long)]
+    oscjson_url: Option<String>,
+
+    /// Serve our own OSCQuery service on this local HTTP port, advertised over mDNS, so that
+    /// other tools can discover the face tracking parameters we send.
+    #[arg(long)]
+    oscquery_port: Option<u16>,
+
+    /// Look up avatar UIDs (e.g. from `/avatar/change`) in `$XDG_CONFIG_HOME/avatars/<uid>.json` first,
+    /// and only fall back to the OSCJSON service if the file doesn't exist. Useful for testing avatars offline.
+    #[arg(long)]
+    local_avatars: bool,
+
+    /// The UID of an avatar to load at startup, without waiting for an `/avatar/change` message.
+    /// Best combined with `--oscjson-url`, so that the fetch doesn't have to wait for discovery.
+    /// The service only serves the avatar that is currently worn, so the json is still fetched from `<url>/avatar`,
+    /// and the UID is checked against the avatar id in the response.
+    #[arg(long)]
+    avatar_uid: Option<String>,
+
+    /// Don't start out with the mapping of the last avatar, as cached in `oscavmgr-avatar.json`.
+    /// Until the current avatar is known, the default parameters are used instead.
+    #[arg(long)]
+    no_cache: bool,
+
+    /// The minimum time (in seconds) between two writes of the local `oscavmgr-avatar.json` copy.
+    /// Identical content is never written twice in a row.
+    #[arg(long, default_value = "5")]
+    avatar_json_cooldown: u64,
+
+    /// How long (in milliseconds) to wait before fetching an avatar's json from the OSC JSON service.
+    /// This gives the service time to settle after an avatar change. Set to 0 to fetch right away.
+    #[arg(long, default_value = "250")]
+    oscjson_fetch_delay_ms: u64,
+
+    /// A comma-separated list of tracker names that are treated as the head, e.g. `head,hmd`.
+    #[arg(long, value_delimiter = ',', default_value = "head")]
+    head_tracker: Vec<String>,
+
+    /// A comma-separated list of tracker names that are treated as the left wrist, e.g. `leftwrist,left_wrist`.
+    #[arg(long, value_delimiter = ',', default_value = "leftwrist")]
+    left_wrist_tracker: Vec<String>,
+
+    /// A comma-separated list of tracker names that are treated as the right wrist, e.g. `rightwrist,right_wrist`.
+    #[arg(long, value_delimiter = ',', default_value = "rightwrist")]
+    right_wrist_tracker: Vec<String>,
+
+    /// Send an `FTActive` bool parameter to the avatar, which is true while face data is being received.
+    #[arg(long)]
+    emit_face_active: bool,
+
+    /// Take in eye data that another app sends in VRChat's native `/tracking/eye/*` format (to our OSC port),
+    /// overriding the eyes of the face source. Useful to combine an external eye tracker with face tracking.
+    #[arg(long)]
+    ingest_eye_osc: bool,
+
+    /// Send a `TrackingActive` bool parameter to the avatar, which is true while head & wrist data is being received.
+    #[arg(long)]
+    emit_tracking_active: bool,
+
+    /// The longest frame time (in seconds) that time-aware features such as smoothing, relaxing and holding
+    /// get to see. This bounds how far they can move in a single frame after a hitch. 0 disables the clamp.
+    #[arg(long, default_value = "0.1")]
+    max_delta_t: f32,
+
+    /// Record a neutral face baseline for a few seconds after startup, which is then subtracted from the face data.
+    /// The baseline is saved and used on later starts. Setting the `FTCalibrate` avatar parameter records a new one.
+    #[arg(long)]
+    calibrate: bool,
+
+    /// How fast (per second) the face relaxes towards neutral once face data is lost. 0 keeps the last expression.
+    #[arg(long, default_value = "0")]
+    relax_rate: f32,
+
+    /// How fast (per second) combined expressions (smile, etc.) relax towards neutral once face data is lost.
+    /// Defaults to `--relax-rate`.
+    #[arg(long)]
+    relax_combined_rate: Option<f32>,
+
+    /// How long (in milliseconds) face data must be missing before the face starts relaxing.
+    #[arg(long, default_value = "1000")]
+    relax_after_ms: u64,
+
+    /// The address prefix (relative to `/avatar/parameters/`) of the default face tracking parameters,
+    /// used until the avatar's parameters are known from OSC JSON.
+    #[arg(long, default_value = "FT/v2/")]
+    ft_prefix: String,
+
+    /// Don't send the default face tracking parameters under `--ft-prefix`, only those found in the avatar's OSC JSON.
+    /// Avoids collisions with unrelated parameters of the same name on avatars without face tracking.
+    #[arg(long)]
+    no_default_params: bool,
+
+    /// A comma-separated list of expression names (e.g. `JawOpen,MouthClosed`) that are never sent,
+    /// neither by default nor when found in the avatar's OSC JSON.
+    #[arg(long, value_delimiter = ',')]
+    suppress_params: Vec<String>,
+
+    /// The fraction (0-1) of nose sneer to add to the upper lip, for avatars without nose shapes.
+    /// Only applies if the avatar has no nose sneer parameters. 0 disables it.
+    #[arg(long, default_value = "0")]
+    nose_sneer_fold: f32,
+
+    /// A master multiplier for all non-eye expressions. Above 1.0 exaggerates, below 1.0 subdues.
+    #[arg(long, default_value = "1.0")]
+    expression_intensity: f32,
+
+    /// Also apply `--expression-intensity` to combined expressions (smile, brow expression, etc.).
+    #[arg(long)]
+    expression_intensity_combined: bool,
+
+    /// A JSON file with per-shape output transforms, keyed by unified or combined expression name.
+    /// Each entry has an optional `gain`, `exponent`, `min` and `max`, e.g. `{ "JawOpen": { "max": 0.8 } }`.
+    #[arg(long)]
+    shape_curves: Option<String>,
+
+    /// The maximum width (in characters) of the status line. Less important items are dropped to fit.
+    /// 0 for unlimited.
+    #[arg(long, default_value = "120")]
+    status_max_width: usize,
+
+    /// The maximum number of items on the status line. 0 for unlimited.
+    #[arg(long, default_value = "0")]
+    status_max_items: usize,
+
+    /// How long (in milliseconds) the main loop may go without a frame before the watchdog acts.
+    /// This must be longer than the interval of the avatar's `VSync` parameter, or the watchdog keeps
+    /// switching to self-drive in between `VSync` frames, and the two fight over the main loop.
+    #[arg(long, default_value = "500")]
+    watchdog_timeout_ms: u64,
+
+    /// What the watchdog does when the main loop stalls.
+    #[arg(long, value_enum, default_value_t = WatchdogAction::SelfDrive)]
+    watchdog_action: WatchdogAction,
+
+    /// Print the status line and instructions without colors. Also enabled by a non-empty `NO_COLOR` variable.
+    #[arg(long)]
+    no_color: bool,
+
+    /// Print which OpenXR face tracking extensions the runtime exposes and what it reports it supports, then exit.
+    #[arg(long)]
+    list_extensions: bool,
+
+    /// Show a full-screen dashboard with a live bar per face shape and a scrollable log, instead of the status line.
+    /// Useful for checking which shapes are mapped and which are stuck. Press `q` to quit.
+    #[arg(long)]
+    tui: bool,
+
+    /// How the status line is shown. `json` prints the rates and module states as JSON lines instead of the spinner.
+    #[arg(long, value_enum, default_value_t = StatusFormat::Pretty)]
+    status_format: StatusFormat,
+
+    /// A JSON file with overrides for the FB (Quest Pro) → unified expression mapping.
+    /// Each entry maps a `source` FB blendshape (name or index) to a unified `target` with an optional `scale`.
+    #[arg(long)]
+    fb_mapping: Option<String>,
+
+    /// A JSON file with custom formulas for the combined expressions (e.g. `MouthSmileLeft`),
+    /// keyed by combined expression name, with the weight of each unified expression input.
+    #[arg(long)]
+    combined_weights: Option<String>,
+
+    /// OpenXR only: zero the mouth or eye/brow shapes while the tracker's confidence for that
+    /// half of the face is below this value (0-1), instead of trusting occluded data.
+    #[arg(long)]
+    region_confidence_gate: Option<f32>,
+
+    /// OpenXR only: treat the face as lost while both of the tracker's confidences (upper and lower face)
+    /// are below this value (0-1), rather than sending whatever it reports after losing the face.
+    #[arg(long)]
+    face_confidence_floor: Option<f32>,
+
+    /// OpenXR only: what happens to the face while it is below `--face-confidence-floor`.
+    #[arg(long, value_enum, default_value_t = LowConfidenceBehavior::Hold)]
+    face_confidence_behavior: LowConfidenceBehavior,
+
+    /// OpenXR only: append the raw FB/HTC weight arrays of every frame to this CSV file, for offline analysis.
+    /// Each line is `<unix time>,<source>,<values...>`, where source is `fb` (70 weights, then the lower and
+    /// upper face confidences), `htc_eye` or `htc_lip`.
+    #[arg(long)]
+    dump_raw_face: Option<String>,
+
+    /// Record the raw face, eye, head and wrist tracking data of every frame to this file,
+    /// so that the session can be replayed later with the `replay` face setup.
+    #[arg(long)]
+    record: Option<String>,
+
+    /// Append the processed face shapes of every frame to this CSV file, one column per shape,
+    /// e.g. for studying expression ranges in a spreadsheet. Unlike `--record`, it can't be replayed.
+    #[arg(long)]
+    csv_log: Option<String>,
+
+    /// OpenXR only: the reference space used for head and hand tracking.
+    /// Use `local` on seated or quick-setup runtimes without a configured playspace.
+    #[arg(long, value_enum, default_value_t = ReferenceSpace::Stage)]
+    reference_space: ReferenceSpace,
+
+    /// OpenXR, and ALVR without face data: eyes start closing once they look down further than this many degrees.
+    /// Raise it if your eyes read as half-closed while looking straight ahead.
+    #[arg(long, default_value = "5")]
+    blink_pitch_offset: f32,
+
+    /// OpenXR, and ALVR without face data: how many degrees further down the eyes are fully closed.
+    #[arg(long, default_value = "55")]
+    blink_pitch_range: f32,
+
+    /// OpenXR only: an eye rotation faster than this many degrees per frame is treated as a blink.
+    #[arg(long, default_value = "10")]
+    blink_velocity_deg: f32,
+
+    /// OpenXR only: how many frames the eyes are forced closed after a detected blink.
+    #[arg(long, default_value = "5")]
+    blink_hold_frames: u32,
+
+    /// OpenXR only: how much (0-1) of the FB per-eye look shapes is blended into the eye gaze pose,
+    /// which helps when the pose drifts during blinks. 0 disables the look shapes' contribution, 1 uses them only.
+    #[arg(long, default_value = "0")]
+    fb_gaze_weight: f32,
+
+    /// OpenXR only: how long (in milliseconds) the headset position must be lost before the avatar's eyes
+    /// are closed, so that momentary tracking hiccups don't look like blinks.
+    #[arg(long, default_value = "150")]
+    tracking_loss_grace_ms: u64,
+
+    /// Hold incoming parameter updates for this many milliseconds and spread out bursts,
+    /// for smoother avatar motion on bursty networks. 0 applies updates instantly.
+    #[arg(long, default_value_t = 0)]
+    param_jitter_ms: u64,
+
+    /// A comma-separated list of `Shape:threshold:Param` bindings. When the shape rises above the threshold,
+    /// the bool parameter is pulsed `true` and then `false`, e.g. to trigger world effects or haptics.
+    #[arg(long, value_delimiter = ',')]
+    expression_pulse: Vec<String>,
+
+    /// How long (in milliseconds) an expression pulse stays `true`.
+    #[arg(long, default_value = "100")]
+    expression_pulse_ms: u64,
+
+    /// Periodically show the tracking state in the VRChat chatbox, so that others can see it.
+    #[arg(long)]
+    chatbox_status: bool,
+
+    /// The chatbox text for `--chatbox-status`. `{face}`, `{gaze}`, `{track}` etc. are replaced with
+    /// `on`/`off`, using the lowercase names of the status line items.
+    #[arg(long, default_value = "FT: {face} | Gaze: {gaze}")]
+    chatbox_template: String,
+
+    /// How often (in seconds) the chatbox text is checked for changes. It's only sent when it changed.
+    #[arg(long, default_value_t = 5.)]
+    chatbox_interval: f32,
+
+    /// A comma-separated list of shapes (e.g. `JawOpen,MouthClosed`) to apply the expression hold to.
+    /// A held shape must stay above/below the threshold for a while before the output follows,
+    /// which removes chatter from shapes that flicker between two values.
+    #[arg(long, value_delimiter = ',')]
+    expression_hold: Vec<String>,
+
+    /// How long (in milliseconds) a held shape must stay across the threshold before the output follows.
+    #[arg(long, default_value = "100")]
+    expression_hold_ms: u64,
+
+    /// The threshold that separates the two sides of a held shape.
+    #[arg(long, default_value = "0.5")]
+    expression_hold_threshold: f32,
+
+    /// The minimum cutoff frequency (in Hz) of the one-euro filter that smooths the face shapes.
+    /// Lower values smooth more, at the cost of lag on slow movements. If not set, shapes are not smoothed.
+    #[arg(long)]
+    smoothing_mincutoff: Option<f32>,
+
+    /// How much the one-euro filter's cutoff rises with movement speed. Higher values reduce lag on fast movements.
+    #[arg(long, default_value = "0.1")]
+    smoothing_beta: f32,
+
+    /// How much (0-1) of the previous value each face parameter keeps when it's sent, after all other processing.
+    /// Mostly useful for bit-packed parameters, which otherwise visibly step between values. 0 disables it.
+    #[arg(long, default_value = "0")]
+    param_smoothing: f32,
+
+    /// How far (0-1) a float face parameter must move before it's re-sent. Larger values save bandwidth
+    /// at the cost of precision.
+    #[arg(long, default_value = "0.01")]
+    param_deadband: f32,
+
+    /// How far (in quantization steps) a bit-packed face parameter must move past its last sent step before
+    /// its bits are re-sent. Stops values that hover around a step edge from toggling bits every frame.
+    /// 0 re-sends on every step change.
+    #[arg(long, default_value = "0")]
+    param_bit_hysteresis: f32,
+
+    /// A hysteresis band (0-1) for the `JawOpen` / `MouthClosed` pair. Both stay put until either moves
+    /// further than this, which stops the jaw from chattering when the two fight at the boundary. 0 disables it.
+    #[arg(long, default_value = "0")]
+    jaw_hysteresis: f32,
+
+    /// The minimum position change (in meters) before a forwarded tracker is sent again.
+    #[arg(long, default_value = "0.001")]
+    tracker_pos_epsilon: f32,
+
+    /// The minimum rotation change (in degrees) before a forwarded tracker is sent again.
+    #[arg(long, default_value = "0.1")]
+    tracker_rot_epsilon: f32,
+
+    /// How often (in milliseconds) an unchanged tracker is re-sent anyway, to recover from packet loss.
+    #[arg(long, default_value = "1000")]
+    tracker_keepalive_ms: u64,
+
+    /// Forward the head and wrist poses of the face receiver (e.g. OpenXR) as `/tracking/trackers/*`.
+    /// Trackers received over OSC are not forwarded back.
+    #[arg(long)]
+    forward_trackers: bool,
+
+    /// What face tracking does while the avatar's `FacePause` parameter is set.
+    #[arg(long, value_enum, default_value_t = FacePauseBehavior::Hold)]
+    facepause_behavior: FacePauseBehavior,
+
+    /// How long (in milliseconds) the face takes to fade to neutral while tracking is paused by `AFK`, `FaceFreeze`
+    /// or `Motion`, and by `FacePause` with `--facepause-behavior hold`. It fades back in once tracking resumes.
+    /// 0 keeps the hard freeze on the last frame.
+    #[arg(long, default_value = "0")]
+    pause_ramp_ms: u64,
+
+    /// The OSC address that haptic pulses are sent to, e.g. when the avatar flight jumps.
+    /// The pulse's duration (s), frequency (Hz) and amplitude (0-1) are sent as float arguments.
+    #[arg(long, default_value = "/input/Haptic")]
+    haptic_address: String,
+
+    /// Enable the avatar flight: while `VRCEmote` is 120-124 (see `--flight-emote-min`) and both hands are above
+    /// the head, the avatar jumps repeatedly. Turn this off if an avatar or world uses those emotes for something else.
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    enable_flight: bool,
+
+    /// The lowest `VRCEmote` value that starts the avatar flight.
+    #[arg(long, default_value = "120")]
+    flight_emote_min: i32,
+
+    /// The highest `VRCEmote` value that starts the avatar flight.
+    #[arg(long, default_value = "124")]
+    flight_emote_max: i32,
+
+    /// The highest jump power of the avatar flight: how far (in meters) above the head the hands still
+    /// make a difference. It also raises the strength of the haptic pulse.
+    #[arg(long, default_value = "0.3")]
+    flight_max_power: f32,
+
+    /// The frames between two flight jumps with the hands just above the head.
+    #[arg(long, default_value = "30")]
+    flight_cooldown_frames: f32,
+
+    /// How many frames shorter the cooldown between flight jumps gets per meter of power,
+    /// so that raising the hands further flaps faster. Lower it for tall avatars.
+    #[arg(long, default_value = "100")]
+    flight_cooldown_per_power: f32,
+
+    /// A bool parameter that must also be `true` for the avatar flight to trigger.
+    #[arg(long)]
+    flight_confirm_param: Option<String>,
+
+    /// Smoothing (0-1) of the hand heights that the avatar flight compares against the head.
+    /// Higher values ignore more jitter, but react later. 0 uses the raw hand positions.
+    #[arg(long, default_value = "0.5")]
+    flight_smoothing: f32,
+
+    /// Smoothing (0-1) of the head pose used by the avatar flight and `--forward-trackers`.
+    /// Higher values hide more jitter (e.g. prediction overshoot), but lag behind. 0 uses the raw pose.
+    #[arg(long, default_value = "0")]
+    head_smoothing: f32,
+
+    /// Where the autopilot sends its jump action to.
+    #[arg(long, value_enum, default_value_t = JumpTarget::Input)]
+    jump_target: JumpTarget,
+
+    /// The bool parameter that is set when `--jump-target parameter` is used.
+    #[arg(long, default_value = "Jump")]
+    jump_param: String,
+
+    /// Invert the autopilot's forward/backward (`Vertical`) movement.
+    #[arg(long)]
+    invert_forward: bool,
+
+    /// Invert the autopilot's left/right (`Horizontal`) movement.
+    #[arg(long)]
+    invert_strafe: bool,
+
+    /// Invert the autopilot's turning (`LookHorizontal`).
+    #[arg(long)]
+    invert_look: bool,
+
+    /// Autopilot follow mode: how far (in meters) a grabbed seeker must be before walking towards it.
+    #[arg(long, default_value = "0.1")]
+    autopilot_move_threshold: f32,
+
+    /// Autopilot follow mode: the distance (in meters) at which walking reaches full speed.
+    /// With `Tracker1_Enable`, the target must also be this far before moving at all.
+    #[arg(long, default_value = "0.5")]
+    autopilot_run_threshold: f32,
+
+    /// Autopilot manual mode: the cheek puff (forward) or cheek suck (backward), summed over
+    /// both cheeks (0-2), needed to start moving. Lower it for trackers that read cheeks weakly.
+    #[arg(long, default_value = "0.5")]
+    autopilot_cheek_trigger: f32,
+
+    /// Autopilot manual mode: the factor from summed cheek puff/suck to movement speed.
+    #[arg(long, default_value = "0.6")]
+    autopilot_cheek_scale: f32,
+
+    /// Autopilot manual mode: the raised brows, summed over all four brow shapes (0-4),
+    /// needed to press the `Voice` button.
+    #[arg(long, default_value = "3.0")]
+    autopilot_brow_trigger: f32,
+
+    /// Autopilot manual mode: the summed brows must drop below this before `Voice` is released.
+    #[arg(long, default_value = "2.0")]
+    autopilot_brow_release: f32,
+
+    /// Autopilot manual mode: how far (in radians) the eyes must turn to either side to look left/right.
+    #[arg(long, default_value = "0.5")]
+    autopilot_look_trigger: f32,
+
+    /// The maximum eye rotation (in radians) on each axis. Larger rotations, e.g. from tracker glitches, are clamped.
+    #[arg(long, default_value = "1.0")]
+    gaze_range: f32,
+
+    /// Eye rotations (in radians) closer to the center than this are snapped to the center. 0 disables it.
+    #[arg(long, default_value = "0")]
+    gaze_deadzone: f32,
 }